@@ -1,18 +1,23 @@
-use std::{num::Saturating, sync::Arc};
+use std::{num::Saturating, sync::Arc, time::Duration};
 
 use bytemuck::Zeroable;
 
 use crate::{
-    profile::Profile, OusterPacket, PacketHeader, PointInfo, PointInfos, PrimaryPointInfo,
-    ValidOperationConfig, ValidWindow,
+    packet::ColumnHeader, profile::Profile, CartesianIterator, OusterPacket, PointInfo, PointInfos,
+    PrimaryPointInfo, ReturnPoint, ValidOperationConfig, ValidOusterConfig, ValidWindow,
 };
 
+/// Packets of the next frame, which are awaited before a frame is completed, so out of order packets are still assigned
+const COMPLETION_DELAY_PACKETS: usize = 10;
+
 #[derive(Clone)]
 struct AggregatorEntry<TProfile: Profile> {
     frame_id: u16,
     complete_buf: Box<[Box<OusterPacket<TProfile>>]>,
     missing_packet_histogram: u128,
     count_packets: usize,
+    /// Timestamp of the first column of the window, derived from the first received packet
+    window_start: Option<Duration>,
 }
 
 impl<TProfile: Profile> AggregatorEntry<TProfile> {
@@ -25,6 +30,7 @@ impl<TProfile: Profile> AggregatorEntry<TProfile> {
                 .collect::<Box<_>>(),
             missing_packet_histogram: 0,
             count_packets: Default::default(),
+            window_start: None,
         }
     }
 }
@@ -40,6 +46,17 @@ pub struct Aggregator<TProfile: Profile> {
     completion_historgram: Vec<Saturating<u32>>,
     missing_packets: Vec<Saturating<u32>>,
     dropped_packets: Saturating<u32>,
+    timing: Option<FrameTiming>,
+    received_packets: u64,
+    /// First and last window start of the received packets
+    received_span: Option<(Duration, Duration)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FrameTiming {
+    frame_period: Duration,
+    column_period: Duration,
+    expected_packets_per_second: f32,
 }
 
 #[derive(Debug)]
@@ -47,6 +64,10 @@ pub struct AggregatorStatistics {
     pub completion_historgram: Vec<u32>,
     pub dropped_frames: u32,
     pub missing_packets: Vec<u32>,
+    /// Packets of the column window per second according to the lidar mode, if the aggregator knows the config
+    pub expected_packets_per_second: Option<f32>,
+    /// Packets of the column window per second, measured by their timestamps if the aggregator knows the config
+    pub received_packets_per_second: Option<f32>,
 }
 
 impl<TProfile: Profile> Aggregator<TProfile> {
@@ -65,6 +86,23 @@ impl<TProfile: Profile> Aggregator<TProfile> {
             completion_historgram: vec![Saturating(0); required_measurements + 2],
             missing_packets: vec![Saturating(0); required_measurements],
             dropped_packets: Saturating(0),
+            timing: None,
+            received_packets: 0,
+            received_span: None,
+        }
+    }
+
+    /// Frames are completed once the timestamps of the next frame exceed the frame period of the lidar mode,
+    /// even if less packets than the completion delay arrive, like for small column windows
+    pub fn from_config(config: &ValidOusterConfig<TProfile>) -> Self {
+        let lidar_mode = config.config_params.lidar_mode;
+        Self {
+            timing: Some(FrameTiming {
+                frame_period: lidar_mode.frame_period(),
+                column_period: config.measurement_time_offset(1),
+                expected_packets_per_second: config.expected_packets_per_second(),
+            }),
+            ..Self::new(&config.lidar_data_format.column_window)
         }
     }
 
@@ -87,6 +125,13 @@ impl<TProfile: Profile> Aggregator<TProfile> {
             completion_historgram: self.get_histogram(),
             dropped_frames: self.dropped_packets.0,
             missing_packets: self.missing_packets.iter().map(|x| x.0).collect::<Vec<_>>(),
+            expected_packets_per_second: self.timing.map(|x| x.expected_packets_per_second),
+            received_packets_per_second: self.timing.zip(self.received_span).map(
+                |(timing, (first, last))| {
+                    let duration = last - first + timing.frame_period;
+                    self.received_packets as f32 / duration.as_secs_f32()
+                },
+            ),
         }
     }
 
@@ -118,69 +163,113 @@ impl<TProfile: Profile> Aggregator<TProfile> {
 
     pub fn process_tmp(&mut self) -> Option<CompleteData<TProfile>> {
         let idx = {
-            let pos = self.tmp.columns.as_ref()[0].channels_header.measurement_id
-                / TProfile::COLUMNS as u16;
-            let idx = if pos < self.start_measurement_id {
+            let pos = self.tmp.measurement_id() / TProfile::COLUMNS as u16;
+            if pos < self.start_measurement_id {
                 pos + self.total_measurements_per_frame - self.start_measurement_id
             } else {
                 pos - self.start_measurement_id
-            };
-            idx
+            }
         } as usize;
 
         if idx >= self.entry_active.complete_buf.len() {
             return None;
         }
 
-        if self.entry_active.frame_id == self.tmp.header.frame_id() {
+        let timestamp = self.tmp.columns.as_ref()[0].channels_header.timestamp();
+        let window_start = self.timing.and_then(|timing| {
+            let offset = timing.column_period * (idx * TProfile::COLUMNS) as u32;
+            (!timestamp.is_zero())
+                .then(|| timestamp.checked_sub(offset))
+                .flatten()
+        });
+        self.received_packets += 1;
+        if let Some(start) = window_start {
+            self.received_span =
+                Some(self.received_span.map_or((start, start), |(first, last)| {
+                    (first.min(start), last.max(start))
+                }));
+        }
+
+        if self.entry_active.frame_id == self.tmp.frame_id() {
             std::mem::swap(&mut self.entry_active.complete_buf[idx], &mut self.tmp);
             self.entry_active.count_packets += 1;
             self.entry_active.missing_packet_histogram |= 1 << idx;
+            self.entry_active.window_start = self.entry_active.window_start.or(window_start);
             None
-        } else if self.entry_other.frame_id != self.tmp.header.frame_id() {
-            self.entry_other.frame_id = self.tmp.header.frame_id();
+        } else if self.entry_other.frame_id != self.tmp.frame_id() {
+            self.entry_other.frame_id = self.tmp.frame_id();
             std::mem::swap(&mut self.entry_other.complete_buf[idx], &mut self.tmp);
             self.dropped_packets += self.entry_other.count_packets as u32;
             self.entry_other.count_packets = 1;
             self.entry_other.missing_packet_histogram = 1 << idx;
+            self.entry_other.window_start = window_start;
             None
         } else {
             self.entry_other.missing_packet_histogram |= 1 << idx;
             self.entry_other.count_packets += 1;
+            self.entry_other.window_start = self.entry_other.window_start.or(window_start);
             std::mem::swap(&mut self.entry_other.complete_buf[idx], &mut self.tmp);
             // Finish delayed so out of order UDP Packets are still assigned
-            if self.entry_other.count_packets == 10 {
-                // Always output for now
-                let out = Arc::make_mut(&mut self.entry_out);
-                out.count_packets = 0;
-                out.missing_packet_histogram = 0;
-
-                std::mem::swap(out, &mut self.entry_active);
-                std::mem::swap(&mut self.entry_active, &mut self.entry_other);
-
-                // Statistics
-                let result = if out.count_packets != 0 {
-                    let last_index = self.completion_historgram.len() - 1;
-                    self.completion_historgram[out.count_packets.min(last_index)] += 1;
-
-                    let mut hist = out.missing_packet_histogram;
-                    for x in 0..(self.captured_cols_per_rotation / TProfile::COLUMNS) {
-                        if hist & 1 == 0 {
-                            *out.complete_buf[x] = OusterPacket::zeroed();
-                            self.missing_packets[x] += 1;
-                        }
-                        hist >>= 1;
-                    }
-                    Some(CompleteData(self.entry_out.clone()))
-                } else {
-                    None
-                };
+            if self.entry_other.count_packets == COMPLETION_DELAY_PACKETS
+                || self.is_overdue(timestamp)
+            {
+                return self.complete_active();
+            }
+            None
+        }
+    }
 
-                return result;
+    /// Completes the pending frames, for example at the end of a recording, where no next frame follows
+    /// Call it until it returns None, the frames are returned oldest first
+    pub fn flush(&mut self) -> Option<CompleteData<TProfile>> {
+        while self.entry_active.count_packets != 0 || self.entry_other.count_packets != 0 {
+            if let Some(frame) = self.complete_active() {
+                return Some(frame);
             }
+        }
+        None
+    }
+
+    /// Outputs the active frame and activates the other one
+    fn complete_active(&mut self) -> Option<CompleteData<TProfile>> {
+        // Always output for now
+        let out = Arc::make_mut(&mut self.entry_out);
+        out.count_packets = 0;
+        out.missing_packet_histogram = 0;
+        out.window_start = None;
+
+        std::mem::swap(out, &mut self.entry_active);
+        std::mem::swap(&mut self.entry_active, &mut self.entry_other);
+
+        // Statistics
+        if out.count_packets != 0 {
+            let last_index = self.completion_historgram.len() - 1;
+            self.completion_historgram[out.count_packets.min(last_index)] += 1;
+
+            let mut hist = out.missing_packet_histogram;
+            for x in 0..(self.captured_cols_per_rotation / TProfile::COLUMNS) {
+                if hist & 1 == 0 {
+                    *out.complete_buf[x] = OusterPacket::zeroed();
+                    self.missing_packets[x] += 1;
+                }
+                hist >>= 1;
+            }
+            Some(CompleteData(self.entry_out.clone()))
+        } else {
             None
         }
     }
+
+    /// The timestamp is past the completion delay after the window of the active frame would have been sent again
+    fn is_overdue(&self, timestamp: Duration) -> bool {
+        let (Some(timing), Some(window_start)) = (self.timing, self.entry_active.window_start)
+        else {
+            return false;
+        };
+        let delay_packets = COMPLETION_DELAY_PACKETS.min(self.entry_active.complete_buf.len()) - 1;
+        let delay = timing.column_period * (delay_packets * TProfile::COLUMNS) as u32;
+        timestamp >= window_start + timing.frame_period + delay
+    }
 }
 
 pub struct CompleteData<TProfile: Profile>(Arc<AggregatorEntry<TProfile>>);
@@ -194,10 +283,59 @@ impl<TProfile: Profile> CompleteData<TProfile> {
         self.0.count_packets == 0
     }
 
+    pub fn frame_id(&self) -> u16 {
+        self.0.frame_id
+    }
+
+    /// Header of every column within the window, zeroed for missing packets
+    pub fn column_headers(&self) -> impl Iterator<Item = &TProfile::ChannelsHeader> {
+        self.iter()
+            .flat_map(|lidar_packet| lidar_packet.columns.as_ref().iter())
+            .map(|column| &column.channels_header)
+    }
+
+    /// Timestamp of the first received column, zero if no column has a timestamp
+    pub fn timestamp(&self) -> Duration {
+        self.column_headers()
+            .map(ColumnHeader::timestamp)
+            .find(|ts| !ts.is_zero())
+            .unwrap_or_default()
+    }
+
     pub fn len(&self) -> usize {
         self.0.count_packets
     }
 
+    /// Timestamp of every column within the window, used for deskewing
+    /// Columns of missing packets get the timestamp the sensor would have sent according to the lidar mode
+    pub fn column_timestamps(&self, config: &ValidOusterConfig<TProfile>) -> Vec<Duration> {
+        let timestamps = self
+            .column_headers()
+            .map(ColumnHeader::timestamp)
+            .collect::<Vec<_>>();
+        let Some((reference, reference_timestamp)) = timestamps
+            .iter()
+            .enumerate()
+            .find(|(_, ts)| !ts.is_zero())
+            .map(|(i, ts)| (i, *ts))
+        else {
+            return timestamps;
+        };
+        let reference_offset = config.measurement_time_offset(reference as u16);
+        timestamps
+            .iter()
+            .enumerate()
+            .map(|(i, ts)| {
+                if ts.is_zero() {
+                    (reference_timestamp + config.measurement_time_offset(i as u16))
+                        .saturating_sub(reference_offset)
+                } else {
+                    *ts
+                }
+            })
+            .collect()
+    }
+
     pub fn statistics(&self) -> u128 {
         self.0.missing_packet_histogram
     }
@@ -206,7 +344,7 @@ impl<TProfile: Profile> CompleteData<TProfile> {
         &'a self,
         config: &ValidOperationConfig<TProfile>,
         mut map: impl FnMut(&<TProfile as Profile>::Channel, u32) -> T + 'a,
-    ) -> impl Iterator<Item = T> + '_ {
+    ) -> impl Iterator<Item = T> + 'a {
         let n_vec = config.n_vec();
         self.iter()
             .flat_map(|lidar_packet| lidar_packet.columns.as_ref().iter())
@@ -229,6 +367,36 @@ impl<TProfile: Profile> CompleteData<TProfile> {
         self.iter_flat(config, |point, nvec| point.get_primary_infos(nvec))
     }
 
+    /// Emits every return of every pixel together with its cartesian coordinates.
+    /// Primary returns are always emitted, while secondary returns without a measurement (distance 0) are skipped.
+    /// The CartesianIterator has to be created from the same config
+    pub fn iter_returns<'a, TSlice>(
+        &'a self,
+        config: &ValidOperationConfig<TProfile>,
+        cartesian: CartesianIterator<TProfile, TSlice>,
+    ) -> impl Iterator<Item = ReturnPoint<<TProfile::Channel as PointInfos>::Signal>> + 'a
+    where
+        TSlice: AsRef<[(f32, f32)]> + 'a,
+    {
+        self.iter_infos(config)
+            .zip(cartesian)
+            .flat_map(|(info, polar_point)| {
+                let nir = info.nir;
+                info.channel_info
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(return_index, channel)| *return_index == 0 || channel.distance != 0)
+                    .map(move |(return_index, channel)| ReturnPoint {
+                        xyz: polar_point.calc_xyz(channel.distance as f32),
+                        distance: channel.distance,
+                        reflectifity: channel.reflectifity,
+                        signal: channel.signal,
+                        nir,
+                        return_index: return_index as u8,
+                    })
+            })
+    }
+
     // get_unchecked() didn't improve performance
     // the compiler optimized it out during inline. inline(always) makes sure optimization can be made
     #[inline(always)]
@@ -249,7 +417,12 @@ impl<TProfile: Profile> CompleteData<TProfile> {
 
 #[cfg(test)]
 mod tests {
-    use crate::{Dual64OusterPacket, DualProfile, Profile, ValidWindow};
+    use std::time::Duration;
+
+    use crate::{
+        test_utils::{complete_frame, test_config, test_ouster_config},
+        CartesianIterator, Dual64OusterPacket, DualProfile, LidarProfile, Profile, ValidWindow,
+    };
 
     use super::Aggregator;
 
@@ -340,4 +513,85 @@ mod tests {
         assert_eq!(0, hist[0], "{:?}", hist);
         assert_eq!(2, hist[64], "{:?}", hist);
     }
+
+    #[test]
+    fn timestamps_complete_small_windows() {
+        let mut config =
+            test_ouster_config::<DualProfile<16, 64>>(LidarProfile::DualReturn, &[0; 64]);
+        // 4 packets are less than the completion delay
+        config.valid_operation.lidar_data_format.column_window = ValidWindow::new((0, 63), 1024);
+        let frame_period = config.config_params.lidar_mode.frame_period();
+        let timestamp = |frame_id: u16, measurement_id: u16| {
+            Duration::from_secs(1)
+                + frame_period * frame_id as u32
+                + config.measurement_time_offset(measurement_id)
+        };
+        let packets = [(0, 0), (0, 32), (0, 48), (1, 0), (1, 16), (1, 32), (1, 48)].map(
+            |(frame_id, measurement_id)| {
+                let mut x = Dual64OusterPacket::default();
+                x.header.frame_id = frame_id;
+                for (column, id) in x.columns.iter_mut().zip(measurement_id..) {
+                    column.channels_header.measurement_id = id;
+                    column
+                        .channels_header
+                        .set_timestamp(timestamp(frame_id, id));
+                }
+                x
+            },
+        );
+
+        let mut without_config = Aggregator::new(&config.lidar_data_format.column_window);
+        assert!(packets
+            .iter()
+            .all(|x| without_config.put_data_value(x.clone()).is_none()));
+        assert_eq!(
+            None,
+            without_config.get_statistics().expected_packets_per_second
+        );
+
+        let mut aggregator = Aggregator::from_config(&config);
+        let frames = packets
+            .into_iter()
+            .filter_map(|x| aggregator.put_data_value(x))
+            .collect::<Vec<_>>();
+        assert_eq!(1, frames.len());
+        // Packet 1 is missing
+        assert_eq!(3, frames[0].len());
+        assert_eq!(
+            (0..64).map(|i| timestamp(0, i)).collect::<Vec<_>>(),
+            frames[0].column_timestamps(&config)
+        );
+        let statistics = aggregator.get_statistics();
+        assert_eq!(Some(40.), statistics.expected_packets_per_second);
+        assert_eq!(Some(35.), statistics.received_packets_per_second);
+    }
+
+    #[test]
+    fn iter_returns_skips_empty_second_returns() {
+        let config = test_config::<DualProfile<16, 64>>(LidarProfile::DualReturn, &[0; 64]);
+        let pc = complete_frame(&config, |frame_id, measurement_id| {
+            let mut x = Dual64OusterPacket::default();
+            x.header.frame_id = frame_id;
+            x.columns[0].channels_header.measurement_id = measurement_id;
+            for col in x.columns.iter_mut() {
+                for ch in col.channels.iter_mut() {
+                    ch.info_ret1.raw = 1000;
+                }
+                col.channels[0].info_ret2.raw = 2000;
+            }
+            x
+        });
+
+        let cartesian = CartesianIterator::new_cheap_cloneable_from_config(&config);
+        let points = pc.iter_returns(&config, cartesian).collect::<Vec<_>>();
+        assert_eq!(1024 * 64 + 1024, points.len());
+        assert_eq!(
+            1024,
+            points
+                .iter()
+                .filter(|p| p.return_index == 1 && p.distance == 2000)
+                .count()
+        );
+        assert!(points[..2].iter().map(|p| p.return_index).eq([0, 1]));
+    }
 }
@@ -0,0 +1,403 @@
+//! Inspection and conversion of sensor metadata and pcap recordings
+
+use std::{
+    error::Error,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Read},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use ouster_rs_ce::{
+    write_pcd, write_ply, Aggregator, CartesianIterator, ChanField, DetectionError, DualLowProfile,
+    DualProfile, FiveWordPixelProfile, LayoutDetector, LegacyProfile, LidarProfile, LowDataProfile,
+    OusterConfig, PacketLayout, PcapNgWriter, PcapReader, Profile, SingleProfile,
+    ValidOusterConfig,
+};
+use serde_json::Value;
+
+type CliResult = Result<(), Box<dyn Error>>;
+
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Inspects and converts Ouster metadata and pcap recordings"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Prints sensor, lidar mode, profile and window of the metadata
+    ///
+    /// Metadata arguments accept the JSON or a pcapng recording with embedded metadata
+    Info { metadata: PathBuf },
+    /// Lists all values of the metadata, which contradict each other
+    Validate { metadata: PathBuf },
+    /// Writes the UDP datagrams of a recording into a pcapng file, which embeds the metadata
+    ///
+    /// The metadata is stored as comment of the section header, which is limited to 65535 bytes
+    Embed {
+        metadata: PathBuf,
+        pcap: PathBuf,
+        output: PathBuf,
+    },
+    /// Detects profile and beam count from the packets of a recording
+    Detect {
+        pcap: PathBuf,
+        /// Checks whether the metadata matches the packets, the embedded metadata by default
+        #[arg(long)]
+        metadata: Option<PathBuf>,
+        /// Destination port of the lidar packets, all ports by default
+        #[arg(long)]
+        port: Option<u16>,
+        /// Number of lidar packets to inspect
+        #[arg(long, default_value_t = 100)]
+        packets: usize,
+    },
+    /// Prints packet loss and frame rate of a recording
+    Stats {
+        pcap: PathBuf,
+        /// The embedded metadata by default
+        #[arg(long)]
+        metadata: Option<PathBuf>,
+        /// Destination port of the lidar packets, udp_port_lidar of the metadata by default
+        #[arg(long)]
+        port: Option<u16>,
+    },
+    /// Writes every frame of a recording into a directory
+    Convert {
+        pcap: PathBuf,
+        output: PathBuf,
+        /// The embedded metadata by default
+        #[arg(long)]
+        metadata: Option<PathBuf>,
+        #[arg(long, value_enum)]
+        format: Format,
+        /// Destination port of the lidar packets, udp_port_lidar of the metadata by default
+        #[arg(long)]
+        port: Option<u16>,
+        #[arg(long)]
+        max_frames: Option<usize>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Pcd,
+    Ply,
+    /// One 8 or 16 bit PNG per field
+    Png,
+}
+
+/// Calls the generic function with the profile matching the lidar_data_format of the config
+macro_rules! dispatch {
+    ($config:expr, $f:ident($($arg:expr),*)) => {{
+        let format = &$config.lidar_data_format;
+        if format.columns_per_packet != 16 {
+            return Err(format!(
+                "Unsupported columns_per_packet {}, only 16 is supported",
+                format.columns_per_packet
+            )
+            .into());
+        }
+        match format.pixels_per_column {
+            16 => dispatch!(@profile format.udp_profile_lidar, 16, $f($($arg),*)),
+            32 => dispatch!(@profile format.udp_profile_lidar, 32, $f($($arg),*)),
+            64 => dispatch!(@profile format.udp_profile_lidar, 64, $f($($arg),*)),
+            128 => dispatch!(@profile format.udp_profile_lidar, 128, $f($($arg),*)),
+            layers => Err(format!("Unsupported pixels_per_column {layers}").into()),
+        }
+    }};
+    (@profile $profile:expr, $layers:literal, $f:ident($($arg:expr),*)) => {
+        match $profile {
+            LidarProfile::SingleReturn => $f::<SingleProfile<16, $layers>>($($arg),*),
+            LidarProfile::DualReturn => $f::<DualProfile<16, $layers>>($($arg),*),
+            LidarProfile::LowData => $f::<LowDataProfile<16, $layers>>($($arg),*),
+            LidarProfile::DualLowData => $f::<DualLowProfile<16, $layers>>($($arg),*),
+            LidarProfile::FiveWordPixel => $f::<FiveWordPixelProfile<16, $layers>>($($arg),*),
+            LidarProfile::Legacy => $f::<LegacyProfile<16, $layers>>($($arg),*),
+            profile => Err(format!("Unsupported profile {profile}").into()),
+        }
+    };
+}
+
+fn main() -> ExitCode {
+    let result = match Cli::parse().command {
+        Command::Info { metadata } => info(&metadata),
+        Command::Validate { metadata } => {
+            read_config(&metadata).and_then(|config| dispatch!(config, validate(config)))
+        }
+        Command::Embed {
+            metadata,
+            pcap,
+            output,
+        } => embed(&metadata, &pcap, &output),
+        Command::Detect {
+            pcap,
+            metadata,
+            port,
+            packets,
+        } => detect(&pcap, metadata.as_deref(), port, packets),
+        Command::Stats {
+            pcap,
+            metadata,
+            port,
+        } => read_config(metadata.as_ref().unwrap_or(&pcap))
+            .and_then(|config| dispatch!(config, stats(config, &pcap, port))),
+        Command::Convert {
+            pcap,
+            output,
+            metadata,
+            format,
+            port,
+            max_frames,
+        } => read_config(metadata.as_ref().unwrap_or(&pcap)).and_then(|config| {
+            dispatch!(
+                config,
+                convert(config, &pcap, &output, format, port, max_frames)
+            )
+        }),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Metadata JSON, or the metadata embedded in a pcapng recording
+/// Of recordings only the section header is read
+fn read_metadata(path: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut file = BufReader::new(File::open(path)?);
+    if file.fill_buf()?.trim_ascii_start().starts_with(b"{") {
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        return Ok(data);
+    }
+    let reader = PcapReader::new(file)?;
+    let json = reader
+        .metadata_json()
+        .ok_or_else(|| format!("{} doesn't embed metadata", path.display()))?;
+    Ok(json.as_bytes().to_vec())
+}
+
+fn read_config(path: &Path) -> Result<OusterConfig, Box<dyn Error>> {
+    Ok(OusterConfig::from_metadata_json(&read_metadata(path)?)?)
+}
+
+fn info(path: &Path) -> CliResult {
+    let data = read_metadata(path)?;
+    let config = OusterConfig::from_metadata_json(&data)?;
+    // Nested metadata contains sensor_info, the legacy layout has the keys at the top level
+    let value = serde_json::from_slice::<Value>(&data)?;
+    let sensor_info = value.get("sensor_info").unwrap_or(&value);
+    let get = |key: &str| {
+        sensor_info
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or("unknown")
+            .to_owned()
+    };
+
+    let params = &config.config_params;
+    let format = &config.lidar_data_format;
+    let (azimuth_from, azimuth_to) = params.azimuth_window.degrees();
+    println!("Sensor:          {} {}", get("prod_line"), get("prod_sn"));
+    println!("Firmware:        {}", get("build_rev"));
+    println!("Lidar mode:      {}", params.lidar_mode);
+    println!("Profile:         {}", format.udp_profile_lidar);
+    println!(
+        "Beams:           {} ({} columns per packet)",
+        format.pixels_per_column, format.columns_per_packet
+    );
+    println!("Azimuth window:  {azimuth_from}° - {azimuth_to}°");
+    println!(
+        "Column window:   {} - {} of {}",
+        format.column_window.0, format.column_window.1, format.columns_per_frame
+    );
+    println!("UDP port lidar:  {}", params.udp_port_lidar);
+    if let Some(udp_dest) = &params.udp_dest {
+        println!("UDP destination: {udp_dest}");
+    }
+    Ok(())
+}
+
+fn validate<TProfile: Profile>(config: OusterConfig) -> CliResult {
+    let profile = config.lidar_data_format.udp_profile_lidar;
+    let problems = config.problems::<TProfile>();
+    if !problems.is_empty() {
+        for problem in &problems {
+            println!("{problem}");
+        }
+        return Err(format!("Found {} problems in the metadata", problems.len()).into());
+    }
+    println!("Valid {profile} metadata with {} beams", TProfile::LAYERS);
+    Ok(())
+}
+
+/// Datagrams are written unfragmented, other packets of the recording are dropped
+fn embed(metadata: &Path, pcap: &Path, output: &Path) -> CliResult {
+    let json = String::from_utf8(read_metadata(metadata)?)?;
+    OusterConfig::from_metadata_json(json.as_bytes())?;
+    let mut reader = PcapReader::open(pcap)?;
+    let mut writer = PcapNgWriter::create(output, Some(&json))?;
+    let mut count = 0;
+    while let Some(datagram) = reader.next_datagram()? {
+        writer.write_datagram(&datagram)?;
+        count += 1;
+    }
+    writer.finish()?;
+    println!("Wrote {count} datagrams to {}", output.display());
+    Ok(())
+}
+
+fn detect(pcap: &Path, metadata: Option<&Path>, port: Option<u16>, packets: usize) -> CliResult {
+    let mut reader = PcapReader::open(pcap)?;
+    let mut detector = LayoutDetector::default();
+    let mut inspected = 0;
+    while inspected < packets {
+        let Some(datagram) = reader.next_datagram()? else {
+            break;
+        };
+        if port.is_none_or(|port| datagram.destination.port() == port)
+            && detector.push(&datagram.payload)
+        {
+            inspected += 1;
+        }
+    }
+
+    match detector.result() {
+        Ok(layout) => println!("Detected {layout} in {inspected} packets"),
+        Err(DetectionError::Ambiguous(layouts)) => {
+            println!("{inspected} packets match several layouts:");
+            for layout in layouts {
+                println!("  {layout}");
+            }
+        }
+        Err(err) => return Err(err.into()),
+    }
+    let config = match metadata {
+        Some(metadata) => Some(read_config(metadata)?),
+        None => reader.ouster_config().transpose()?,
+    };
+    if let Some(config) = config {
+        let expected = PacketLayout::from(&config.lidar_data_format);
+        if !detector.candidates().contains(&expected) {
+            return Err(
+                format!("Metadata describes {expected}, which doesn't match the packets").into(),
+            );
+        }
+        println!("Metadata matches the packets");
+    }
+    Ok(())
+}
+
+fn stats<TProfile: Profile>(config: OusterConfig, pcap: &Path, port: Option<u16>) -> CliResult {
+    let config = ValidOusterConfig::<TProfile>::try_from(config)?;
+    let port = port.unwrap_or(config.config_params.udp_port_lidar);
+    let window = &config.lidar_data_format.column_window;
+    let mut aggregator = Aggregator::from_config(&config);
+    let mut reader = PcapReader::open(pcap)?;
+
+    let mut frames = 0;
+    let mut first_and_last = None;
+    while let Some(frame) = reader.next_frame(&mut aggregator, Some(port))? {
+        frames += 1;
+        let timestamp = frame.timestamp();
+        first_and_last =
+            Some(first_and_last.map_or((timestamp, timestamp), |(first, _)| (first, timestamp)));
+    }
+    let statistics = aggregator.get_statistics();
+
+    println!("Frames:               {frames}");
+    if let Some((first, last)) = first_and_last.filter(|(first, last)| last > first) {
+        let rate = (frames - 1) as f64 / (last - first).as_secs_f64();
+        println!(
+            "Frame rate:           {rate:.2} Hz (expected {} Hz)",
+            config.config_params.lidar_mode.frequency_hz()
+        );
+    }
+    if let (Some(received), Some(expected)) = (
+        statistics.received_packets_per_second,
+        statistics.expected_packets_per_second,
+    ) {
+        println!("Packet rate:          {received:.1}/s (expected {expected:.1}/s)");
+    }
+    println!("Dropped frames:       {}", statistics.dropped_frames);
+    let histogram = statistics
+        .completion_historgram
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count != 0)
+        .map(|(packets, count)| format!("{packets}: {count}"))
+        .collect::<Vec<_>>();
+    println!("Frames by packets:    {}", histogram.join(", "));
+    let measurements_per_frame =
+        config.lidar_data_format.columns_per_frame as usize / TProfile::COLUMNS;
+    let lost = statistics.missing_packets.iter().sum::<u32>();
+    println!("Lost packets:         {lost}");
+    for (i, missing) in statistics.missing_packets.iter().enumerate() {
+        if *missing != 0 {
+            let index = (window.start_measurement_id() as usize + i) % measurements_per_frame;
+            println!(
+                "  measurement_id {:>5}: {missing}",
+                index * TProfile::COLUMNS
+            );
+        }
+    }
+    Ok(())
+}
+
+fn convert<TProfile: Profile>(
+    config: OusterConfig,
+    pcap: &Path,
+    output: &Path,
+    format: Format,
+    port: Option<u16>,
+    max_frames: Option<usize>,
+) -> CliResult {
+    let config = ValidOusterConfig::<TProfile>::try_from(config)?;
+    let port = port.unwrap_or(config.config_params.udp_port_lidar);
+    let mut aggregator = Aggregator::from_config(&config);
+    let cartesian = CartesianIterator::new_cheap_cloneable_from_config(&config);
+    let mut reader = PcapReader::open(pcap)?;
+    std::fs::create_dir_all(output)?;
+
+    let mut count = 0;
+    while count < max_frames.unwrap_or(usize::MAX) {
+        let Some(frame) = reader.next_frame(&mut aggregator, Some(port))? else {
+            break;
+        };
+        let create = |extension: &str| {
+            File::create(output.join(format!("{count:06}.{extension}"))).map(BufWriter::new)
+        };
+        match format {
+            Format::Pcd | Format::Ply => {
+                let points = frame
+                    .export_points(&config, cartesian.clone())
+                    .collect::<Vec<_>>();
+                match format {
+                    Format::Pcd => write_pcd(create("pcd")?, &points)?,
+                    _ => write_ply(create("ply")?, &points)?,
+                }
+            }
+            Format::Png => {
+                for field in ChanField::ALL {
+                    if let Some(image) = frame.field(&config, field) {
+                        let name = field.to_string().to_lowercase();
+                        image.save_png(output.join(format!("{count:06}_{name}.png")))?;
+                    }
+                }
+            }
+        }
+        count += 1;
+    }
+    println!("Wrote {count} frames to {}", output.display());
+    Ok(())
+}
@@ -18,6 +18,18 @@ impl PolarPoint {
     }
 }
 
+/// A single return of a pixel in cartesian coordinates
+/// return_index is 0 for the strongest and 1 for the second strongest return
+#[derive(Debug, Clone, Copy)]
+pub struct ReturnPoint<TSignal> {
+    pub xyz: (f32, f32, f32),
+    pub distance: u16,
+    pub reflectifity: u8,
+    pub signal: TSignal,
+    pub nir: u8,
+    pub return_index: u8,
+}
+
 impl<TProfile: Profile> CartesianIterator<TProfile, Arc<[(f32, f32)]>> {
     pub fn new_cheap_cloneable_from_config(config: &ValidOperationConfig<TProfile>) -> Self {
         let azimuth_roh_lut = config
@@ -141,7 +153,7 @@ mod tests {
             x.iter()
                 .zip([0.2, 0.4, 0.2, 0.4])
                 .map(|(actual, expected)| {
-                    assert!((actual.roh - expected).abs() < std::f32::EPSILON, "{x:?}");
+                    assert!((actual.roh - expected).abs() < f32::EPSILON, "{x:?}");
                 })
                 .count()
         );
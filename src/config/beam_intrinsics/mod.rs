@@ -1,11 +1,17 @@
 use serde::{Deserialize, Serialize};
 
+use crate::config::json_number::{serialize_f32, serialize_f32s};
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BeamIntrinsics {
+    #[serde(serialize_with = "serialize_f32s")]
     pub beam_altitude_angles: Vec<f32>,
+    #[serde(serialize_with = "serialize_f32s")]
     pub beam_azimuth_angles: Vec<f32>,
-    pub lidar_origin_to_beam_origin_mm: f32,
+    #[serde(serialize_with = "serialize_f32s")]
     pub beam_to_lidar_transform: [f32; 16],
+    #[serde(serialize_with = "serialize_f32")]
+    pub lidar_origin_to_beam_origin_mm: f32,
 }
 
 impl BeamIntrinsics {
@@ -2,21 +2,36 @@ use std::ops::Deref;
 
 use serde::{Deserialize, Serialize};
 
+use crate::{ConfigField, InvalidConfig, LidarMode};
+
+const FULL_CIRCLE: i64 = 360_000;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
 pub struct AzimuthWindow([u32; 2]);
 
 impl TryFrom<[u32; 2]> for AzimuthWindow {
-    type Error = &'static str;
+    type Error = InvalidConfig;
 
     fn try_from(value: [u32; 2]) -> Result<Self, Self::Error> {
         if value[0] > 360_000 || value[1] > 360_000 {
-            Err("Component musten't be > 360'000")
+            Err(InvalidConfig::out_of_range(
+                ConfigField::AzimuthWindow,
+                "components <= 360000",
+                format_args!("{value:?}"),
+            ))
         } else {
             Ok(AzimuthWindow(value))
         }
     }
 }
 
+/// Full circle
+impl Default for AzimuthWindow {
+    fn default() -> Self {
+        AzimuthWindow([0, 360_000])
+    }
+}
+
 impl From<AzimuthWindow> for [u32; 2] {
     fn from(value: AzimuthWindow) -> Self {
         value.0
@@ -31,6 +46,59 @@ impl AzimuthWindow {
             360_000 - self[0] + self[1]
         }
     }
+
+    /// Window from degrees, rounded to millidegree
+    pub fn from_degrees(start: f32, end: f32) -> Result<Self, InvalidConfig> {
+        let to_milli = |deg: f32| {
+            let milli = (deg * 1000.).round();
+            if (0. ..=360_000.).contains(&milli) {
+                Ok(milli as u32)
+            } else {
+                Err(InvalidConfig::out_of_range(
+                    ConfigField::AzimuthWindow,
+                    "components within 0..=360 deg",
+                    format_args!("{deg} deg"),
+                ))
+            }
+        };
+        Self::try_from([to_milli(start)?, to_milli(end)?])
+    }
+
+    /// (start, end) in degree
+    pub fn degrees(&self) -> (f32, f32) {
+        (self[0] as f32 / 1000., self[1] as f32 / 1000.)
+    }
+
+    /// First and last column (inclusive), which the sensor sends for this window
+    /// `azimuth_offset` is the `lidar_frame_azimuth_offset` in millidegree, where column 0 starts
+    pub fn column_window(&self, lidar_mode: LidarMode, azimuth_offset: u32) -> (u16, u16) {
+        let columns = lidar_mode.horizontal_resolution() as i64;
+        let first_column_after = |milli_deg: u32| {
+            let relative = (milli_deg as i64 - azimuth_offset as i64).rem_euclid(FULL_CIRCLE);
+            (relative * columns + FULL_CIRCLE - 1) / FULL_CIRCLE
+        };
+        let from = first_column_after(self[0]).rem_euclid(columns);
+        let to = first_column_after(self[1]) - 1;
+        (from as u16, to.rem_euclid(columns) as u16)
+    }
+
+    /// Inverse of [AzimuthWindow::column_window]
+    pub fn from_column_window(
+        (from, to): (u16, u16),
+        lidar_mode: LidarMode,
+        azimuth_offset: u32,
+    ) -> Self {
+        let columns = lidar_mode.horizontal_resolution() as i64;
+        let to_milli_deg = |column: u16| {
+            (column as i64 * FULL_CIRCLE / columns + azimuth_offset as i64) % FULL_CIRCLE
+        };
+        let start = to_milli_deg(from);
+        let end = match to_milli_deg((to + 1) % columns as u16) {
+            0 => FULL_CIRCLE,
+            end => end,
+        };
+        AzimuthWindow([start as u32, end as u32])
+    }
 }
 
 impl<'de> Deserialize<'de> for AzimuthWindow {
@@ -54,7 +122,7 @@ impl Deref for AzimuthWindow {
 
 #[cfg(test)]
 mod tests {
-    use crate::AzimuthWindow;
+    use crate::{AzimuthWindow, LidarMode};
 
     #[test]
     fn overflow_angel() {
@@ -75,4 +143,43 @@ mod tests {
                 .milli_angle_deg()
         );
     }
+
+    #[test]
+    fn column_window() {
+        let full = AzimuthWindow::default();
+        assert_eq!((0, 1023), full.column_window(LidarMode::Mode1024x10, 0));
+        assert_eq!(
+            (1024 - 256, 1023 - 256),
+            full.column_window(LidarMode::Mode1024x10, 90_000)
+        );
+        let wrapping = AzimuthWindow::try_from([270_000, 90_000]).unwrap();
+        assert_eq!(
+            (768, 255),
+            wrapping.column_window(LidarMode::Mode1024x10, 0)
+        );
+        assert_eq!(
+            (512, 1023),
+            wrapping.column_window(LidarMode::Mode1024x10, 90_000)
+        );
+    }
+
+    #[test]
+    fn column_window_round_trip() {
+        for mode in [LidarMode::Mode512x10, LidarMode::Mode2048x10] {
+            for offset in [0, 1_234, 90_000] {
+                for window in [(0, 17), (3, 2), (100, 300), (300, 100)] {
+                    let azimuth = AzimuthWindow::from_column_window(window, mode, offset);
+                    assert_eq!(window, azimuth.column_window(mode, offset), "{azimuth:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn degrees() {
+        let window = AzimuthWindow::from_degrees(45.5, 90.).unwrap();
+        assert_eq!([45_500, 90_000], *window);
+        assert_eq!((45.5, 90.), window.degrees());
+        assert!(AzimuthWindow::from_degrees(-1., 90.).is_err());
+    }
 }
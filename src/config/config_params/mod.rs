@@ -1,27 +1,120 @@
-use std::{net::Ipv4Addr, str::FromStr};
+use std::time::Duration;
 
-use serde::{Deserialize, Deserializer, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-use crate::{InvalidConfig, LidarProfile};
+use crate::{ConfigField, InvalidConfig, LidarProfile};
 
 mod azimuth_window;
+mod modes;
 mod signal_multiplier;
+mod udp_dest;
 
 pub use azimuth_window::*;
+pub use modes::*;
 pub use signal_multiplier::*;
+pub use udp_dest::*;
 
+/// Parameters, which older firmwares don't report, are optional
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ConfigParamsRaw {
     pub azimuth_window: AzimuthWindow,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub columns_per_packet: Option<u8>,
+    /// Millidegree
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lidar_frame_azimuth_offset: Option<u32>,
     pub lidar_mode: LidarMode,
-    #[serde(deserialize_with = "option_v4_for_empty_string")]
-    pub udp_dest: Option<Ipv4Addr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multipurpose_io_mode: Option<MultipurposeIoMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nmea_baud_rate: Option<NmeaBaudRate>,
+    /// 0 or 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nmea_ignore_valid_char: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nmea_in_polarity: Option<Polarity>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nmea_leap_seconds: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operating_mode: Option<OperatingMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phase_lock_enable: Option<bool>,
+    /// Millidegree
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phase_lock_offset: Option<u32>,
+    pub signal_multiplier: SignalMultiplier,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_pulse_in_polarity: Option<Polarity>,
+    /// Degree
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_pulse_out_angle: Option<u16>,
+    /// Hz
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_pulse_out_frequency: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_pulse_out_polarity: Option<Polarity>,
+    /// Milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync_pulse_out_pulse_width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp_mode: Option<TimestampMode>,
+    /// Empty, if the sensor doesn't send data
+    #[serde(
+        deserialize_with = "none_for_empty_string",
+        serialize_with = "empty_string_for_none"
+    )]
+    pub udp_dest: Option<UdpDest>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub udp_port_imu: Option<u16>,
     pub udp_port_lidar: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub udp_profile_imu: Option<ImuProfile>,
     pub udp_profile_lidar: LidarProfile,
-    pub signal_multiplier: SignalMultiplier,
 }
 
-fn option_v4_for_empty_string<'de, D>(deserializer: D) -> Result<Option<Ipv4Addr>, D::Error>
+/// Factory settings of the sensor, unreported parameters are left empty
+impl Default for ConfigParamsRaw {
+    fn default() -> Self {
+        Self {
+            azimuth_window: AzimuthWindow::default(),
+            columns_per_packet: None,
+            lidar_frame_azimuth_offset: None,
+            lidar_mode: LidarMode::default(),
+            multipurpose_io_mode: None,
+            nmea_baud_rate: None,
+            nmea_ignore_valid_char: None,
+            nmea_in_polarity: None,
+            nmea_leap_seconds: None,
+            operating_mode: None,
+            phase_lock_enable: None,
+            phase_lock_offset: None,
+            signal_multiplier: SignalMultiplier::default(),
+            sync_pulse_in_polarity: None,
+            sync_pulse_out_angle: None,
+            sync_pulse_out_frequency: None,
+            sync_pulse_out_polarity: None,
+            sync_pulse_out_pulse_width: None,
+            timestamp_mode: None,
+            udp_dest: None,
+            udp_port_imu: None,
+            udp_port_lidar: 7502,
+            udp_profile_imu: None,
+            udp_profile_lidar: LidarProfile::default(),
+        }
+    }
+}
+
+fn empty_string_for_none<S>(value: &Option<UdpDest>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match value {
+        Some(x) => x.serialize(serializer),
+        None => serializer.serialize_str(""),
+    }
+}
+
+fn none_for_empty_string<'de, D>(deserializer: D) -> Result<Option<UdpDest>, D::Error>
 where
     D: Deserializer<'de>,
 {
@@ -30,7 +123,9 @@ where
         Ok(None)
     } else {
         Ok(Some(
-            Ipv4Addr::from_str(&as_str).map_err(|e| <D::Error as serde::de::Error>::custom(e))?,
+            as_str
+                .parse()
+                .map_err(<D::Error as serde::de::Error>::custom)?,
         ))
     }
 }
@@ -46,6 +141,16 @@ impl std::ops::Deref for ConfigParams {
     }
 }
 
+impl ConfigParams {
+    /// Column window the sensor derives from the azimuth window
+    pub fn column_window(&self) -> (u16, u16) {
+        self.azimuth_window.column_window(
+            self.lidar_mode,
+            self.lidar_frame_azimuth_offset.unwrap_or_default(),
+        )
+    }
+}
+
 impl<'de> Deserialize<'de> for ConfigParams {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -73,17 +178,105 @@ impl TryFrom<ConfigParamsRaw> for ConfigParams {
             .maximum_supported_azimuth_angle_deg();
         let given_milli_deg = value.azimuth_window.milli_angle_deg();
         if allowed_deg * 1000 < given_milli_deg {
-            Err(InvalidConfig {
-                reason: format!(
-                    "Azimuth-Angle is too big for signal_multiplier({:?}): Allowed({} deg) > Window({} millideg)",
-                    value.signal_multiplier,
-                    allowed_deg,
-                    given_milli_deg
-                ),
-            })
-        } else {
-            Ok(ConfigParams(value))
+            return Err(InvalidConfig::inconsistent(
+                ConfigField::AzimuthWindow,
+                ConfigField::SignalMultiplier,
+                format_args!("<= {} millideg", allowed_deg * 1000),
+                format_args!("{given_milli_deg} millideg"),
+            ));
+        }
+        if let Some(columns) = value.columns_per_packet {
+            if ![1, 2, 4, 8, 16].contains(&columns) {
+                return Err(InvalidConfig::out_of_range(
+                    ConfigField::ParamsColumnsPerPacket,
+                    "one of 1, 2, 4, 8, 16",
+                    columns,
+                ));
+            }
         }
+        check_max(
+            ConfigField::LidarFrameAzimuthOffset,
+            value.lidar_frame_azimuth_offset,
+            359_999,
+        )?;
+        check_max(
+            ConfigField::PhaseLockOffset,
+            value.phase_lock_offset,
+            359_999,
+        )?;
+        check_max(
+            ConfigField::NmeaIgnoreValidChar,
+            value.nmea_ignore_valid_char,
+            1,
+        )?;
+        check_max(
+            ConfigField::SyncPulseOutAngle,
+            value.sync_pulse_out_angle,
+            360,
+        )?;
+        if value.sync_pulse_out_frequency == Some(0) {
+            return Err(InvalidConfig::out_of_range(
+                ConfigField::SyncPulseOutFrequency,
+                ">= 1 Hz",
+                "0 Hz",
+            ));
+        }
+        if let (Some(width), Some(frequency)) = (
+            value.sync_pulse_out_pulse_width,
+            value.sync_pulse_out_frequency,
+        ) {
+            // The pulse has to end before the next one starts
+            let period_ms = 1000 / frequency;
+            if width >= period_ms.max(1) {
+                return Err(InvalidConfig::inconsistent(
+                    ConfigField::SyncPulseOutPulseWidth,
+                    ConfigField::SyncPulseOutFrequency,
+                    format_args!("< {period_ms} ms"),
+                    format_args!("{width} ms"),
+                ));
+            }
+        }
+        let required_timestamp_mode = match value.multipurpose_io_mode {
+            Some(MultipurposeIoMode::OutputFromSyncPulseIn) => Some(TimestampMode::SyncPulseIn),
+            Some(MultipurposeIoMode::OutputFromPtp1588) => Some(TimestampMode::Ptp1588),
+            _ => None,
+        };
+        if let (Some(required), Some(actual)) = (required_timestamp_mode, value.timestamp_mode) {
+            if required != actual {
+                return Err(InvalidConfig::inconsistent(
+                    ConfigField::TimestampMode,
+                    ConfigField::MultipurposeIoMode,
+                    format_args!("{required:?}"),
+                    format_args!("{actual:?}"),
+                ));
+            }
+        }
+        if value
+            .udp_port_imu
+            .is_some_and(|port| port != 0 && port == value.udp_port_lidar)
+        {
+            return Err(InvalidConfig::inconsistent(
+                ConfigField::UdpPortImu,
+                ConfigField::UdpPortLidar,
+                format_args!("a port other than {}", value.udp_port_lidar),
+                value.udp_port_lidar,
+            ));
+        }
+        Ok(ConfigParams(value))
+    }
+}
+
+fn check_max<T>(field: ConfigField, value: Option<T>, max: T) -> Result<(), InvalidConfig>
+where
+    T: PartialOrd + std::fmt::Display,
+{
+    match value {
+        Some(value) if value > max => Err(InvalidConfig::out_of_range(
+            field,
+            format_args!("<= {max}"),
+            value,
+        )),
+        _ => Ok(()),
     }
 }
 
@@ -100,14 +293,174 @@ pub enum LidarMode {
     Mode1024x20,
     #[serde(rename = "2048x10")]
     Mode2048x10,
+    #[serde(rename = "4096x5")]
+    Mode4096x5,
 }
 
 impl LidarMode {
+    /// Name used by the sensor
+    pub fn name(&self) -> &'static str {
+        match self {
+            LidarMode::Mode512x10 => "512x10",
+            LidarMode::Mode512x20 => "512x20",
+            LidarMode::Mode1024x10 => "1024x10",
+            LidarMode::Mode1024x20 => "1024x20",
+            LidarMode::Mode2048x10 => "2048x10",
+            LidarMode::Mode4096x5 => "4096x5",
+        }
+    }
+
     pub fn horizontal_resolution(&self) -> u16 {
         match self {
             LidarMode::Mode512x10 | LidarMode::Mode512x20 => 512,
             LidarMode::Mode1024x10 | LidarMode::Mode1024x20 => 1024,
             LidarMode::Mode2048x10 => 2048,
+            LidarMode::Mode4096x5 => 4096,
+        }
+    }
+
+    /// Rotations per second
+    pub fn frequency_hz(&self) -> u8 {
+        match self {
+            LidarMode::Mode4096x5 => 5,
+            LidarMode::Mode512x10 | LidarMode::Mode1024x10 | LidarMode::Mode2048x10 => 10,
+            LidarMode::Mode512x20 | LidarMode::Mode1024x20 => 20,
+        }
+    }
+
+    /// Duration of one rotation
+    pub fn frame_period(&self) -> Duration {
+        Duration::from_secs(1) / self.frequency_hz() as u32
+    }
+
+    /// Time between two consecutive measurements
+    pub fn column_period(&self) -> Duration {
+        self.frame_period() / self.horizontal_resolution() as u32
+    }
+}
+
+impl std::fmt::Display for LidarMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use super::*;
+
+    #[test]
+    fn azimuth_window_too_big_for_signal_multiplier() {
+        let err = ConfigParams::try_from(ConfigParamsRaw {
+            azimuth_window: [0, 200_000].try_into().unwrap(),
+            signal_multiplier: SignalMultiplier::Two,
+            udp_profile_lidar: LidarProfile::DualReturn,
+            ..Default::default()
+        })
+        .unwrap_err();
+        assert_eq!(ConfigField::AzimuthWindow, err.field());
+        assert!(matches!(
+            err,
+            InvalidConfig::Inconsistent {
+                related: ConfigField::SignalMultiplier,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn sync_pulse_longer_than_period() {
+        let err = ConfigParams::try_from(ConfigParamsRaw {
+            sync_pulse_out_frequency: Some(10),
+            sync_pulse_out_pulse_width: Some(100),
+            ..Default::default()
+        })
+        .unwrap_err();
+        assert_eq!(ConfigField::SyncPulseOutPulseWidth, err.field());
+    }
+
+    #[test]
+    fn ptp_output_requires_ptp_timestamps() {
+        let raw = ConfigParamsRaw {
+            multipurpose_io_mode: Some(MultipurposeIoMode::OutputFromPtp1588),
+            timestamp_mode: Some(TimestampMode::InternalOsc),
+            ..Default::default()
+        };
+        let err = ConfigParams::try_from(raw.clone()).unwrap_err();
+        assert_eq!(ConfigField::TimestampMode, err.field());
+        ConfigParams::try_from(ConfigParamsRaw {
+            timestamp_mode: Some(TimestampMode::Ptp1588),
+            ..raw
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn columns_per_packet() {
+        let err = ConfigParams::try_from(ConfigParamsRaw {
+            columns_per_packet: Some(3),
+            ..Default::default()
+        })
+        .unwrap_err();
+        assert_eq!(ConfigField::ParamsColumnsPerPacket, err.field());
+    }
+
+    #[test]
+    fn lidar_mode_periods() {
+        let mode: LidarMode = serde_json::from_str(r#""4096x5""#).unwrap();
+        assert_eq!(LidarMode::Mode4096x5, mode);
+        assert_eq!(Duration::from_millis(200), mode.frame_period());
+        assert_eq!(Duration::from_nanos(48_828), mode.column_period());
+        assert_eq!(
+            Duration::from_nanos(48_828),
+            LidarMode::Mode1024x20.column_period()
+        );
+    }
+
+    #[test]
+    fn imu_profile_of_newer_firmwares() {
+        let json = include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/data/os-1-64_v3.0.1_1024x10.json"
+        ))
+        .replace(r#""LEGACY""#, r#""ACCEL32_GYRO32_NMEA""#);
+        let config = crate::OusterConfig::from_metadata_json(json.as_bytes()).unwrap();
+        assert_eq!(
+            Some(ImuProfile::Accel32Gyro32Nmea),
+            config.config_params.udp_profile_imu
+        );
+    }
+
+    #[test]
+    fn udp_dest_serialization() {
+        for (json, expected) in [
+            ("", None),
+            ("@auto", Some(UdpDest::Auto)),
+            ("fe80::1", Some("fe80::1".parse::<IpAddr>().unwrap().into())),
+        ] {
+            let raw = ConfigParamsRaw {
+                udp_dest: expected,
+                ..Default::default()
+            };
+            let value = serde_json::to_value(&raw).unwrap();
+            assert_eq!(json, value["udp_dest"]);
+            assert_eq!(raw, serde_json::from_value(value).unwrap());
         }
     }
+
+    #[test]
+    fn unknown_values() {
+        let err = SignalMultiplier::try_from(1.5).unwrap_err();
+        assert_eq!(ConfigField::SignalMultiplier, err.field());
+        let err = "RNG".parse::<LidarProfile>().unwrap_err();
+        assert_eq!(
+            InvalidConfig::UnknownValue {
+                field: ConfigField::UdpProfileLidar,
+                actual: "RNG".into()
+            },
+            err
+        );
+    }
 }
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OperatingMode {
+    #[default]
+    Normal,
+    Standby,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum TimestampMode {
+    #[default]
+    #[serde(rename = "TIME_FROM_INTERNAL_OSC")]
+    InternalOsc,
+    #[serde(rename = "TIME_FROM_SYNC_PULSE_IN")]
+    SyncPulseIn,
+    #[serde(rename = "TIME_FROM_PTP_1588")]
+    Ptp1588,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum MultipurposeIoMode {
+    #[default]
+    #[serde(rename = "OFF")]
+    Off,
+    #[serde(rename = "INPUT_NMEA_UART")]
+    InputNmeaUart,
+    #[serde(rename = "OUTPUT_FROM_INTERNAL_OSC")]
+    OutputFromInternalOsc,
+    #[serde(rename = "OUTPUT_FROM_SYNC_PULSE_IN")]
+    OutputFromSyncPulseIn,
+    #[serde(rename = "OUTPUT_FROM_PTP_1588")]
+    OutputFromPtp1588,
+    #[serde(rename = "OUTPUT_FROM_ENCODER_ANGLE")]
+    OutputFromEncoderAngle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Polarity {
+    ActiveLow,
+    #[default]
+    ActiveHigh,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+pub enum NmeaBaudRate {
+    #[default]
+    #[serde(rename = "BAUD_9600")]
+    Baud9600,
+    #[serde(rename = "BAUD_115200")]
+    Baud115200,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[non_exhaustive]
+pub enum ImuProfile {
+    #[default]
+    Legacy,
+    /// Accelerometer and gyroscope with 32 bit floats and the NMEA sentence, since firmware 3.1
+    #[serde(rename = "ACCEL32_GYRO32_NMEA")]
+    Accel32Gyro32Nmea,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sensor_names() {
+        assert_eq!(
+            r#"["TIME_FROM_PTP_1588","OUTPUT_FROM_ENCODER_ANGLE","ACTIVE_LOW","BAUD_115200","STANDBY","LEGACY"]"#,
+            serde_json::to_string(&(
+                TimestampMode::Ptp1588,
+                MultipurposeIoMode::OutputFromEncoderAngle,
+                Polarity::ActiveLow,
+                NmeaBaudRate::Baud115200,
+                OperatingMode::Standby,
+                ImuProfile::Legacy
+            ))
+            .unwrap()
+        );
+        assert_eq!(
+            ImuProfile::Accel32Gyro32Nmea,
+            serde_json::from_str(r#""ACCEL32_GYRO32_NMEA""#).unwrap()
+        );
+    }
+}
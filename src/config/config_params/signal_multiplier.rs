@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::InvalidConfig;
+use crate::{ConfigField, InvalidConfig};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum SignalMultiplier {
@@ -30,9 +30,10 @@ impl Serialize for SignalMultiplier {
         match self {
             SignalMultiplier::Quarter => serializer.serialize_f32(0.25),
             SignalMultiplier::Half => serializer.serialize_f32(0.5),
-            SignalMultiplier::One => serializer.serialize_f32(1.),
-            SignalMultiplier::Two => serializer.serialize_f32(2.),
-            SignalMultiplier::Three => serializer.serialize_f32(3.),
+            // Integral values are written without fraction like the sensor does
+            SignalMultiplier::One => serializer.serialize_u8(1),
+            SignalMultiplier::Two => serializer.serialize_u8(2),
+            SignalMultiplier::Three => serializer.serialize_u8(3),
         }
     }
 }
@@ -63,9 +64,11 @@ impl TryFrom<f32> for SignalMultiplier {
         } else if value == 3. {
             Ok(Self::Three)
         } else {
-            Err(InvalidConfig {
-                reason: format!("Invalid signal multiplier: {value}"),
-            })
+            Err(InvalidConfig::out_of_range(
+                ConfigField::SignalMultiplier,
+                "one of 0.25, 0.5, 1, 2, 3",
+                value,
+            ))
         }
     }
 }
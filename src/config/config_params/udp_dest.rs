@@ -0,0 +1,115 @@
+use std::{
+    borrow::Cow,
+    fmt::Display,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ConfigField, InvalidConfig};
+
+/// Destination of the lidar and imu packets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UdpDest {
+    /// The sensor sends to the host which requested the data
+    Auto,
+    Address(IpAddr),
+}
+
+impl UdpDest {
+    const AUTO: &'static str = "@auto";
+
+    pub fn ip(&self) -> Option<IpAddr> {
+        match self {
+            UdpDest::Auto => None,
+            UdpDest::Address(ip) => Some(*ip),
+        }
+    }
+
+    /// Group which receivers have to join
+    pub fn multicast_group(&self) -> Option<IpAddr> {
+        self.ip().filter(IpAddr::is_multicast)
+    }
+}
+
+impl From<IpAddr> for UdpDest {
+    fn from(value: IpAddr) -> Self {
+        UdpDest::Address(value)
+    }
+}
+
+impl From<Ipv4Addr> for UdpDest {
+    fn from(value: Ipv4Addr) -> Self {
+        UdpDest::Address(value.into())
+    }
+}
+
+impl From<Ipv6Addr> for UdpDest {
+    fn from(value: Ipv6Addr) -> Self {
+        UdpDest::Address(value.into())
+    }
+}
+
+impl FromStr for UdpDest {
+    type Err = InvalidConfig;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == Self::AUTO {
+            return Ok(UdpDest::Auto);
+        }
+        s.parse::<IpAddr>()
+            .map(UdpDest::Address)
+            .map_err(|_| InvalidConfig::UnknownValue {
+                field: ConfigField::UdpDest,
+                actual: s.into(),
+            })
+    }
+}
+
+impl Display for UdpDest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UdpDest::Auto => f.write_str(Self::AUTO),
+            UdpDest::Address(ip) => ip.fmt(f),
+        }
+    }
+}
+
+impl Serialize for UdpDest {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for UdpDest {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let x = Cow::<str>::deserialize(deserializer)?;
+        Self::from_str(&x).map_err(<D::Error as serde::de::Error>::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(UdpDest::Auto, "@auto".parse().unwrap());
+        let v6: UdpDest = "ff02::1".parse().unwrap();
+        assert_eq!(Some("ff02::1".parse().unwrap()), v6.multicast_group());
+        let v4: UdpDest = "192.168.1.2".parse().unwrap();
+        assert_eq!(None, v4.multicast_group());
+        assert_eq!("192.168.1.2", v4.to_string());
+        assert_eq!(
+            ConfigField::UdpDest,
+            "auto".parse::<UdpDest>().unwrap_err().field()
+        );
+    }
+}
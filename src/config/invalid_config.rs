@@ -0,0 +1,167 @@
+use std::fmt::Display;
+
+/// Field of the sensor metadata
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ConfigField {
+    AzimuthWindow,
+    LidarMode,
+    SignalMultiplier,
+    UdpProfileLidar,
+    ColumnWindow,
+    ColumnsPerFrame,
+    ColumnsPerPacket,
+    PixelShiftByRow,
+    PixelsPerColumn,
+    DataFormatUdpProfileLidar,
+    BeamAltitudeAngles,
+    BeamAzimuthAngles,
+    ParamsColumnsPerPacket,
+    LidarFrameAzimuthOffset,
+    MultipurposeIoMode,
+    NmeaIgnoreValidChar,
+    PhaseLockOffset,
+    SyncPulseOutAngle,
+    SyncPulseOutFrequency,
+    SyncPulseOutPulseWidth,
+    TimestampMode,
+    UdpPortImu,
+    UdpPortLidar,
+    UdpDest,
+}
+
+impl ConfigField {
+    /// Path within the metadata json
+    pub fn path(&self) -> &'static str {
+        match self {
+            ConfigField::AzimuthWindow => "config_params.azimuth_window",
+            ConfigField::LidarMode => "config_params.lidar_mode",
+            ConfigField::SignalMultiplier => "config_params.signal_multiplier",
+            ConfigField::UdpProfileLidar => "config_params.udp_profile_lidar",
+            ConfigField::ColumnWindow => "lidar_data_format.column_window",
+            ConfigField::ColumnsPerFrame => "lidar_data_format.columns_per_frame",
+            ConfigField::ColumnsPerPacket => "lidar_data_format.columns_per_packet",
+            ConfigField::PixelShiftByRow => "lidar_data_format.pixel_shift_by_row",
+            ConfigField::PixelsPerColumn => "lidar_data_format.pixels_per_column",
+            ConfigField::DataFormatUdpProfileLidar => "lidar_data_format.udp_profile_lidar",
+            ConfigField::BeamAltitudeAngles => "beam_intrinsics.beam_altitude_angles",
+            ConfigField::BeamAzimuthAngles => "beam_intrinsics.beam_azimuth_angles",
+            ConfigField::ParamsColumnsPerPacket => "config_params.columns_per_packet",
+            ConfigField::LidarFrameAzimuthOffset => "config_params.lidar_frame_azimuth_offset",
+            ConfigField::MultipurposeIoMode => "config_params.multipurpose_io_mode",
+            ConfigField::NmeaIgnoreValidChar => "config_params.nmea_ignore_valid_char",
+            ConfigField::PhaseLockOffset => "config_params.phase_lock_offset",
+            ConfigField::SyncPulseOutAngle => "config_params.sync_pulse_out_angle",
+            ConfigField::SyncPulseOutFrequency => "config_params.sync_pulse_out_frequency",
+            ConfigField::SyncPulseOutPulseWidth => "config_params.sync_pulse_out_pulse_width",
+            ConfigField::TimestampMode => "config_params.timestamp_mode",
+            ConfigField::UdpPortImu => "config_params.udp_port_imu",
+            ConfigField::UdpPortLidar => "config_params.udp_port_lidar",
+            ConfigField::UdpDest => "config_params.udp_dest",
+        }
+    }
+}
+
+impl Display for ConfigField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.path())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum InvalidConfig {
+    /// The value is not allowed for this field
+    #[error("{field}: Expected {expected}, got {actual}")]
+    OutOfRange {
+        field: ConfigField,
+        expected: String,
+        actual: String,
+    },
+    /// The value doesn't match the Profile, which is used to process the data
+    #[error("{field}: Expected {expected} for the chosen profile, got {actual}")]
+    ProfileMismatch {
+        field: ConfigField,
+        expected: String,
+        actual: String,
+    },
+    /// The value contradicts another field
+    #[error("{field}: Expected {expected} to be consistent with {related}, got {actual}")]
+    Inconsistent {
+        field: ConfigField,
+        related: ConfigField,
+        expected: String,
+        actual: String,
+    },
+    /// The value can't be parsed
+    #[error("{field}: Unknown value '{actual}'")]
+    UnknownValue { field: ConfigField, actual: String },
+}
+
+impl InvalidConfig {
+    pub fn field(&self) -> ConfigField {
+        match self {
+            InvalidConfig::OutOfRange { field, .. }
+            | InvalidConfig::ProfileMismatch { field, .. }
+            | InvalidConfig::Inconsistent { field, .. }
+            | InvalidConfig::UnknownValue { field, .. } => *field,
+        }
+    }
+
+    /// None, if there is no single expected value
+    pub fn expected(&self) -> Option<&str> {
+        match self {
+            InvalidConfig::OutOfRange { expected, .. }
+            | InvalidConfig::ProfileMismatch { expected, .. }
+            | InvalidConfig::Inconsistent { expected, .. } => Some(expected),
+            InvalidConfig::UnknownValue { .. } => None,
+        }
+    }
+
+    pub fn actual(&self) -> &str {
+        match self {
+            InvalidConfig::OutOfRange { actual, .. }
+            | InvalidConfig::ProfileMismatch { actual, .. }
+            | InvalidConfig::Inconsistent { actual, .. }
+            | InvalidConfig::UnknownValue { actual, .. } => actual,
+        }
+    }
+
+    pub(crate) fn out_of_range(
+        field: ConfigField,
+        expected: impl Display,
+        actual: impl Display,
+    ) -> Self {
+        Self::OutOfRange {
+            field,
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        }
+    }
+
+    pub(crate) fn profile_mismatch(
+        field: ConfigField,
+        expected: impl Display,
+        actual: impl Display,
+    ) -> Self {
+        Self::ProfileMismatch {
+            field,
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        }
+    }
+
+    pub(crate) fn inconsistent(
+        field: ConfigField,
+        related: ConfigField,
+        expected: impl Display,
+        actual: impl Display,
+    ) -> Self {
+        Self::Inconsistent {
+            field,
+            related,
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use serde::{Serialize, Serializer};
+
+/// The sensor writes integral numbers without fraction (1 instead of 1.0)
+/// f32 are written with their shortest representation, so values read from json are written identically
+pub(crate) struct CompactFloat(pub f64);
+
+impl From<f32> for CompactFloat {
+    fn from(value: f32) -> Self {
+        // Display uses the shortest representation which parses back to the same f32
+        Self(value.to_string().parse().unwrap_or(value as f64))
+    }
+}
+
+impl Serialize for CompactFloat {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.0.fract() == 0. && self.0.abs() < i64::MAX as f64 {
+            serializer.serialize_i64(self.0 as i64)
+        } else {
+            serializer.serialize_f64(self.0)
+        }
+    }
+}
+
+pub(crate) fn serialize_f32<S: Serializer>(value: &f32, serializer: S) -> Result<S::Ok, S::Error> {
+    CompactFloat::from(*value).serialize(serializer)
+}
+
+pub(crate) fn serialize_f32s<S: Serializer>(
+    values: &impl AsRef<[f32]>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(values.as_ref().iter().map(|&x| CompactFloat::from(x)))
+}
+
+pub(crate) fn serialize_f64s<S: Serializer>(
+    values: &impl AsRef<[f64]>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(values.as_ref().iter().map(|&x| CompactFloat(x)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integral_without_fraction() {
+        let json = serde_json::to_string(&[
+            CompactFloat(1.),
+            CompactFloat(-0.),
+            CompactFloat::from(20.64f32),
+            CompactFloat(-11.775),
+        ])
+        .unwrap();
+        assert_eq!("[1,0,20.64,-11.775]", json);
+    }
+}
@@ -0,0 +1,180 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    AzimuthWindow, BeamIntrinsics, ConfigParamsRaw, InvalidConfig, LidarDataFormat, LidarMode,
+    LidarProfile, OusterConfig,
+};
+
+const DEFAULT_UDP_PORT_LIDAR: u16 = 7502;
+
+/// Flat metadata written by firmware < 2.x and old recordings
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct LegacyMetadata {
+    pub lidar_mode: LidarMode,
+    pub beam_altitude_angles: Vec<f32>,
+    pub beam_azimuth_angles: Vec<f32>,
+    #[serde(default)]
+    pub lidar_origin_to_beam_origin_mm: f32,
+    #[serde(default)]
+    pub beam_to_lidar_transform: Option<[f32; 16]>,
+    #[serde(default)]
+    pub data_format: Option<LegacyDataFormat>,
+    #[serde(default)]
+    pub udp_port_lidar: Option<u16>,
+    #[serde(default)]
+    pub azimuth_window: Option<AzimuthWindow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct LegacyDataFormat {
+    pub pixels_per_column: u8,
+    pub columns_per_packet: u8,
+    pub columns_per_frame: u16,
+    pub pixel_shift_by_row: Box<[i8]>,
+    #[serde(default)]
+    pub column_window: Option<(u16, u16)>,
+    #[serde(default)]
+    pub udp_profile_lidar: Option<LidarProfile>,
+}
+
+impl TryFrom<LegacyMetadata> for OusterConfig {
+    type Error = InvalidConfig;
+
+    /// Missing values are filled with the defaults of the firmware, which produced the legacy format
+    fn try_from(value: LegacyMetadata) -> Result<Self, Self::Error> {
+        let columns_per_frame = value.lidar_mode.horizontal_resolution();
+        let azimuth_window = value.azimuth_window.unwrap_or_default();
+        let column_window = azimuth_window.column_window(value.lidar_mode, 0);
+        let lidar_data_format = match value.data_format {
+            Some(format) => LidarDataFormat {
+                column_window: format.column_window.unwrap_or(column_window),
+                columns_per_frame: format.columns_per_frame,
+                columns_per_packet: format.columns_per_packet,
+                pixel_shift_by_row: format.pixel_shift_by_row,
+                pixels_per_column: format.pixels_per_column,
+                udp_profile_lidar: format.udp_profile_lidar.unwrap_or(LidarProfile::Legacy),
+            },
+            None => LidarDataFormat {
+                column_window,
+                columns_per_frame,
+                columns_per_packet: 16,
+                // Staggering compensates the azimuth offset of each beam
+                pixel_shift_by_row: value
+                    .beam_azimuth_angles
+                    .iter()
+                    .map(|azimuth| (azimuth * columns_per_frame as f32 / 360.).round() as i8)
+                    .collect(),
+                pixels_per_column: value.beam_altitude_angles.len() as u8,
+                udp_profile_lidar: LidarProfile::Legacy,
+            },
+        };
+
+        let beam_to_lidar_transform = value.beam_to_lidar_transform.unwrap_or_else(|| {
+            let mut transform = [0.; 16];
+            for i in 0..4 {
+                transform[i * 5] = 1.;
+            }
+            transform[3] = value.lidar_origin_to_beam_origin_mm;
+            transform
+        });
+
+        let config_params = ConfigParamsRaw {
+            azimuth_window,
+            lidar_mode: value.lidar_mode,
+            udp_port_lidar: value.udp_port_lidar.unwrap_or(DEFAULT_UDP_PORT_LIDAR),
+            udp_profile_lidar: lidar_data_format.udp_profile_lidar,
+            ..Default::default()
+        }
+        .try_into()?;
+
+        Ok(OusterConfig {
+            beam_intrinsics: BeamIntrinsics {
+                beam_altitude_angles: value.beam_altitude_angles,
+                beam_azimuth_angles: value.beam_azimuth_angles,
+                beam_to_lidar_transform,
+                lidar_origin_to_beam_origin_mm: value.lidar_origin_to_beam_origin_mm,
+            },
+            config_params,
+            lidar_data_format,
+        })
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ParseMetadataError {
+    #[error("Invalid json: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error(
+        "Unknown metadata layout: Neither 'lidar_data_format' nor 'beam_altitude_angles' found"
+    )]
+    UnknownLayout,
+    #[error(transparent)]
+    InvalidConfig(#[from] InvalidConfig),
+}
+
+impl OusterConfig {
+    /// Detects whether the metadata is in the nested layout of firmware >= 2.x or in the flat legacy layout
+    pub fn from_metadata_json(data: &[u8]) -> Result<Self, ParseMetadataError> {
+        let value = serde_json::from_slice::<Value>(data)?;
+        if value.get("lidar_data_format").is_some() {
+            Ok(serde_json::from_value(value)?)
+        } else if value.get("beam_altitude_angles").is_some() {
+            Ok(serde_json::from_value::<LegacyMetadata>(value)?.try_into()?)
+        } else {
+            Err(ParseMetadataError::UnknownLayout)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{LegacyProfile, ValidOusterConfig};
+
+    use super::*;
+
+    const LEGACY: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/data/os-1-64_v1.14.0_1024x10_legacy.json"
+    ));
+    const NESTED: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/data/os-1-64_v3.0.1_1024x10.json"
+    ));
+
+    #[test]
+    fn detect_layout() {
+        let legacy = OusterConfig::from_metadata_json(LEGACY.as_bytes()).unwrap();
+        let nested = OusterConfig::from_metadata_json(NESTED.as_bytes()).unwrap();
+        assert_eq!(nested.beam_intrinsics, legacy.beam_intrinsics);
+        assert_eq!(
+            nested.lidar_data_format.pixel_shift_by_row,
+            legacy.lidar_data_format.pixel_shift_by_row
+        );
+        assert_eq!(LidarProfile::Legacy, legacy.config_params.udp_profile_lidar);
+        ValidOusterConfig::<LegacyProfile<16, 64>>::try_from(legacy).unwrap();
+    }
+
+    #[test]
+    fn unknown_layout() {
+        assert!(matches!(
+            OusterConfig::from_metadata_json(b"{\"foo\": 1}"),
+            Err(ParseMetadataError::UnknownLayout)
+        ));
+    }
+
+    #[test]
+    fn defaults_without_data_format() {
+        let mut value = serde_json::from_str::<Value>(LEGACY).unwrap();
+        value.as_object_mut().unwrap().remove("data_format");
+        let config = OusterConfig::from_metadata_json(value.to_string().as_bytes()).unwrap();
+        assert_eq!(64, config.lidar_data_format.pixels_per_column);
+        assert_eq!((0, 1023), config.lidar_data_format.column_window);
+        assert_eq!(
+            &[12, 4, -4, -12],
+            &config.lidar_data_format.pixel_shift_by_row[..4]
+        );
+        assert_eq!(15.806, config.beam_intrinsics.beam_to_lidar_transform[3]);
+        assert_eq!(16, config.beam_intrinsics.n_vec());
+    }
+}
@@ -2,7 +2,7 @@ use std::{fmt::Debug, marker::PhantomData};
 
 use serde::{Deserialize, Serialize};
 
-use crate::{InvalidConfig, LidarProfile, Profile};
+use crate::{ConfigField, InvalidConfig, LidarProfile, Profile};
 
 mod valid_window;
 
@@ -10,11 +10,11 @@ pub use valid_window::*;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LidarDataFormat {
-    pub columns_per_packet: u8,
-    pub pixels_per_column: u8,
+    pub column_window: (u16, u16),
     pub columns_per_frame: u16,
+    pub columns_per_packet: u8,
     pub pixel_shift_by_row: Box<[i8]>,
-    pub column_window: (u16, u16),
+    pub pixels_per_column: u8,
     pub udp_profile_lidar: LidarProfile,
 }
 
@@ -32,23 +32,58 @@ impl<T: Profile> ValidLidarDataFormat<T> {
     }
 }
 
+impl LidarDataFormat {
+    /// All values, which don't match the profile or contradict each other
+    pub fn problems<T: Profile>(&self) -> Vec<InvalidConfig> {
+        let mut problems = Vec::new();
+        if self.pixels_per_column as usize != T::LAYERS {
+            problems.push(InvalidConfig::profile_mismatch(
+                ConfigField::PixelsPerColumn,
+                T::LAYERS,
+                self.pixels_per_column,
+            ));
+        }
+        if self.columns_per_packet as usize != T::COLUMNS {
+            problems.push(InvalidConfig::profile_mismatch(
+                ConfigField::ColumnsPerPacket,
+                T::COLUMNS,
+                self.columns_per_packet,
+            ));
+        }
+        if self.pixel_shift_by_row.len() != T::LAYERS {
+            problems.push(InvalidConfig::profile_mismatch(
+                ConfigField::PixelShiftByRow,
+                format_args!("{} entries", T::LAYERS),
+                format_args!("{} entries", self.pixel_shift_by_row.len()),
+            ));
+        }
+        if self.udp_profile_lidar != T::LIDAR_PROFILE {
+            problems.push(InvalidConfig::profile_mismatch(
+                ConfigField::DataFormatUdpProfileLidar,
+                T::LIDAR_PROFILE,
+                self.udp_profile_lidar,
+            ));
+        }
+        if self.column_window.0 >= self.columns_per_frame
+            || self.column_window.1 >= self.columns_per_frame
+        {
+            problems.push(InvalidConfig::inconsistent(
+                ConfigField::ColumnWindow,
+                ConfigField::ColumnsPerFrame,
+                format_args!("columns < {}", self.columns_per_frame),
+                format_args!("{:?}", self.column_window),
+            ));
+        }
+        problems
+    }
+}
+
 impl<T: Profile> TryFrom<LidarDataFormat> for ValidLidarDataFormat<T> {
     type Error = InvalidConfig;
 
     fn try_from(value: LidarDataFormat) -> Result<Self, Self::Error> {
-        if value.pixels_per_column as usize != T::LAYERS {
-            return Err(InvalidConfig::new(format!(
-                "Expected pixels_per_column to be {}, got {}",
-                T::LAYERS,
-                value.pixels_per_column
-            )));
-        }
-        if value.columns_per_packet as usize != T::COLUMNS {
-            return Err(InvalidConfig::new(format!(
-                "Expected columns_per_packet to be {}, got {}",
-                T::LAYERS,
-                value.columns_per_packet
-            )));
+        if let Some(problem) = value.problems::<T>().into_iter().next() {
+            return Err(problem);
         }
 
         let column_window = ValidWindow::from(&value);
@@ -1,6 +1,6 @@
 use std::{fmt::Debug, marker::PhantomData};
 
-use crate::{LidarDataFormat, Profile};
+use crate::{ConfigParams, LidarDataFormat, Profile};
 
 #[derive(Clone)]
 pub struct ValidWindow<TProfile> {
@@ -26,10 +26,20 @@ impl<'a, TProfile: Profile> From<&'a LidarDataFormat> for ValidWindow<TProfile>
     }
 }
 
+/// Allows to set up an aggregator before the lidar_data_format is known
+impl<'a, TProfile: Profile> From<&'a ConfigParams> for ValidWindow<TProfile> {
+    fn from(value: &'a ConfigParams) -> Self {
+        Self::new(
+            value.column_window(),
+            value.lidar_mode.horizontal_resolution(),
+        )
+    }
+}
+
 impl<TProfile: Profile> ValidWindow<TProfile> {
     pub fn new((column_from, column_to): (u16, u16), columns_per_frame: u16) -> Self {
-        let start_measurement_id = (column_from / TProfile::COLUMNS as u16) as u16;
-        let end_measurement_id = (column_to / TProfile::COLUMNS as u16) as u16;
+        let start_measurement_id = column_from / TProfile::COLUMNS as u16;
+        let end_measurement_id = column_to / TProfile::COLUMNS as u16;
 
         let required_measurements = (end_measurement_id
             + if column_from > column_to {
@@ -63,6 +73,10 @@ impl<TProfile: Profile> ValidWindow<TProfile> {
         self.required_measurements * TProfile::COLUMNS
     }
 
+    pub const fn is_empty(&self) -> bool {
+        self.required_measurements == 0
+    }
+
     pub const fn end(&self) -> usize {
         (self.start_measurement_id as usize + self.required_measurements) * TProfile::COLUMNS
     }
@@ -96,7 +110,9 @@ impl<TProfile: Profile> ValidWindow<TProfile> {
 
 #[cfg(test)]
 mod tests {
-    use crate::{DualProfile, LowDataProfile, ValidWindow};
+    use crate::{
+        ConfigParams, ConfigParamsRaw, DualProfile, LidarMode, LowDataProfile, ValidWindow,
+    };
 
     type TestProfile = DualProfile<16, 128>;
 
@@ -144,6 +160,18 @@ mod tests {
         assert_eq!((66, 32), res);
     }
 
+    #[test]
+    fn from_config_params() {
+        let params = ConfigParams::try_from(ConfigParamsRaw {
+            azimuth_window: [90_000, 270_000].try_into().unwrap(),
+            lidar_mode: LidarMode::Mode2048x10,
+            ..Default::default()
+        })
+        .unwrap();
+        let window = ValidWindow::<TestProfile>::from(&params);
+        assert_eq!((512, 1536), (window.start(), window.end()));
+    }
+
     #[test]
     fn small_doesnt_panic() {
         let w = ValidWindow::<LowDataProfile<13, 128>>::new((0, 1), 1024);
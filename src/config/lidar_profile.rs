@@ -1,7 +1,16 @@
-use std::{borrow::Cow, fmt::Debug, str::FromStr};
+use std::{
+    borrow::Cow,
+    fmt::{Debug, Display},
+    str::FromStr,
+};
 
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    ChanField, ConfigField, DualChannel, DualLowChannel, FiveWordPixelChannel, InvalidConfig,
+    LegacyChannel, LowDataChannel, PointInfos, SingleChannel,
+};
+
 #[derive(Debug, Clone, PartialEq, Eq, Copy, Hash, Default)]
 #[non_exhaustive]
 pub enum LidarProfile {
@@ -10,6 +19,52 @@ pub enum LidarProfile {
     DualReturn,
     LowData,
     DualLowData,
+    FiveWordPixel,
+    /// Packet format of firmware < 2.0
+    Legacy,
+}
+
+impl LidarProfile {
+    pub const ALL: [LidarProfile; 6] = [
+        LidarProfile::SingleReturn,
+        LidarProfile::DualReturn,
+        LidarProfile::LowData,
+        LidarProfile::DualLowData,
+        LidarProfile::FiveWordPixel,
+        LidarProfile::Legacy,
+    ];
+
+    /// Fields which can be extracted from packets of this profile
+    pub fn fields(&self) -> &'static [ChanField] {
+        match self {
+            LidarProfile::SingleReturn => SingleChannel::FIELDS,
+            LidarProfile::DualReturn => DualChannel::FIELDS,
+            LidarProfile::LowData => LowDataChannel::FIELDS,
+            LidarProfile::DualLowData => DualLowChannel::FIELDS,
+            LidarProfile::FiveWordPixel => FiveWordPixelChannel::FIELDS,
+            LidarProfile::Legacy => LegacyChannel::FIELDS,
+        }
+    }
+}
+
+impl LidarProfile {
+    /// Name used by the sensor
+    pub fn name(&self) -> &'static str {
+        match self {
+            LidarProfile::SingleReturn => "RNG19_RFL8_SIG16_NIR16",
+            LidarProfile::DualReturn => "RNG19_RFL8_SIG16_NIR16_DUAL",
+            LidarProfile::LowData => "RNG15_RFL8_NIR8",
+            LidarProfile::DualLowData => "FUSA_RNG15_RFL8_NIR8_DUAL",
+            LidarProfile::FiveWordPixel => "FIVE_WORD_PIXEL",
+            LidarProfile::Legacy => "LEGACY",
+        }
+    }
+}
+
+impl Display for LidarProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
 impl Serialize for LidarProfile {
@@ -17,12 +72,7 @@ impl Serialize for LidarProfile {
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(match self {
-            LidarProfile::SingleReturn => "RNG19_RFL8_SIG16_NIR16",
-            LidarProfile::DualReturn => "RNG19_RFL8_SIG16_NIR16_DUAL",
-            LidarProfile::LowData => "RNG15_RFL8_NIR8",
-            LidarProfile::DualLowData => "FUSA_RNG15_RFL8_NIR8_DUAL",
-        })
+        serializer.serialize_str(self.name())
     }
 }
 
@@ -37,7 +87,7 @@ impl<'de> Deserialize<'de> for LidarProfile {
 }
 
 impl FromStr for LidarProfile {
-    type Err = Box<dyn std::error::Error>;
+    type Err = InvalidConfig;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
@@ -45,7 +95,33 @@ impl FromStr for LidarProfile {
             "RNG15_RFL8_NIR8" => Ok(Self::LowData),
             "RNG19_RFL8_SIG16_NIR16_DUAL" => Ok(Self::DualReturn),
             "FUSA_RNG15_RFL8_NIR8_DUAL" => Ok(Self::DualLowData),
-            s => Err(format!("Can't parse '{}' into LidarProfile", s).into()),
+            "FIVE_WORD_PIXEL" => Ok(Self::FiveWordPixel),
+            "LEGACY" => Ok(Self::Legacy),
+            s => Err(InvalidConfig::UnknownValue {
+                field: ConfigField::UdpProfileLidar,
+                actual: s.into(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serde_names_roundtrip() {
+        for profile in LidarProfile::ALL {
+            let json = serde_json::to_string(&profile).unwrap();
+            assert_eq!(profile, serde_json::from_str(&json).unwrap());
         }
+        assert_eq!(
+            LidarProfile::Legacy,
+            serde_json::from_str("\"LEGACY\"").unwrap()
+        );
+        assert_eq!(
+            LidarProfile::FiveWordPixel,
+            serde_json::from_str("\"FIVE_WORD_PIXEL\"").unwrap()
+        );
     }
 }
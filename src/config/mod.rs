@@ -1,4 +1,4 @@
-use std::{fmt::Debug, ops::Deref};
+use std::{fmt::Debug, ops::Deref, time::Duration};
 
 use serde::Deserialize;
 
@@ -6,15 +6,23 @@ use crate::Profile;
 
 mod beam_intrinsics;
 mod config_params;
+mod invalid_config;
+mod json_number;
+mod legacy_metadata;
 mod lidar_data_format;
 mod lidar_profile;
+mod sensor_metadata;
 
 pub use beam_intrinsics::*;
 pub use config_params::*;
+pub use invalid_config::*;
+pub use legacy_metadata::*;
 pub use lidar_data_format::*;
 pub use lidar_profile::*;
+pub use sensor_metadata::*;
 
 /// Not Serializable, as it doesn't contain all values from the spec and won't be the same as when it's read again
+/// Use SensorMetadata to read and write the complete metadata
 #[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct OusterConfig {
     pub beam_intrinsics: BeamIntrinsics,
@@ -36,6 +44,19 @@ impl<TProfile> Deref for ValidOusterConfig<TProfile> {
     }
 }
 
+impl<TProfile: Profile> ValidOusterConfig<TProfile> {
+    /// Lidar packets the sensor sends per second for the configured column window
+    pub fn expected_packets_per_second(&self) -> f32 {
+        let packets_per_frame = self.lidar_data_format.column_window.required_measurements;
+        packets_per_frame as f32 * self.config_params.lidar_mode.frequency_hz() as f32
+    }
+
+    /// Time of the measurement relative to the start of the rotation, used for deskewing
+    pub fn measurement_time_offset(&self, measurement_id: u16) -> Duration {
+        self.config_params.lidar_mode.column_period() * measurement_id as u32
+    }
+}
+
 pub struct ValidOperationConfig<TProfile> {
     pub beam_intrinsics: BeamIntrinsics,
     pub lidar_data_format: ValidLidarDataFormat<TProfile>,
@@ -47,10 +68,60 @@ impl<TProfile> ValidOperationConfig<TProfile> {
     }
 }
 
+impl OusterConfig {
+    /// All values, which contradict each other or don't match the profile, in the order they are checked by try_into
+    pub fn problems<T: Profile>(&self) -> Vec<InvalidConfig> {
+        let params = &self.config_params;
+        let format = &self.lidar_data_format;
+        let mut problems = Vec::new();
+        if params.lidar_mode.horizontal_resolution() != format.columns_per_frame {
+            problems.push(InvalidConfig::inconsistent(
+                ConfigField::ColumnsPerFrame,
+                ConfigField::LidarMode,
+                params.lidar_mode.horizontal_resolution(),
+                format.columns_per_frame,
+            ));
+        }
+        if params.udp_profile_lidar != format.udp_profile_lidar {
+            problems.push(InvalidConfig::inconsistent(
+                ConfigField::DataFormatUdpProfileLidar,
+                ConfigField::UdpProfileLidar,
+                params.udp_profile_lidar,
+                format.udp_profile_lidar,
+            ));
+        }
+        for (field, len) in [
+            (
+                ConfigField::BeamAltitudeAngles,
+                self.beam_intrinsics.beam_altitude_angles.len(),
+            ),
+            (
+                ConfigField::BeamAzimuthAngles,
+                self.beam_intrinsics.beam_azimuth_angles.len(),
+            ),
+        ] {
+            if len != T::LAYERS {
+                problems.push(InvalidConfig::profile_mismatch(
+                    field,
+                    format_args!("{} entries", T::LAYERS),
+                    format_args!("{len} entries"),
+                ));
+            }
+        }
+        problems.extend(check_column_window(params, format).err());
+        problems.extend(format.problems::<T>());
+        problems
+    }
+}
+
 impl<T: Profile> TryFrom<OusterConfig> for ValidOusterConfig<T> {
     type Error = InvalidConfig;
 
     fn try_from(value: OusterConfig) -> Result<Self, Self::Error> {
+        if let Some(problem) = value.problems::<T>().into_iter().next() {
+            return Err(problem);
+        }
+
         Ok(Self {
             config_params: value.config_params,
             valid_operation: ValidOperationConfig {
@@ -61,16 +132,167 @@ impl<T: Profile> TryFrom<OusterConfig> for ValidOusterConfig<T> {
     }
 }
 
-#[derive(Debug, thiserror::Error)]
-#[error("{reason}")]
-pub struct InvalidConfig {
-    reason: String,
+/// The column_window is rounded to whole columns by the sensor
+const COLUMN_WINDOW_TOLERANCE: i64 = 2;
+
+fn check_column_window(
+    params: &ConfigParams,
+    format: &LidarDataFormat,
+) -> Result<(), InvalidConfig> {
+    let columns_per_frame = format.columns_per_frame as i64;
+    let expected = params.column_window();
+    let distance = |a: u16, b: u16| {
+        let d = (a as i64 - b as i64).rem_euclid(columns_per_frame);
+        d.min(columns_per_frame - d)
+    };
+    if distance(expected.0, format.column_window.0) > COLUMN_WINDOW_TOLERANCE
+        || distance(expected.1, format.column_window.1) > COLUMN_WINDOW_TOLERANCE
+    {
+        return Err(InvalidConfig::inconsistent(
+            ConfigField::ColumnWindow,
+            ConfigField::AzimuthWindow,
+            format_args!("{expected:?}"),
+            format_args!("{:?}", format.column_window),
+        ));
+    }
+    Ok(())
 }
 
-impl InvalidConfig {
-    fn new(reason: impl Into<String>) -> Self {
-        Self {
-            reason: reason.into(),
-        }
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use crate::{DualProfile, SingleProfile};
+
+    use super::*;
+
+    const METADATA: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/data/os-1-64_v3.0.1_1024x10.json"
+    ));
+
+    fn validate_modified(
+        modify: impl FnOnce(&mut Value),
+    ) -> Result<ValidOusterConfig<DualProfile<16, 64>>, InvalidConfig> {
+        let mut value = serde_json::from_str::<Value>(METADATA).unwrap();
+        modify(&mut value);
+        serde_json::from_value::<OusterConfig>(value)
+            .unwrap()
+            .try_into()
+    }
+
+    #[test]
+    fn valid_metadata() {
+        validate_modified(|_| {}).unwrap();
+    }
+
+    #[test]
+    fn timing() {
+        let config = validate_modified(|_| {}).unwrap();
+        assert_eq!(640., config.expected_packets_per_second());
+        assert_eq!(
+            Duration::from_nanos(97_656 * 512),
+            config.measurement_time_offset(512)
+        );
+    }
+
+    #[test]
+    fn lidar_mode_mismatch() {
+        let err = validate_modified(|v| v["config_params"]["lidar_mode"] = "2048x10".into())
+            .err()
+            .unwrap();
+        assert_eq!(
+            InvalidConfig::Inconsistent {
+                field: ConfigField::ColumnsPerFrame,
+                related: ConfigField::LidarMode,
+                expected: "2048".into(),
+                actual: "1024".into()
+            },
+            err
+        );
+        assert_eq!("lidar_data_format.columns_per_frame", err.field().path());
+    }
+
+    #[test]
+    fn profile_mismatch_between_sections() {
+        let err = validate_modified(|v| {
+            v["config_params"]["udp_profile_lidar"] = "RNG19_RFL8_SIG16_NIR16".into()
+        });
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn profile_mismatch_with_type() {
+        let config = serde_json::from_str::<OusterConfig>(METADATA).unwrap();
+        let err = ValidOusterConfig::<SingleProfile<16, 64>>::try_from(config)
+            .err()
+            .unwrap();
+        assert!(
+            matches!(err, InvalidConfig::ProfileMismatch { .. }),
+            "{err}"
+        );
+        assert_eq!(ConfigField::DataFormatUdpProfileLidar, err.field());
+        assert_eq!(Some("RNG19_RFL8_SIG16_NIR16"), err.expected());
+        assert_eq!("RNG19_RFL8_SIG16_NIR16_DUAL", err.actual());
+    }
+
+    #[test]
+    fn beam_count_mismatch() {
+        let err = validate_modified(|v| {
+            v["beam_intrinsics"]["beam_azimuth_angles"]
+                .as_array_mut()
+                .unwrap()
+                .pop();
+        });
+        assert!(err.is_err());
+        let err = validate_modified(|v| {
+            v["lidar_data_format"]["pixel_shift_by_row"]
+                .as_array_mut()
+                .unwrap()
+                .pop();
+        });
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn column_window_mismatch() {
+        let err = validate_modified(|v| {
+            v["config_params"]["azimuth_window"] = serde_json::json!([90_000, 270_000])
+        });
+        assert!(err.is_err());
+        validate_modified(|v| {
+            v["config_params"]["azimuth_window"] = serde_json::json!([90_000, 270_000]);
+            v["lidar_data_format"]["column_window"] = serde_json::json!([256, 767]);
+        })
+        .unwrap();
+        validate_modified(|v| {
+            v["config_params"]["azimuth_window"] = serde_json::json!([270_000, 90_000]);
+            v["lidar_data_format"]["column_window"] = serde_json::json!([768, 255]);
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn all_problems() {
+        let mut value = serde_json::from_str::<Value>(METADATA).unwrap();
+        value["config_params"]["lidar_mode"] = "2048x10".into();
+        value["beam_intrinsics"]["beam_azimuth_angles"]
+            .as_array_mut()
+            .unwrap()
+            .pop();
+        let config = serde_json::from_value::<OusterConfig>(value).unwrap();
+        assert_eq!(
+            vec![
+                ConfigField::ColumnsPerFrame,
+                ConfigField::BeamAzimuthAngles,
+                ConfigField::DataFormatUdpProfileLidar
+            ],
+            config
+                .problems::<SingleProfile<16, 64>>()
+                .iter()
+                .map(InvalidConfig::field)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(2, config.problems::<DualProfile<16, 64>>().len());
     }
 }
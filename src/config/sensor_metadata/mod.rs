@@ -0,0 +1,223 @@
+use std::ops::{Deref, DerefMut};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    config::json_number::serialize_f64s, BeamIntrinsics, ConfigParams, InvalidConfig,
+    LidarDataFormat, OusterConfig, Profile, ValidOusterConfig,
+};
+
+/// Complete content of /api/v1/sensor/metadata
+/// Fields which are unknown to this crate are kept, so the metadata can be written again without loosing information.
+/// Use to_json_string() to get the same key order as the sensor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SensorMetadata {
+    pub beam_intrinsics: WithUnknownFields<BeamIntrinsics>,
+    pub calibration_status: CalibrationStatus,
+    pub config_params: WithUnknownFields<ConfigParams>,
+    pub imu_intrinsics: ImuIntrinsics,
+    pub lidar_data_format: WithUnknownFields<LidarDataFormat>,
+    pub lidar_intrinsics: LidarIntrinsics,
+    pub sensor_info: SensorInfo,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_data: Option<String>,
+    #[serde(flatten)]
+    pub unknown_fields: Map<String, Value>,
+}
+
+impl SensorMetadata {
+    /// Keys are sorted alphabetically like in the metadata of the sensor
+    pub fn to_json_string(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&serde_json::to_value(self)?)
+    }
+
+    pub fn to_json_string_pretty(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&serde_json::to_value(self)?)
+    }
+}
+
+impl From<SensorMetadata> for OusterConfig {
+    fn from(value: SensorMetadata) -> Self {
+        OusterConfig {
+            beam_intrinsics: value.beam_intrinsics.inner,
+            config_params: value.config_params.inner,
+            lidar_data_format: value.lidar_data_format.inner,
+        }
+    }
+}
+
+impl<T: Profile> TryFrom<SensorMetadata> for ValidOusterConfig<T> {
+    type Error = InvalidConfig;
+
+    fn try_from(value: SensorMetadata) -> Result<Self, Self::Error> {
+        OusterConfig::from(value).try_into()
+    }
+}
+
+/// Section of the metadata, which keeps all fields unknown to T
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WithUnknownFields<T> {
+    #[serde(flatten)]
+    pub inner: T,
+    #[serde(flatten)]
+    pub unknown_fields: Map<String, Value>,
+}
+
+impl<T> From<T> for WithUnknownFields<T> {
+    fn from(inner: T) -> Self {
+        Self {
+            inner,
+            unknown_fields: Map::new(),
+        }
+    }
+}
+
+impl<T> Deref for WithUnknownFields<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<T> DerefMut for WithUnknownFields<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inner
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SensorInfo {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub build_date: Option<String>,
+    pub build_rev: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_rev: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initialization_id: Option<u64>,
+    pub prod_line: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prod_pn: Option<String>,
+    pub prod_sn: String,
+    pub status: String,
+    #[serde(flatten)]
+    pub unknown_fields: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImuIntrinsics {
+    #[serde(serialize_with = "serialize_f64s")]
+    pub imu_to_sensor_transform: [f64; 16],
+    #[serde(flatten)]
+    pub unknown_fields: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LidarIntrinsics {
+    #[serde(serialize_with = "serialize_f64s")]
+    pub lidar_to_sensor_transform: [f64; 16],
+    #[serde(flatten)]
+    pub unknown_fields: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CalibrationStatus {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reflectivity: Option<CalibrationEntry>,
+    #[serde(flatten)]
+    pub unknown_fields: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CalibrationEntry {
+    pub timestamp: String,
+    pub valid: bool,
+    #[serde(flatten)]
+    pub unknown_fields: Map<String, Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DualProfile, LidarMode};
+
+    use super::*;
+
+    const METADATA: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/data/os-1-64_v3.0.1_1024x10.json"
+    ));
+
+    #[test]
+    fn lossless_roundtrip() {
+        let metadata = serde_json::from_str::<SensorMetadata>(METADATA).unwrap();
+        assert_eq!(METADATA.trim_end(), metadata.to_json_string().unwrap());
+        assert_eq!(
+            metadata,
+            serde_json::from_str(&metadata.to_json_string_pretty().unwrap()).unwrap()
+        );
+    }
+
+    /// Pretty printed with the key order of the sdk, which differs from the sensor
+    #[test]
+    fn equivalent_roundtrip_of_pretty_metadata() {
+        let pretty = include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/data/os-1-64_v3.0.1_1024x10_pretty.json"
+        ));
+        let metadata = serde_json::from_str::<SensorMetadata>(pretty).unwrap();
+        let original = serde_json::from_str::<Value>(pretty).unwrap();
+        assert_eq!(
+            serde_json::to_string(&original).unwrap(),
+            metadata.to_json_string().unwrap()
+        );
+        assert_eq!(
+            serde_json::to_string_pretty(&original).unwrap(),
+            metadata.to_json_string_pretty().unwrap()
+        );
+        assert!(metadata.unknown_fields.contains_key("ouster-sdk"));
+        assert_eq!(
+            OusterConfig::from(metadata),
+            serde_json::from_str::<SensorMetadata>(METADATA)
+                .unwrap()
+                .into()
+        );
+    }
+
+    #[test]
+    fn typed_fields() {
+        let metadata = serde_json::from_str::<SensorMetadata>(METADATA).unwrap();
+        assert_eq!("OS-1-64", metadata.sensor_info.prod_line);
+        assert_eq!(Some(7109750), metadata.sensor_info.initialization_id);
+        assert_eq!(-11.775, metadata.imu_intrinsics.imu_to_sensor_transform[7]);
+        assert_eq!(
+            36.18,
+            metadata.lidar_intrinsics.lidar_to_sensor_transform[11]
+        );
+        assert!(
+            metadata
+                .calibration_status
+                .reflectivity
+                .as_ref()
+                .unwrap()
+                .valid
+        );
+        assert_eq!(Some(""), metadata.user_data.as_deref());
+        assert_eq!(Some(7503), metadata.config_params.udp_port_imu);
+        assert!(metadata.config_params.unknown_fields.is_empty());
+    }
+
+    #[test]
+    fn edit_and_derive_config() {
+        let mut metadata = serde_json::from_str::<SensorMetadata>(METADATA).unwrap();
+        metadata.user_data = Some("edited".into());
+        metadata.lidar_data_format.pixel_shift_by_row[0] = 11;
+        let json = metadata.to_json_string().unwrap();
+        assert!(json.contains(r#""user_data":"edited""#));
+        assert!(json.contains(r#""pixel_shift_by_row":[11,4,"#));
+
+        let config = OusterConfig::from(metadata.clone());
+        assert_eq!(LidarMode::Mode1024x10, config.config_params.lidar_mode);
+        ValidOusterConfig::<DualProfile<16, 64>>::try_from(metadata).unwrap();
+    }
+}
@@ -0,0 +1,417 @@
+//! Compressed archive of destaggered field images
+//!
+//! `"OFAR" | version: u8 | block (metadata json) | block (frame) ...`
+//!
+//! Every block is a zlib stream prefixed by its compressed size as u32.
+//! A frame contains `frame_id: u16 | width: u32 | height: u32 | columns: u32 | timestamps: [u64; columns] |
+//! measurement_ids: [u16; columns] | field count: u8` followed by the fields
+//! `name length: u8 | name | bytes per pixel: u8 | pixels`.
+//! The pixels of every row are delta encoded, which improves the compression of range images.
+//! All integers are little endian.
+
+use std::io::{self, Read, Write};
+
+use crate::{
+    packet::ColumnHeader, ChanField, CompleteData, FieldBuffer, FieldImage, OusterConfig,
+    ParseMetadataError, Profile, RangeImage, ValidOperationConfig,
+};
+
+const MAGIC: &[u8; 4] = b"OFAR";
+const VERSION: u8 = 1;
+const COMPRESSION_LEVEL: u8 = 6;
+
+#[derive(Debug, thiserror::Error)]
+pub enum FieldArchiveError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Invalid field archive: {0}")]
+    Format(&'static str),
+    #[error("Invalid sensor metadata: {0}")]
+    Metadata(#[from] ParseMetadataError),
+}
+
+/// Destaggered frame of a field archive, equivalent to the images of a [CompleteData]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldArchiveFrame {
+    pub frame_id: u16,
+    /// Nanoseconds per column
+    pub timestamps: Vec<u64>,
+    pub measurement_ids: Vec<u16>,
+    pub fields: Vec<(ChanField, FieldBuffer)>,
+}
+
+impl FieldArchiveFrame {
+    pub fn field(&self, field: ChanField) -> Option<&FieldBuffer> {
+        self.fields
+            .iter()
+            .find(|(x, _)| *x == field)
+            .map(|(_, image)| image)
+    }
+
+    pub fn range_image(&self) -> Option<RangeImage> {
+        self.field(ChanField::Range).map(FieldBuffer::to_u16)
+    }
+}
+
+/// Writes all fields of frames together with the sensor metadata, which is needed to reconstruct the points
+pub struct FieldArchiveWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> FieldArchiveWriter<W> {
+    /// `metadata_json` is stored as is, so readers can use it to process the frames
+    pub fn new(mut writer: W, metadata_json: &str) -> io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        write_block(&mut writer, metadata_json.as_bytes())?;
+        Ok(Self { writer })
+    }
+
+    /// Stores all fields of the profile as destaggered images
+    pub fn write_frame<TProfile: Profile>(
+        &mut self,
+        frame: &CompleteData<TProfile>,
+        config: &ValidOperationConfig<TProfile>,
+    ) -> io::Result<()> {
+        let fields = ChanField::ALL
+            .iter()
+            .filter_map(|field| frame.field(config, *field).map(|image| (*field, image)))
+            .collect::<Vec<_>>();
+        let headers = frame.column_headers().collect::<Vec<_>>();
+        let width = config.lidar_data_format.column_window.len();
+
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&frame.frame_id().to_le_bytes());
+        buf.extend_from_slice(&(width as u32).to_le_bytes());
+        buf.extend_from_slice(&(TProfile::LAYERS as u32).to_le_bytes());
+        buf.extend_from_slice(&(headers.len() as u32).to_le_bytes());
+        for header in &headers {
+            buf.extend_from_slice(&(header.timestamp().as_nanos() as u64).to_le_bytes());
+        }
+        for header in &headers {
+            buf.extend_from_slice(&header.measurement_id().to_le_bytes());
+        }
+        buf.push(fields.len() as u8);
+        for (field, image) in &fields {
+            let name = field.name();
+            buf.push(name.len() as u8);
+            buf.extend_from_slice(name.as_bytes());
+            match image {
+                FieldBuffer::U8(image) => {
+                    buf.push(1);
+                    for row in image.as_slice().chunks_exact(image.width().max(1)) {
+                        let mut previous = 0;
+                        for x in row {
+                            buf.push(x.wrapping_sub(previous));
+                            previous = *x;
+                        }
+                    }
+                }
+                FieldBuffer::U16(image) => {
+                    buf.push(2);
+                    for row in image.as_slice().chunks_exact(image.width().max(1)) {
+                        let mut previous = 0;
+                        for x in row {
+                            buf.extend_from_slice(&x.wrapping_sub(previous).to_le_bytes());
+                            previous = *x;
+                        }
+                    }
+                }
+                FieldBuffer::U32(image) => {
+                    buf.push(4);
+                    for row in image.as_slice().chunks_exact(image.width().max(1)) {
+                        let mut previous = 0;
+                        for x in row {
+                            buf.extend_from_slice(&x.wrapping_sub(previous).to_le_bytes());
+                            previous = *x;
+                        }
+                    }
+                }
+            }
+        }
+        write_block(&mut self.writer, &buf)
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Iterates over the frames of an archive
+pub struct FieldArchiveReader<R: Read> {
+    reader: R,
+    metadata_json: String,
+}
+
+impl<R: Read> FieldArchiveReader<R> {
+    pub fn new(mut reader: R) -> Result<Self, FieldArchiveError> {
+        let mut header = [0; 5];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(FieldArchiveError::Format("not a field archive"));
+        }
+        if header[4] != VERSION {
+            return Err(FieldArchiveError::Format("unsupported version"));
+        }
+        let metadata = read_block(&mut reader)?.ok_or(FieldArchiveError::Format("no metadata"))?;
+        Ok(Self {
+            reader,
+            metadata_json: String::from_utf8(metadata)
+                .map_err(|_| FieldArchiveError::Format("metadata isn't utf8"))?,
+        })
+    }
+
+    /// Sensor metadata as passed to the writer
+    pub fn metadata_json(&self) -> &str {
+        &self.metadata_json
+    }
+
+    pub fn ouster_config(&self) -> Result<OusterConfig, FieldArchiveError> {
+        Ok(OusterConfig::from_metadata_json(
+            self.metadata_json.as_bytes(),
+        )?)
+    }
+}
+
+impl<R: Read> Iterator for FieldArchiveReader<R> {
+    type Item = Result<FieldArchiveFrame, FieldArchiveError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        read_block(&mut self.reader)
+            .transpose()
+            .map(|block| parse_frame(&block?))
+    }
+}
+
+fn write_block(writer: &mut impl Write, data: &[u8]) -> io::Result<()> {
+    let compressed = miniz_oxide::deflate::compress_to_vec_zlib(data, COMPRESSION_LEVEL);
+    writer.write_all(&(compressed.len() as u32).to_le_bytes())?;
+    writer.write_all(&compressed)
+}
+
+/// None at the end of the archive
+fn read_block(reader: &mut impl Read) -> Result<Option<Vec<u8>>, FieldArchiveError> {
+    let mut size = [0; 4];
+    match reader.read_exact(&mut size) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.into()),
+    }
+    let mut compressed = vec![0; u32::from_le_bytes(size) as usize];
+    reader.read_exact(&mut compressed)?;
+    miniz_oxide::inflate::decompress_to_vec_zlib(&compressed)
+        .map(Some)
+        .map_err(|_| FieldArchiveError::Format("corrupted block"))
+}
+
+struct Cursor<'a>(&'a [u8]);
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], FieldArchiveError> {
+        if len > self.0.len() {
+            return Err(FieldArchiveError::Format("frame is truncated"));
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, FieldArchiveError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16s(&mut self, len: usize) -> Result<Vec<u16>, FieldArchiveError> {
+        Ok(self
+            .take(len * 2)?
+            .chunks_exact(2)
+            .map(|x| u16::from_le_bytes([x[0], x[1]]))
+            .collect())
+    }
+
+    fn u32s(&mut self, len: usize) -> Result<Vec<u32>, FieldArchiveError> {
+        Ok(self
+            .take(len * 4)?
+            .chunks_exact(4)
+            .map(|x| u32::from_le_bytes(x.try_into().expect("4 bytes")))
+            .collect())
+    }
+
+    fn u32(&mut self) -> Result<u32, FieldArchiveError> {
+        Ok(u32::from_le_bytes(
+            self.take(4)?.try_into().expect("4 bytes"),
+        ))
+    }
+}
+
+fn parse_frame(data: &[u8]) -> Result<FieldArchiveFrame, FieldArchiveError> {
+    let mut cursor = Cursor(data);
+    let frame_id = cursor.u16s(1)?[0];
+    let width = cursor.u32()? as usize;
+    let height = cursor.u32()? as usize;
+    let columns = cursor.u32()? as usize;
+    let timestamps = cursor
+        .take(columns * 8)?
+        .chunks_exact(8)
+        .map(|x| u64::from_le_bytes(x.try_into().expect("8 bytes")))
+        .collect();
+    let measurement_ids = cursor.u16s(columns)?;
+
+    let mut fields = Vec::new();
+    for _ in 0..cursor.u8()? {
+        let name_len = cursor.u8()? as usize;
+        let name = std::str::from_utf8(cursor.take(name_len)?)
+            .map_err(|_| FieldArchiveError::Format("field name isn't utf8"))?;
+        let field = name
+            .parse::<ChanField>()
+            .map_err(|_| FieldArchiveError::Format("unknown field"))?;
+        let image = match cursor.u8()? {
+            1 => FieldBuffer::U8(FieldImage::new(
+                width,
+                height,
+                undo_delta(cursor.take(width * height)?.to_vec(), width),
+            )),
+            2 => FieldBuffer::U16(FieldImage::new(
+                width,
+                height,
+                undo_delta(cursor.u16s(width * height)?, width),
+            )),
+            4 => FieldBuffer::U32(FieldImage::new(
+                width,
+                height,
+                undo_delta(cursor.u32s(width * height)?, width),
+            )),
+            _ => return Err(FieldArchiveError::Format("unsupported pixel size")),
+        };
+        fields.push((field, image));
+    }
+
+    Ok(FieldArchiveFrame {
+        frame_id,
+        timestamps,
+        measurement_ids,
+        fields,
+    })
+}
+
+fn undo_delta<T: WrappingAdd>(mut data: Vec<T>, width: usize) -> Vec<T> {
+    for row in data.chunks_exact_mut(width.max(1)) {
+        for i in 1..row.len() {
+            row[i] = row[i].wrapping_add(row[i - 1]);
+        }
+    }
+    data
+}
+
+trait WrappingAdd: Copy {
+    fn wrapping_add(self, other: Self) -> Self;
+}
+
+impl WrappingAdd for u8 {
+    fn wrapping_add(self, other: Self) -> Self {
+        u8::wrapping_add(self, other)
+    }
+}
+
+impl WrappingAdd for u16 {
+    fn wrapping_add(self, other: Self) -> Self {
+        u16::wrapping_add(self, other)
+    }
+}
+
+impl WrappingAdd for u32 {
+    fn wrapping_add(self, other: Self) -> Self {
+        u32::wrapping_add(self, other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        test_utils::{complete_frame, test_config},
+        Dual64OusterPacket, DualProfile, LidarProfile,
+    };
+
+    use super::*;
+
+    const METADATA: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/data/os-1-64_v3.0.1_1024x10.json"
+    ));
+
+    #[test]
+    fn round_trip() {
+        let config = test_config::<DualProfile<16, 64>>(LidarProfile::DualReturn, &[0; 64]);
+        let mut writer = FieldArchiveWriter::new(Vec::new(), METADATA).unwrap();
+        let mut expected = Vec::new();
+        for offset in 0..2 {
+            let frame = complete_frame(&config, |frame_id, measurement_id| {
+                let mut x = Dual64OusterPacket::default();
+                x.header.frame_id = frame_id + offset;
+                for (i, col) in x.columns.iter_mut().enumerate() {
+                    let measurement_id = measurement_id + i as u16;
+                    col.channels_header.measurement_id = measurement_id;
+                    col.channels_header
+                        .set_timestamp(Duration::from_micros(measurement_id as u64 + 1));
+                    for (row, ch) in col.channels.iter_mut().enumerate() {
+                        ch.info_ret1.raw = (row as u32) << 24 | (measurement_id as u32 * 60 + 7);
+                        ch.info_ret2.raw = 60_000 + offset as u32;
+                        ch.signal_ret_1 = 500 - row as u16;
+                        ch.nir = 0x1234 + row as u16;
+                    }
+                }
+                x
+            });
+            writer.write_frame(&frame, &config).unwrap();
+            expected.push(frame);
+        }
+        let data = writer.finish().unwrap();
+
+        let reader = FieldArchiveReader::new(data.as_slice()).unwrap();
+        assert_eq!(METADATA, reader.metadata_json());
+        reader.ouster_config().unwrap();
+        let frames = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(2, frames.len());
+        for (frame, complete) in frames.iter().zip(&expected) {
+            assert_eq!(complete.frame_id(), frame.frame_id);
+            assert_eq!(Some(complete.range_image(&config)), frame.range_image());
+            for field in LidarProfile::DualReturn.fields() {
+                assert_eq!(
+                    complete.field(&config, *field).as_ref(),
+                    frame.field(*field),
+                    "{field:?}"
+                );
+            }
+            // Nir keeps the 16 bit resolution of the packet
+            assert_eq!(
+                Some(0x1234 + 5),
+                frame.field(ChanField::Nir).and_then(|nir| match nir {
+                    FieldBuffer::U16(image) => image.get(5, 3).copied(),
+                    _ => None,
+                })
+            );
+            assert_eq!(18_000, frame.timestamps[17]);
+            assert_eq!(17, frame.measurement_ids[17]);
+        }
+    }
+
+    #[test]
+    fn invalid_archive() {
+        assert!(matches!(
+            FieldArchiveReader::new(&b"OSFA\x01"[..]),
+            Err(FieldArchiveError::Format(_))
+        ));
+        let mut data = FieldArchiveWriter::new(Vec::new(), METADATA)
+            .unwrap()
+            .finish()
+            .unwrap();
+        data.extend_from_slice(&[4, 0, 0, 0, 1, 2, 3, 4]);
+        let mut reader = FieldArchiveReader::new(data.as_slice()).unwrap();
+        assert!(matches!(
+            reader.next(),
+            Some(Err(FieldArchiveError::Format(_)))
+        ));
+    }
+}
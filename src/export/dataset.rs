@@ -0,0 +1,208 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use crate::{
+    Aggregator, CartesianIterator, CompleteData, PcapError, PcapReader, Profile,
+    ValidOperationConfig,
+};
+
+use super::ExportPoint;
+
+const MANIFEST_FILE_NAME: &str = "manifest.csv";
+
+/// Binary layouts of training datasets, both contain only the primary return
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatasetFormat {
+    /// `.bin` with float32 x, y, z, intensity, where intensity is the reflectivity scaled to 0..1
+    Kitti,
+    /// `.pcd.bin` with float32 x, y, z, intensity, ring, where intensity is the reflectivity 0..255
+    NuScenes,
+}
+
+impl DatasetFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            DatasetFormat::Kitti => "bin",
+            DatasetFormat::NuScenes => "pcd.bin",
+        }
+    }
+
+    pub fn write(&self, mut writer: impl Write, points: &[ExportPoint]) -> io::Result<()> {
+        let floats_per_point = match self {
+            DatasetFormat::Kitti => 4,
+            DatasetFormat::NuScenes => 5,
+        };
+        let mut buf = Vec::with_capacity(points.len() * floats_per_point * 4);
+        for p in points.iter().filter(|p| p.return_index == 0) {
+            let intensity = match self {
+                DatasetFormat::Kitti => p.reflectivity as f32 / u8::MAX as f32,
+                DatasetFormat::NuScenes => p.reflectivity as f32,
+            };
+            let values = [p.xyz.0, p.xyz.1, p.xyz.2, intensity, p.ring as f32];
+            for value in &values[..floats_per_point] {
+                buf.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        writer.write_all(&buf)
+    }
+}
+
+/// Line of the manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    pub frame_id: u16,
+    /// Timestamp of the first received column
+    pub timestamp: Duration,
+    /// Relative to the directory of the dataset
+    pub file_name: String,
+}
+
+/// Writes one file per frame into a directory and a `manifest.csv` with
+/// `frame_id,timestamp_ns,file_name` per frame on [DatasetWriter::finish]
+pub struct DatasetWriter {
+    dir: PathBuf,
+    format: DatasetFormat,
+    entries: Vec<ManifestEntry>,
+}
+
+impl DatasetWriter {
+    /// Creates the directory, if it doesn't exist
+    pub fn new(dir: impl AsRef<Path>, format: DatasetFormat) -> io::Result<Self> {
+        std::fs::create_dir_all(dir.as_ref())?;
+        Ok(Self {
+            dir: dir.as_ref().to_owned(),
+            format,
+            entries: Vec::new(),
+        })
+    }
+
+    pub fn entries(&self) -> &[ManifestEntry] {
+        &self.entries
+    }
+
+    /// Files are numbered consecutively, as the frame id wraps around
+    /// The CartesianIterator has to be created from the same config
+    pub fn write_frame<TProfile: Profile, TSlice: AsRef<[(f32, f32)]>>(
+        &mut self,
+        frame: &CompleteData<TProfile>,
+        config: &ValidOperationConfig<TProfile>,
+        cartesian: CartesianIterator<TProfile, TSlice>,
+    ) -> io::Result<&ManifestEntry> {
+        let file_name = format!("{:06}.{}", self.entries.len(), self.format.extension());
+        let points = frame.export_points(config, cartesian).collect::<Vec<_>>();
+        let mut writer = BufWriter::new(File::create(self.dir.join(&file_name))?);
+        self.format.write(&mut writer, &points)?;
+        writer.flush()?;
+        self.entries.push(ManifestEntry {
+            frame_id: frame.frame_id(),
+            timestamp: frame.timestamp(),
+            file_name,
+        });
+        Ok(self.entries.last().expect("Just pushed"))
+    }
+
+    /// Writes every frame of the recording, returns the number of written frames
+    /// Datagrams, which are sent to another port than `udp_port_lidar`, are ignored
+    pub fn write_pcap<TProfile: Profile, R: Read>(
+        &mut self,
+        pcap: &mut PcapReader<R>,
+        config: &ValidOperationConfig<TProfile>,
+        udp_port_lidar: Option<u16>,
+    ) -> Result<usize, PcapError> {
+        let mut aggregator = Aggregator::new(&config.lidar_data_format.column_window);
+        let cartesian = CartesianIterator::new_cheap_cloneable_from_config(config);
+        let mut count = 0;
+        while let Some(frame) = pcap.next_frame(&mut aggregator, udp_port_lidar)? {
+            self.write_frame(&frame, config, cartesian.clone())?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Writes the manifest
+    pub fn finish(self) -> io::Result<Vec<ManifestEntry>> {
+        let mut manifest = BufWriter::new(File::create(self.dir.join(MANIFEST_FILE_NAME))?);
+        writeln!(manifest, "frame_id,timestamp_ns,file_name")?;
+        for entry in &self.entries {
+            writeln!(
+                manifest,
+                "{},{},{}",
+                entry.frame_id,
+                entry.timestamp.as_nanos(),
+                entry.file_name
+            )?;
+        }
+        manifest.flush()?;
+        Ok(self.entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_utils::{frame_packets, pcap_file, test_config},
+        DualProfile, LidarProfile,
+    };
+
+    use super::*;
+
+    #[test]
+    fn formats() {
+        let points = crate::export::tests::test_points();
+        let mut kitti = Vec::new();
+        DatasetFormat::Kitti.write(&mut kitti, &points).unwrap();
+        // The second return is skipped
+        assert_eq!(2 * 16, kitti.len());
+        assert_eq!(
+            10. / 255.,
+            f32::from_le_bytes(kitti[12..16].try_into().unwrap())
+        );
+
+        let mut nuscenes = Vec::new();
+        DatasetFormat::NuScenes
+            .write(&mut nuscenes, &points)
+            .unwrap();
+        assert_eq!(2 * 20, nuscenes.len());
+        assert_eq!(
+            11.,
+            f32::from_le_bytes(nuscenes[32..36].try_into().unwrap())
+        );
+        assert_eq!(1., f32::from_le_bytes(nuscenes[36..40].try_into().unwrap()));
+    }
+
+    #[test]
+    fn pcap_to_dataset() {
+        let config = test_config::<DualProfile<16, 64>>(LidarProfile::DualReturn, &[0; 64]);
+        let packets = frame_packets::<DualProfile<16, 64>>(3);
+        let data = pcap_file(
+            &packets
+                .iter()
+                .map(|x| (Duration::ZERO, 7502, x.as_slice()))
+                .collect::<Vec<_>>(),
+        );
+
+        let dir = std::env::temp_dir().join(format!("ouster-dataset-{}", std::process::id()));
+        let mut writer = DatasetWriter::new(&dir, DatasetFormat::Kitti).unwrap();
+        let mut pcap = PcapReader::new(data.as_slice()).unwrap();
+        assert_eq!(
+            3,
+            writer.write_pcap(&mut pcap, &config, Some(7502)).unwrap()
+        );
+        writer.finish().unwrap();
+
+        let manifest = std::fs::read_to_string(dir.join(MANIFEST_FILE_NAME)).unwrap();
+        assert_eq!(
+            "frame_id,timestamp_ns,file_name\n0,1000000,000000.bin\n1,101000000,000001.bin\n2,201000000,000002.bin\n",
+            manifest
+        );
+        assert_eq!(
+            64 * 16,
+            std::fs::metadata(dir.join("000001.bin")).unwrap().len()
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
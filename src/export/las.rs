@@ -0,0 +1,184 @@
+use std::io::{self, Write};
+
+use super::ExportPoint;
+
+const HEADER_SIZE: u16 = 375;
+const VLR_HEADER_SIZE: usize = 54;
+const EXTRA_BYTES_DESCRIPTOR_SIZE: usize = 192;
+/// Point data record format 6 without extra bytes
+const POINT_FORMAT_6_SIZE: u16 = 30;
+/// Millimeter resolution
+const SCALE: f64 = 0.001;
+
+/// Stored as extra bytes, as point format 6 has no field for them: (name, LAS data type, size)
+const EXTRA_BYTES: [(&str, u8, u16); 3] = [("reflectivity", 1, 1), ("nir", 3, 2), ("ring", 3, 2)];
+
+/// LAS 1.4 with point data record format 6
+///
+/// The signal is stored as intensity and the timestamp in seconds as GPS time.
+/// Reflectivity, NIR and ring are described by an extra bytes VLR.
+pub fn write_las(mut writer: impl Write, points: &[ExportPoint]) -> io::Result<()> {
+    let extra_size: u16 = EXTRA_BYTES.iter().map(|(_, _, size)| size).sum();
+    let vlr_size = VLR_HEADER_SIZE + EXTRA_BYTES.len() * EXTRA_BYTES_DESCRIPTOR_SIZE;
+    writer.write_all(&header(
+        points,
+        HEADER_SIZE as u32 + vlr_size as u32,
+        POINT_FORMAT_6_SIZE + extra_size,
+    ))?;
+    writer.write_all(&extra_bytes_vlr())?;
+
+    let mut buf = Vec::with_capacity(points.len() * (POINT_FORMAT_6_SIZE + extra_size) as usize);
+    for p in points {
+        for coordinate in [p.xyz.0, p.xyz.1, p.xyz.2] {
+            buf.extend_from_slice(&to_las_coordinate(coordinate).to_le_bytes());
+        }
+        buf.extend_from_slice(&p.signal.to_le_bytes());
+        let return_number = (p.return_index + 1).min(15);
+        let returns = p.returns.clamp(return_number, 15);
+        buf.push(return_number | returns << 4);
+        // Classification flags, scanner channel, scan direction, edge of flight line
+        buf.push(0);
+        // Classification: Created, never classified
+        buf.push(0);
+        // User data
+        buf.push(0);
+        // Scan angle
+        buf.extend_from_slice(&0i16.to_le_bytes());
+        // Point source id
+        buf.extend_from_slice(&0u16.to_le_bytes());
+        buf.extend_from_slice(&(p.timestamp as f64 * 1e-9).to_le_bytes());
+
+        buf.push(p.reflectivity);
+        buf.extend_from_slice(&p.nir.to_le_bytes());
+        buf.extend_from_slice(&p.ring.to_le_bytes());
+    }
+    writer.write_all(&buf)
+}
+
+fn to_las_coordinate(meter: f32) -> i32 {
+    (meter as f64 / SCALE).round() as i32
+}
+
+fn header(points: &[ExportPoint], offset_to_points: u32, record_length: u16) -> Vec<u8> {
+    let mut min = [f64::MAX; 3];
+    let mut max = [f64::MIN; 3];
+    let mut points_by_return = [0u64; 15];
+    for p in points {
+        for (axis, value) in [p.xyz.0, p.xyz.1, p.xyz.2].into_iter().enumerate() {
+            let value = to_las_coordinate(value) as f64 * SCALE;
+            min[axis] = min[axis].min(value);
+            max[axis] = max[axis].max(value);
+        }
+        points_by_return[(p.return_index as usize).min(14)] += 1;
+    }
+    if points.is_empty() {
+        (min, max) = ([0.; 3], [0.; 3]);
+    }
+
+    let mut h = Vec::with_capacity(HEADER_SIZE as usize);
+    h.extend_from_slice(b"LASF");
+    // File source id
+    h.extend_from_slice(&0u16.to_le_bytes());
+    // Global encoding: GPS time is not adjusted, WKT is required for point format 6
+    h.extend_from_slice(&0x10u16.to_le_bytes());
+    // Project id
+    h.extend_from_slice(&[0; 16]);
+    h.extend_from_slice(&[1, 4]);
+    h.extend_from_slice(&fixed_str::<32>("OTHER"));
+    h.extend_from_slice(&fixed_str::<32>("ouster-rs-ce"));
+    // Creation day of year and year are unknown
+    h.extend_from_slice(&[0; 4]);
+    h.extend_from_slice(&HEADER_SIZE.to_le_bytes());
+    h.extend_from_slice(&offset_to_points.to_le_bytes());
+    // Number of VLRs
+    h.extend_from_slice(&1u32.to_le_bytes());
+    h.push(6);
+    h.extend_from_slice(&record_length.to_le_bytes());
+    // Legacy point counts have to be 0 for point format 6
+    h.extend_from_slice(&[0; 4 + 5 * 4]);
+    for _ in 0..3 {
+        h.extend_from_slice(&SCALE.to_le_bytes());
+    }
+    // Offsets
+    h.extend_from_slice(&[0; 3 * 8]);
+    for axis in 0..3 {
+        h.extend_from_slice(&max[axis].to_le_bytes());
+        h.extend_from_slice(&min[axis].to_le_bytes());
+    }
+    // Waveform data, first EVLR and number of EVLRs
+    h.extend_from_slice(&[0; 8 + 8 + 4]);
+    h.extend_from_slice(&(points.len() as u64).to_le_bytes());
+    for count in points_by_return {
+        h.extend_from_slice(&count.to_le_bytes());
+    }
+    debug_assert_eq!(HEADER_SIZE as usize, h.len());
+    h
+}
+
+fn extra_bytes_vlr() -> Vec<u8> {
+    let mut vlr = Vec::new();
+    // Reserved
+    vlr.extend_from_slice(&0u16.to_le_bytes());
+    vlr.extend_from_slice(&fixed_str::<16>("LASF_Spec"));
+    // Record id of extra bytes
+    vlr.extend_from_slice(&4u16.to_le_bytes());
+    vlr.extend_from_slice(
+        &((EXTRA_BYTES.len() * EXTRA_BYTES_DESCRIPTOR_SIZE) as u16).to_le_bytes(),
+    );
+    vlr.extend_from_slice(&fixed_str::<32>("Extra Bytes"));
+    for (name, data_type, _) in EXTRA_BYTES {
+        let mut descriptor = [0; EXTRA_BYTES_DESCRIPTOR_SIZE];
+        descriptor[2] = data_type;
+        descriptor[4..36].copy_from_slice(&fixed_str::<32>(name));
+        descriptor[160..192].copy_from_slice(&fixed_str::<32>(name));
+        vlr.extend_from_slice(&descriptor);
+    }
+    vlr
+}
+
+/// Zero padded, truncated if too long
+fn fixed_str<const N: usize>(s: &str) -> [u8; N] {
+    let mut out = [0; N];
+    let len = s.len().min(N);
+    out[..len].copy_from_slice(&s.as_bytes()[..len]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_layout() {
+        let points = crate::export::tests::test_points();
+        let mut out = Vec::new();
+        write_las(&mut out, &points).unwrap();
+
+        let offset_to_points = u32::from_le_bytes(out[96..100].try_into().unwrap()) as usize;
+        let record_length = u16::from_le_bytes(out[105..107].try_into().unwrap()) as usize;
+        assert_eq!(35, record_length);
+        assert_eq!(offset_to_points + 3 * record_length, out.len());
+        assert_eq!(3, u64::from_le_bytes(out[247..255].try_into().unwrap()));
+        // points by return
+        assert_eq!(2, u64::from_le_bytes(out[255..263].try_into().unwrap()));
+        assert_eq!(1, u64::from_le_bytes(out[263..271].try_into().unwrap()));
+
+        let second_return = &out[offset_to_points + 2 * record_length..];
+        assert_eq!(
+            4000,
+            i32::from_le_bytes(second_return[0..4].try_into().unwrap())
+        );
+        // return 2 of 2
+        assert_eq!(0x22, second_return[14]);
+        // reflectivity, nir and ring as extra bytes
+        assert_eq!(0, second_return[30]);
+        assert_eq!(
+            0x1234,
+            u16::from_le_bytes([second_return[31], second_return[32]])
+        );
+        assert_eq!(
+            1,
+            u16::from_le_bytes([second_return[33], second_return[34]])
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! ROS 2 messages serialized as little endian CDR, as expected for the message encoding `cdr`
+
+use std::time::Duration;
+
+use crate::{ExportPoint, ImuPacket};
+
+/// Offsets of the fields relative to the start of the data after the encapsulation header
+pub(super) struct CdrWriter {
+    buf: Vec<u8>,
+}
+
+impl CdrWriter {
+    pub fn new() -> Self {
+        // Encapsulation header: CDR_LE
+        Self {
+            buf: vec![0, 1, 0, 0],
+        }
+    }
+
+    fn align(&mut self, alignment: usize) {
+        while !(self.buf.len() - 4).is_multiple_of(alignment) {
+            self.buf.push(0);
+        }
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.align(4);
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn i32(&mut self, value: i32) {
+        self.align(4);
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn f64(&mut self, value: f64) {
+        self.align(8);
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Null terminated, the length includes the terminator
+    pub fn string(&mut self, value: &str) {
+        self.u32(value.len() as u32 + 1);
+        self.buf.extend_from_slice(value.as_bytes());
+        self.buf.push(0);
+    }
+
+    /// uint8[]
+    pub fn bytes(&mut self, value: &[u8]) {
+        self.u32(value.len() as u32);
+        self.buf.extend_from_slice(value);
+    }
+
+    /// std_msgs/Header
+    pub fn header(&mut self, stamp: Duration, frame_id: &str) {
+        self.i32(stamp.as_secs() as i32);
+        self.u32(stamp.subsec_nanos());
+        self.string(frame_id);
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+const POINT_FIELD_UINT16: u8 = 4;
+const POINT_FIELD_UINT32: u8 = 6;
+const POINT_FIELD_FLOAT32: u8 = 7;
+
+/// (name, offset, datatype)
+const POINT_FIELDS: [(&str, u32, u8); 7] = [
+    ("x", 0, POINT_FIELD_FLOAT32),
+    ("y", 4, POINT_FIELD_FLOAT32),
+    ("z", 8, POINT_FIELD_FLOAT32),
+    ("intensity", 12, POINT_FIELD_FLOAT32),
+    ("t", 16, POINT_FIELD_UINT32),
+    ("reflectivity", 20, POINT_FIELD_UINT16),
+    ("ring", 22, POINT_FIELD_UINT16),
+];
+pub(super) const POINT_STEP: u32 = 24;
+
+/// Unorganized sensor_msgs/PointCloud2 with the signal as intensity and t as nanoseconds since the stamp
+pub(super) fn point_cloud2(stamp: Duration, frame_id: &str, points: &[ExportPoint]) -> Vec<u8> {
+    let stamp_ns = stamp.as_nanos() as u64;
+    let mut data = Vec::with_capacity(points.len() * POINT_STEP as usize);
+    for p in points {
+        for value in [p.xyz.0, p.xyz.1, p.xyz.2, p.signal as f32] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&(p.timestamp.saturating_sub(stamp_ns) as u32).to_le_bytes());
+        data.extend_from_slice(&(p.reflectivity as u16).to_le_bytes());
+        data.extend_from_slice(&p.ring.to_le_bytes());
+    }
+
+    let mut cdr = CdrWriter::new();
+    cdr.header(stamp, frame_id);
+    // height, width
+    cdr.u32(1);
+    cdr.u32(points.len() as u32);
+    cdr.u32(POINT_FIELDS.len() as u32);
+    for (name, offset, datatype) in POINT_FIELDS {
+        cdr.string(name);
+        cdr.u32(offset);
+        cdr.u8(datatype);
+        // count
+        cdr.u32(1);
+    }
+    // is_bigendian
+    cdr.u8(0);
+    cdr.u32(POINT_STEP);
+    cdr.u32(data.len() as u32);
+    cdr.bytes(&data);
+    // is_dense, as only measured points are contained
+    cdr.u8(1);
+    cdr.finish()
+}
+
+/// sensor_msgs/Imu without orientation estimate and with unknown covariances
+pub(super) fn imu(frame_id: &str, packet: &ImuPacket) -> Vec<u8> {
+    let mut cdr = CdrWriter::new();
+    cdr.header(packet.timestamp(), frame_id);
+    for value in [0., 0., 0., 1.] {
+        cdr.f64(value);
+    }
+    let mut orientation_covariance = [0.; 9];
+    // Marks the orientation as not estimated
+    orientation_covariance[0] = -1.;
+    for values in [
+        &orientation_covariance[..],
+        &packet.angular_velocity_rad(),
+        &[0.; 9],
+        &packet.linear_acceleration(),
+        &[0.; 9],
+    ] {
+        for value in values {
+            cdr.f64(*value);
+        }
+    }
+    cdr.finish()
+}
+
+/// ouster_sensor_msgs/PacketMsg as published by the ouster-ros driver
+pub(super) fn packet_msg(buf: &[u8]) -> Vec<u8> {
+    let mut cdr = CdrWriter::new();
+    cdr.bytes(buf);
+    cdr.finish()
+}
+
+const SEPARATOR: &str =
+    "================================================================================\n";
+
+fn with_dependencies(definition: &str, dependencies: &[(&str, &str)]) -> String {
+    let mut out = definition.to_owned();
+    for (name, definition) in dependencies {
+        out.push_str(SEPARATOR);
+        out.push_str("MSG: ");
+        out.push_str(name);
+        out.push('\n');
+        out.push_str(definition);
+    }
+    out
+}
+
+const HEADER: (&str, &str) = (
+    "std_msgs/Header",
+    "builtin_interfaces/Time stamp\nstring frame_id\n",
+);
+const TIME: (&str, &str) = ("builtin_interfaces/Time", "int32 sec\nuint32 nanosec\n");
+
+/// Message definitions in the ros2msg schema encoding
+pub(super) fn point_cloud2_schema() -> String {
+    with_dependencies(
+        "std_msgs/Header header\nuint32 height\nuint32 width\nPointField[] fields\n\
+         bool is_bigendian\nuint32 point_step\nuint32 row_step\nuint8[] data\nbool is_dense\n",
+        &[
+            HEADER,
+            TIME,
+            (
+                "sensor_msgs/PointField",
+                "uint8 INT8=1\nuint8 UINT8=2\nuint8 INT16=3\nuint8 UINT16=4\nuint8 INT32=5\n\
+                 uint8 UINT32=6\nuint8 FLOAT32=7\nuint8 FLOAT64=8\n\
+                 string name\nuint32 offset\nuint8 datatype\nuint32 count\n",
+            ),
+        ],
+    )
+}
+
+pub(super) fn imu_schema() -> String {
+    with_dependencies(
+        "std_msgs/Header header\ngeometry_msgs/Quaternion orientation\n\
+         float64[9] orientation_covariance\ngeometry_msgs/Vector3 angular_velocity\n\
+         float64[9] angular_velocity_covariance\ngeometry_msgs/Vector3 linear_acceleration\n\
+         float64[9] linear_acceleration_covariance\n",
+        &[
+            HEADER,
+            TIME,
+            (
+                "geometry_msgs/Quaternion",
+                "float64 x\nfloat64 y\nfloat64 z\nfloat64 w\n",
+            ),
+            ("geometry_msgs/Vector3", "float64 x\nfloat64 y\nfloat64 z\n"),
+        ],
+    )
+}
+
+pub(super) fn packet_msg_schema() -> String {
+    "uint8[] buf\n".to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alignment_is_relative_to_encapsulation() {
+        let mut cdr = CdrWriter::new();
+        cdr.u8(1);
+        cdr.f64(2.);
+        cdr.string("ab");
+        cdr.u32(3);
+        let buf = cdr.finish();
+        assert_eq!(&[0, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0], &buf[..12]);
+        assert_eq!(2., f64::from_le_bytes(buf[12..20].try_into().unwrap()));
+        assert_eq!(&[3, 0, 0, 0, b'a', b'b', 0, 0, 3, 0, 0, 0], &buf[20..]);
+    }
+}
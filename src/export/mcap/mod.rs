@@ -0,0 +1,373 @@
+//! MCAP files with ROS 2 messages, which can be opened by Foxglove and rosbag2
+//!
+//! Records are written without chunks and compression. The summary contains the schemas, channels
+//! and statistics, but no indexes. CRCs are 0, which marks them as not available.
+
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
+
+use crate::{
+    packet::ColumnHeader, CartesianIterator, CompleteData, ExportPoint, ImuPacket, OusterPacket,
+    Profile, ValidOperationConfig,
+};
+
+mod cdr;
+
+const MAGIC: &[u8; 8] = b"\x89MCAP0\r\n";
+
+const OP_HEADER: u8 = 0x01;
+const OP_FOOTER: u8 = 0x02;
+const OP_SCHEMA: u8 = 0x03;
+const OP_CHANNEL: u8 = 0x04;
+const OP_MESSAGE: u8 = 0x05;
+const OP_STATISTICS: u8 = 0x0B;
+const OP_DATA_END: u8 = 0x0F;
+
+/// Topics and frame ids match the ouster-ros driver
+pub const MCAP_POINTS_TOPIC: &str = "/ouster/points";
+pub const MCAP_IMU_TOPIC: &str = "/ouster/imu";
+pub const MCAP_LIDAR_PACKETS_TOPIC: &str = "/ouster/lidar_packets";
+pub const MCAP_IMU_PACKETS_TOPIC: &str = "/ouster/imu_packets";
+pub const MCAP_LIDAR_FRAME_ID: &str = "os_lidar";
+pub const MCAP_IMU_FRAME_ID: &str = "os_imu";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Topic {
+    Points,
+    Imu,
+    LidarPackets,
+    ImuPackets,
+}
+
+impl Topic {
+    fn name(&self) -> &'static str {
+        match self {
+            Topic::Points => MCAP_POINTS_TOPIC,
+            Topic::Imu => MCAP_IMU_TOPIC,
+            Topic::LidarPackets => MCAP_LIDAR_PACKETS_TOPIC,
+            Topic::ImuPackets => MCAP_IMU_PACKETS_TOPIC,
+        }
+    }
+
+    fn schema(&self) -> (&'static str, String) {
+        match self {
+            Topic::Points => ("sensor_msgs/msg/PointCloud2", cdr::point_cloud2_schema()),
+            Topic::Imu => ("sensor_msgs/msg/Imu", cdr::imu_schema()),
+            Topic::LidarPackets | Topic::ImuPackets => {
+                ("ouster_sensor_msgs/msg/PacketMsg", cdr::packet_msg_schema())
+            }
+        }
+    }
+}
+
+struct Channel {
+    topic: Topic,
+    messages: u64,
+}
+
+/// Writes frames as `sensor_msgs/PointCloud2` and optionally IMU data and raw packets into an MCAP file
+/// [McapWriter::finish] has to be called, otherwise the file misses its summary and footer
+pub struct McapWriter<W: Write> {
+    writer: W,
+    position: u64,
+    /// Every channel has its own schema, both use the index + 1 as id
+    channels: Vec<Channel>,
+    message_time: Option<(u64, u64)>,
+}
+
+impl<W: Write> McapWriter<W> {
+    pub fn new(writer: W) -> io::Result<Self> {
+        let mut this = Self {
+            writer,
+            position: 0,
+            channels: Vec::new(),
+            message_time: None,
+        };
+        this.writer.write_all(MAGIC)?;
+        this.position += MAGIC.len() as u64;
+        let mut header = Vec::new();
+        put_string(&mut header, "ros2");
+        put_string(&mut header, "ouster-rs-ce");
+        this.record(OP_HEADER, &header)?;
+        Ok(this)
+    }
+
+    /// Only returns with a measurement are contained, stamped with the first valid column timestamp
+    /// The CartesianIterator has to be created from the same config
+    pub fn write_point_cloud<TProfile: Profile, TSlice: AsRef<[(f32, f32)]>>(
+        &mut self,
+        frame: &CompleteData<TProfile>,
+        config: &ValidOperationConfig<TProfile>,
+        cartesian: CartesianIterator<TProfile, TSlice>,
+    ) -> io::Result<()> {
+        let stamp = frame.timestamp();
+        let points = frame
+            .export_points(config, cartesian)
+            .collect::<Vec<ExportPoint>>();
+        let data = cdr::point_cloud2(stamp, MCAP_LIDAR_FRAME_ID, &points);
+        self.message(Topic::Points, stamp, &data)
+    }
+
+    pub fn write_imu(&mut self, packet: &ImuPacket) -> io::Result<()> {
+        let data = cdr::imu(MCAP_IMU_FRAME_ID, packet);
+        self.message(Topic::Imu, packet.timestamp(), &data)
+    }
+
+    /// Stamped with the timestamp of the first column
+    pub fn write_lidar_packet<TProfile: Profile>(
+        &mut self,
+        packet: &OusterPacket<TProfile>,
+    ) -> io::Result<()> {
+        let stamp = packet.columns.as_ref()[0].channels_header.timestamp();
+        self.message(
+            Topic::LidarPackets,
+            stamp,
+            &cdr::packet_msg(packet.as_slice()),
+        )
+    }
+
+    pub fn write_imu_packet(&mut self, packet: &ImuPacket) -> io::Result<()> {
+        self.message(
+            Topic::ImuPackets,
+            packet.timestamp(),
+            &cdr::packet_msg(packet.as_slice()),
+        )
+    }
+
+    /// Writes the summary and the footer
+    pub fn finish(mut self) -> io::Result<W> {
+        // data_section_crc
+        self.record(OP_DATA_END, &0u32.to_le_bytes())?;
+
+        let summary_start = self.position;
+        for id in 1..=self.channels.len() as u16 {
+            self.schema_and_channel(id)?;
+        }
+        let mut statistics = Vec::new();
+        let message_count = self.channels.iter().map(|c| c.messages).sum::<u64>();
+        statistics.extend_from_slice(&message_count.to_le_bytes());
+        statistics.extend_from_slice(&(self.channels.len() as u16).to_le_bytes());
+        statistics.extend_from_slice(&(self.channels.len() as u32).to_le_bytes());
+        // attachment_count, metadata_count, chunk_count
+        statistics.extend_from_slice(&[0; 12]);
+        let (start, end) = self.message_time.unwrap_or_default();
+        statistics.extend_from_slice(&start.to_le_bytes());
+        statistics.extend_from_slice(&end.to_le_bytes());
+        statistics.extend_from_slice(&(self.channels.len() as u32 * 10).to_le_bytes());
+        for (i, channel) in self.channels.iter().enumerate() {
+            statistics.extend_from_slice(&(i as u16 + 1).to_le_bytes());
+            statistics.extend_from_slice(&channel.messages.to_le_bytes());
+        }
+        self.record(OP_STATISTICS, &statistics)?;
+
+        let mut footer = Vec::new();
+        footer.extend_from_slice(&summary_start.to_le_bytes());
+        // summary_offset_start, summary_crc
+        footer.extend_from_slice(&[0; 12]);
+        self.record(OP_FOOTER, &footer)?;
+        self.writer.write_all(MAGIC)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn message(&mut self, topic: Topic, stamp: Duration, data: &[u8]) -> io::Result<()> {
+        let id = match self.channels.iter().position(|c| c.topic == topic) {
+            Some(index) => index as u16 + 1,
+            None => {
+                self.channels.push(Channel { topic, messages: 0 });
+                let id = self.channels.len() as u16;
+                self.schema_and_channel(id)?;
+                id
+            }
+        };
+        let channel = &mut self.channels[id as usize - 1];
+        let sequence = channel.messages as u32;
+        channel.messages += 1;
+        let time = stamp.as_nanos() as u64;
+        self.message_time = Some(match self.message_time {
+            Some((start, end)) => (start.min(time), end.max(time)),
+            None => (time, time),
+        });
+
+        let mut record = Vec::with_capacity(22 + data.len());
+        record.extend_from_slice(&id.to_le_bytes());
+        record.extend_from_slice(&sequence.to_le_bytes());
+        // log_time and publish_time
+        record.extend_from_slice(&time.to_le_bytes());
+        record.extend_from_slice(&time.to_le_bytes());
+        record.extend_from_slice(data);
+        self.record(OP_MESSAGE, &record)
+    }
+
+    fn schema_and_channel(&mut self, id: u16) -> io::Result<()> {
+        let topic = self.channels[id as usize - 1].topic;
+        let (name, definition) = topic.schema();
+        let mut schema = Vec::new();
+        schema.extend_from_slice(&id.to_le_bytes());
+        put_string(&mut schema, name);
+        put_string(&mut schema, "ros2msg");
+        put_string(&mut schema, &definition);
+        self.record(OP_SCHEMA, &schema)?;
+
+        let mut channel = Vec::new();
+        channel.extend_from_slice(&id.to_le_bytes());
+        channel.extend_from_slice(&id.to_le_bytes());
+        put_string(&mut channel, topic.name());
+        put_string(&mut channel, "cdr");
+        // Empty metadata map
+        channel.extend_from_slice(&0u32.to_le_bytes());
+        self.record(OP_CHANNEL, &channel)
+    }
+
+    fn record(&mut self, op: u8, content: &[u8]) -> io::Result<()> {
+        self.writer.write_all(&[op])?;
+        self.writer
+            .write_all(&(content.len() as u64).to_le_bytes())?;
+        self.writer.write_all(content)?;
+        self.position += 9 + content.len() as u64;
+        Ok(())
+    }
+}
+
+/// Strings and byte arrays are prefixed by their length as u32
+fn put_string(buf: &mut Vec<u8>, value: &str) {
+    buf.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    buf.extend_from_slice(value.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_utils::{complete_frame, test_config},
+        Dual64OusterPacket, DualProfile, LidarProfile,
+    };
+
+    use super::*;
+
+    fn records(data: &[u8]) -> Vec<(u8, &[u8])> {
+        assert_eq!(MAGIC, &data[..8]);
+        assert_eq!(MAGIC, &data[data.len() - 8..]);
+        let mut records = Vec::new();
+        let mut pos = 8;
+        while pos < data.len() - 8 {
+            let len = u64::from_le_bytes(data[pos + 1..pos + 9].try_into().unwrap()) as usize;
+            records.push((data[pos], &data[pos + 9..pos + 9 + len]));
+            pos += 9 + len;
+        }
+        assert_eq!(data.len() - 8, pos);
+        records
+    }
+
+    #[test]
+    fn point_cloud_imu_and_packets() {
+        let config = test_config::<DualProfile<16, 64>>(LidarProfile::DualReturn, &[0; 64]);
+        let mut packets = Vec::new();
+        let frame = complete_frame(&config, |frame_id, measurement_id| {
+            let mut x = Dual64OusterPacket::default();
+            x.header.frame_id = frame_id;
+            for (i, col) in x.columns.iter_mut().enumerate() {
+                let measurement_id = measurement_id + i as u16;
+                col.channels_header.measurement_id = measurement_id;
+                col.channels_header.set_timestamp(
+                    Duration::from_secs(5) + Duration::from_micros(measurement_id as u64),
+                );
+                if measurement_id == 1 {
+                    col.channels[3].info_ret1.raw = 7 << 24 | 2000;
+                    col.channels[3].signal_ret_1 = 300;
+                }
+            }
+            packets.push(x.clone());
+            x
+        });
+        let imu = ImuPacket {
+            accel_timestamp: 5_000_000_500,
+            gyro_timestamp: 5_000_000_400,
+            acceleration: [0., 0., 1.],
+            ..Default::default()
+        };
+
+        let mut writer = McapWriter::new(Vec::new()).unwrap();
+        writer
+            .write_point_cloud(
+                &frame,
+                &config,
+                CartesianIterator::new_cheap_cloneable_from_config(&config),
+            )
+            .unwrap();
+        writer.write_imu(&imu).unwrap();
+        writer.write_lidar_packet(&packets[0]).unwrap();
+        writer.write_lidar_packet(&packets[1]).unwrap();
+        let data = writer.finish().unwrap();
+
+        let records = records(&data);
+        assert_eq!(
+            vec![
+                OP_HEADER,
+                OP_SCHEMA,
+                OP_CHANNEL,
+                OP_MESSAGE,
+                OP_SCHEMA,
+                OP_CHANNEL,
+                OP_MESSAGE,
+                OP_SCHEMA,
+                OP_CHANNEL,
+                OP_MESSAGE,
+                OP_MESSAGE,
+                OP_DATA_END,
+                OP_SCHEMA,
+                OP_CHANNEL,
+                OP_SCHEMA,
+                OP_CHANNEL,
+                OP_SCHEMA,
+                OP_CHANNEL,
+                OP_STATISTICS,
+                OP_FOOTER,
+            ],
+            records.iter().map(|(op, _)| *op).collect::<Vec<_>>()
+        );
+
+        // Footer points to the first summary record
+        let footer = records.last().unwrap().1;
+        let summary_start = u64::from_le_bytes(footer[..8].try_into().unwrap()) as usize;
+        assert_eq!(OP_SCHEMA, data[summary_start]);
+
+        let statistics = records[18].1;
+        assert_eq!(4, u64::from_le_bytes(statistics[..8].try_into().unwrap()));
+        assert_eq!(
+            5_000_000_000,
+            u64::from_le_bytes(statistics[26..34].try_into().unwrap())
+        );
+
+        let points = records[3].1;
+        assert_eq!(&[1, 0], &points[..2]);
+        // log_time
+        assert_eq!(
+            5_000_000_000,
+            u64::from_le_bytes(points[6..14].try_into().unwrap())
+        );
+        let cdr = &points[22..];
+        // width after the header with frame id "os_lidar"
+        assert_eq!(
+            1,
+            u32::from_le_bytes(cdr[4 + 28..4 + 32].try_into().unwrap())
+        );
+        let point = &cdr[cdr.len() - 1 - cdr::POINT_STEP as usize..cdr.len() - 1];
+        assert!((f32::from_le_bytes(point[..4].try_into().unwrap()) - 2.).abs() < 1e-3);
+        assert_eq!(300., f32::from_le_bytes(point[12..16].try_into().unwrap()));
+        assert_eq!(1000, u32::from_le_bytes(point[16..20].try_into().unwrap()));
+        assert_eq!(7, u16::from_le_bytes([point[20], point[21]]));
+        assert_eq!(3, u16::from_le_bytes([point[22], point[23]]));
+
+        let imu_message = records[6].1;
+        assert_eq!(
+            5_000_000_500,
+            u64::from_le_bytes(imu_message[6..14].try_into().unwrap())
+        );
+
+        let packet = records[10].1;
+        assert_eq!(&[3, 0, 1, 0, 0, 0], &packet[..6]);
+        assert_eq!(packets[1].as_slice(), &packet[22 + 8..]);
+    }
+}
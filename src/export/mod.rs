@@ -0,0 +1,179 @@
+//! Writers for point clouds and field images, each enabled by its feature:
+//! - `pcd`, `ply`, `las`: Common point cloud formats
+//! - `dataset`: KITTI and nuScenes training data
+//! - `png`, `archive`: Field images
+//! - `mcap`: ROS 2 messages for Foxglove and rosbag2
+
+use crate::{
+    packet::ColumnHeader, CartesianIterator, CompleteData, PointInfos, Profile,
+    ValidOperationConfig,
+};
+
+#[cfg(feature = "archive")]
+mod archive;
+#[cfg(feature = "dataset")]
+mod dataset;
+#[cfg(feature = "las")]
+mod las;
+#[cfg(feature = "mcap")]
+mod mcap;
+#[cfg(feature = "pcd")]
+mod pcd;
+#[cfg(feature = "ply")]
+mod ply;
+#[cfg(feature = "png")]
+mod png;
+
+#[cfg(feature = "png")]
+pub use self::png::*;
+#[cfg(feature = "archive")]
+pub use archive::*;
+#[cfg(feature = "dataset")]
+pub use dataset::*;
+#[cfg(feature = "las")]
+pub use las::*;
+#[cfg(feature = "mcap")]
+pub use mcap::*;
+#[cfg(feature = "pcd")]
+pub use pcd::*;
+#[cfg(feature = "ply")]
+pub use ply::*;
+
+/// A single return with all attributes, which are written by the exporters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExportPoint {
+    /// Meter
+    pub xyz: (f32, f32, f32),
+    pub reflectivity: u8,
+    /// 0, if the profile doesn't contain a signal
+    pub signal: u16,
+    pub nir: u16,
+    /// Row of the pixel, 0 is the top most beam
+    pub ring: u16,
+    /// Timestamp of the column in nanoseconds
+    pub timestamp: u64,
+    /// 0 for the strongest return
+    pub return_index: u8,
+    /// Number of returns of this pixel
+    pub returns: u8,
+}
+
+impl<TProfile: Profile> CompleteData<TProfile> {
+    /// All returns with a measurement (distance != 0)
+    /// The CartesianIterator has to be created from the same config
+    pub fn export_points<'a, TSlice>(
+        &'a self,
+        config: &ValidOperationConfig<TProfile>,
+        cartesian: CartesianIterator<TProfile, TSlice>,
+    ) -> impl Iterator<Item = ExportPoint> + 'a
+    where
+        TSlice: AsRef<[(f32, f32)]> + 'a,
+    {
+        let n_vec = config.n_vec();
+        self.iter()
+            .flat_map(|lidar_packet| lidar_packet.columns.as_ref().iter())
+            .flat_map(|column| {
+                let timestamp = column.channels_header.timestamp().as_nanos() as u64;
+                column
+                    .channels
+                    .as_ref()
+                    .iter()
+                    .enumerate()
+                    .map(move |(ring, channel)| (timestamp, ring as u16, channel))
+            })
+            .zip(cartesian)
+            .flat_map(move |((timestamp, ring, channel), polar_point)| {
+                let nir = channel.get_nir();
+                let infos = channel.get_infos(n_vec).channel_info;
+                let returns = infos
+                    .as_ref()
+                    .iter()
+                    .filter(|info| info.distance != 0)
+                    .count() as u8;
+                infos
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, info)| info.distance != 0)
+                    .map(move |(return_index, info)| {
+                        let (x, y, z) = polar_point.calc_xyz(info.distance as f32);
+                        ExportPoint {
+                            xyz: (x / 1000., y / 1000., z / 1000.),
+                            reflectivity: info.reflectifity,
+                            signal: info.get_signal().unwrap_or_default(),
+                            nir,
+                            ring,
+                            timestamp,
+                            return_index: return_index as u8,
+                            returns,
+                        }
+                    })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        test_utils::{complete_frame, test_config},
+        Dual64OusterPacket, DualProfile, LidarProfile,
+    };
+
+    use super::*;
+
+    pub(super) fn test_points() -> Vec<ExportPoint> {
+        let config = test_config::<DualProfile<16, 64>>(LidarProfile::DualReturn, &[0; 64]);
+        let frame = complete_frame(&config, |frame_id, measurement_id| {
+            let mut x = Dual64OusterPacket::default();
+            x.header.frame_id = frame_id;
+            for (i, col) in x.columns.iter_mut().enumerate() {
+                let measurement_id = measurement_id + i as u16;
+                col.channels_header.measurement_id = measurement_id;
+                col.channels_header
+                    .set_timestamp(Duration::from_micros(measurement_id as u64));
+                if measurement_id == 0 {
+                    for (row, ch) in col.channels.iter_mut().enumerate().take(2) {
+                        ch.info_ret1.raw = (10 + row as u32) << 24 | 2000;
+                        ch.signal_ret_1 = 300;
+                        ch.nir = 0x1234;
+                        if row == 1 {
+                            ch.info_ret2.raw = 4000;
+                        }
+                    }
+                }
+            }
+            x
+        });
+        frame
+            .export_points(
+                &config,
+                CartesianIterator::new_cheap_cloneable_from_config(&config),
+            )
+            .collect()
+    }
+
+    #[test]
+    fn only_measured_returns() {
+        let points = test_points();
+        assert_eq!(3, points.len());
+        let first = points[0];
+        assert!((first.xyz.0 - 2.).abs() < 1e-3, "{first:?}");
+        assert_eq!((10, 300, 0x1234, 0, 0, 0, 1), point_attributes(&first));
+        assert_eq!((11, 300, 0x1234, 1, 0, 0, 2), point_attributes(&points[1]));
+        assert_eq!(1, points[2].return_index);
+        assert!((points[2].xyz.0 - 4.).abs() < 1e-3);
+    }
+
+    fn point_attributes(p: &ExportPoint) -> (u8, u16, u16, u16, u64, u8, u8) {
+        (
+            p.reflectivity,
+            p.signal,
+            p.nir,
+            p.ring,
+            p.timestamp,
+            p.return_index,
+            p.returns,
+        )
+    }
+}
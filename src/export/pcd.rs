@@ -0,0 +1,54 @@
+use std::io::{self, Write};
+
+use super::ExportPoint;
+
+/// Binary PCD v0.7 with the fields x y z reflectivity signal nir ring timestamp return_index
+pub fn write_pcd(mut writer: impl Write, points: &[ExportPoint]) -> io::Result<()> {
+    let n = points.len();
+    write!(
+        writer,
+        "# .PCD v0.7 - Point Cloud Data file format\n\
+         VERSION 0.7\n\
+         FIELDS x y z reflectivity signal nir ring timestamp return_index\n\
+         SIZE 4 4 4 1 2 2 2 8 1\n\
+         TYPE F F F U U U U U U\n\
+         COUNT 1 1 1 1 1 1 1 1 1\n\
+         WIDTH {n}\n\
+         HEIGHT 1\n\
+         VIEWPOINT 0 0 0 1 0 0 0\n\
+         POINTS {n}\n\
+         DATA binary\n"
+    )?;
+    let mut buf = Vec::with_capacity(n * 28);
+    for p in points {
+        buf.extend_from_slice(&p.xyz.0.to_le_bytes());
+        buf.extend_from_slice(&p.xyz.1.to_le_bytes());
+        buf.extend_from_slice(&p.xyz.2.to_le_bytes());
+        buf.push(p.reflectivity);
+        buf.extend_from_slice(&p.signal.to_le_bytes());
+        buf.extend_from_slice(&p.nir.to_le_bytes());
+        buf.extend_from_slice(&p.ring.to_le_bytes());
+        buf.extend_from_slice(&p.timestamp.to_le_bytes());
+        buf.push(p.return_index);
+    }
+    writer.write_all(&buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_layout() {
+        let points = crate::export::tests::test_points();
+        let mut out = Vec::new();
+        write_pcd(&mut out, &points).unwrap();
+        let header_end = out.windows(12).position(|x| x == b"DATA binary\n").unwrap() + 12;
+        let header = std::str::from_utf8(&out[..header_end]).unwrap();
+        assert!(header.contains("POINTS 3\n"));
+        assert_eq!(3 * 28, out.len() - header_end);
+        let record = &out[header_end + 28..header_end + 56];
+        assert_eq!(11, record[12]);
+        assert_eq!(1, u16::from_le_bytes([record[17], record[18]]));
+    }
+}
@@ -0,0 +1,57 @@
+use std::io::{self, Write};
+
+use super::ExportPoint;
+
+/// Binary little endian PLY, the timestamp is stored in seconds as PLY lacks 64 bit integers
+pub fn write_ply(mut writer: impl Write, points: &[ExportPoint]) -> io::Result<()> {
+    write!(
+        writer,
+        "ply\n\
+         format binary_little_endian 1.0\n\
+         comment ouster-rs-ce\n\
+         element vertex {}\n\
+         property float x\n\
+         property float y\n\
+         property float z\n\
+         property uchar reflectivity\n\
+         property ushort signal\n\
+         property ushort nir\n\
+         property ushort ring\n\
+         property double timestamp\n\
+         property uchar return_index\n\
+         end_header\n",
+        points.len()
+    )?;
+    let mut buf = Vec::with_capacity(points.len() * 28);
+    for p in points {
+        buf.extend_from_slice(&p.xyz.0.to_le_bytes());
+        buf.extend_from_slice(&p.xyz.1.to_le_bytes());
+        buf.extend_from_slice(&p.xyz.2.to_le_bytes());
+        buf.push(p.reflectivity);
+        buf.extend_from_slice(&p.signal.to_le_bytes());
+        buf.extend_from_slice(&p.nir.to_le_bytes());
+        buf.extend_from_slice(&p.ring.to_le_bytes());
+        buf.extend_from_slice(&(p.timestamp as f64 * 1e-9).to_le_bytes());
+        buf.push(p.return_index);
+    }
+    writer.write_all(&buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_layout() {
+        let points = crate::export::tests::test_points();
+        let mut out = Vec::new();
+        write_ply(&mut out, &points).unwrap();
+        let header_end = out.windows(11).position(|x| x == b"end_header\n").unwrap() + 11;
+        assert!(std::str::from_utf8(&out[..header_end])
+            .unwrap()
+            .contains("element vertex 3\n"));
+        assert_eq!(3 * 28, out.len() - header_end);
+        let x = f32::from_le_bytes(out[header_end + 56..header_end + 60].try_into().unwrap());
+        assert!((x - 4.).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,196 @@
+use std::{fs::File, io, path::Path};
+
+use crate::{FieldBuffer, FieldImage};
+
+#[derive(Debug, thiserror::Error)]
+pub enum PngError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Png encoding: {0}")]
+    Encoding(#[from] png::EncodingError),
+    #[error("Png decoding: {0}")]
+    Decoding(#[from] png::DecodingError),
+    #[error("Only 8 and 16 bit grayscale and 8 bit RGB or RGBA pngs can be read as field")]
+    UnsupportedFormat,
+}
+
+impl FieldBuffer {
+    /// Lossless grayscale PNG with the bit depth of the field, so 16 bit fields are stored as 16 bit PNG
+    /// 32 bit fields are stored as 8 bit RGBA with the little endian bytes as channels, like the Ouster SDK does
+    pub fn to_png(&self) -> Result<Vec<u8>, PngError> {
+        let mut out = Vec::new();
+        self.write_png(&mut out)?;
+        Ok(out)
+    }
+
+    pub fn write_png(&self, writer: impl io::Write) -> Result<(), PngError> {
+        let data = match self {
+            FieldBuffer::U8(image) => image.as_slice().to_vec(),
+            FieldBuffer::U16(image) => image
+                .as_slice()
+                .iter()
+                .flat_map(|x| x.to_be_bytes())
+                .collect(),
+            FieldBuffer::U32(image) => image
+                .as_slice()
+                .iter()
+                .flat_map(|x| x.to_le_bytes())
+                .collect(),
+        };
+        let mut encoder = png::Encoder::new(writer, self.width() as u32, self.height() as u32);
+        let (color, depth) = match self {
+            FieldBuffer::U8(_) => (png::ColorType::Grayscale, png::BitDepth::Eight),
+            FieldBuffer::U16(_) => (png::ColorType::Grayscale, png::BitDepth::Sixteen),
+            FieldBuffer::U32(_) => (png::ColorType::Rgba, png::BitDepth::Eight),
+        };
+        encoder.set_color(color);
+        encoder.set_depth(depth);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)?;
+        writer.finish()?;
+        Ok(())
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<(), PngError> {
+        self.write_png(io::BufWriter::new(File::create(path)?))
+    }
+
+    /// Inverse of [FieldBuffer::to_png]
+    /// 8 bit RGB and RGBA are read as 24 and 32 bit values, which the Ouster SDK writes for wide fields like the range
+    pub fn from_png(data: &[u8]) -> Result<Self, PngError> {
+        let mut reader = png::Decoder::new(io::Cursor::new(data)).read_info()?;
+        let mut buf = vec![
+            0;
+            reader
+                .output_buffer_size()
+                .ok_or(PngError::UnsupportedFormat)?
+        ];
+        let info = reader.next_frame(&mut buf)?;
+        buf.truncate(info.buffer_size());
+        let (width, height) = (info.width as usize, info.height as usize);
+        match (info.color_type, info.bit_depth) {
+            (png::ColorType::Grayscale, png::BitDepth::Eight) => {
+                Ok(FieldBuffer::U8(FieldImage::new(width, height, buf)))
+            }
+            (png::ColorType::Grayscale, png::BitDepth::Sixteen) => {
+                Ok(FieldBuffer::U16(FieldImage::new(
+                    width,
+                    height,
+                    buf.chunks_exact(2)
+                        .map(|x| u16::from_be_bytes([x[0], x[1]]))
+                        .collect(),
+                )))
+            }
+            (png::ColorType::Rgb, png::BitDepth::Eight) => Ok(FieldBuffer::U32(FieldImage::new(
+                width,
+                height,
+                buf.chunks_exact(3)
+                    .map(|x| u32::from_le_bytes([x[0], x[1], x[2], 0]))
+                    .collect(),
+            ))),
+            (png::ColorType::Rgba, png::BitDepth::Eight) => Ok(FieldBuffer::U32(FieldImage::new(
+                width,
+                height,
+                buf.chunks_exact(4)
+                    .map(|x| u32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+                    .collect(),
+            ))),
+            _ => Err(PngError::UnsupportedFormat),
+        }
+    }
+
+    pub fn open_png(path: impl AsRef<Path>) -> Result<Self, PngError> {
+        Self::from_png(&std::fs::read(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_utils::{complete_frame, test_config},
+        ChanField, Dual64OusterPacket, DualProfile, LidarProfile,
+    };
+
+    use super::*;
+
+    #[test]
+    fn lossless_round_trip() {
+        let range = FieldBuffer::U16(FieldImage::new(
+            3,
+            2,
+            vec![0, 1, 300, u16::MAX, 4096, 65000],
+        ));
+        let png = range.to_png().unwrap();
+        // Bit depth in the IHDR chunk
+        assert_eq!(16, png[24]);
+        assert_eq!(range, FieldBuffer::from_png(&png).unwrap());
+
+        let reflectivity = FieldBuffer::U8(FieldImage::new(2, 2, vec![0, 1, 254, 255]));
+        assert_eq!(
+            reflectivity,
+            FieldBuffer::from_png(&reflectivity.to_png().unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn nir_keeps_16_bit() {
+        let config = test_config::<DualProfile<16, 64>>(LidarProfile::DualReturn, &[0; 64]);
+        let frame = complete_frame(&config, |frame_id, measurement_id| {
+            let mut x = Dual64OusterPacket::default();
+            x.header.frame_id = frame_id;
+            for (i, col) in x.columns.iter_mut().enumerate() {
+                col.channels_header.measurement_id = measurement_id + i as u16;
+                for ch in col.channels.iter_mut() {
+                    ch.nir = 0x1234;
+                }
+            }
+            x
+        });
+        let nir = frame.field(&config, ChanField::Nir).unwrap();
+        let decoded = FieldBuffer::from_png(&nir.to_png().unwrap()).unwrap();
+        assert_eq!(nir, decoded);
+        assert_eq!(
+            FieldBuffer::U16(FieldImage::new(1024, 64, vec![0x1234; 1024 * 64])),
+            decoded
+        );
+    }
+
+    #[test]
+    fn wide_fields_as_color() {
+        let range = FieldBuffer::U32(FieldImage::new(3, 1, vec![0, 0x0007_a120, u32::MAX]));
+        let png = range.to_png().unwrap();
+        // Bit depth and color type in the IHDR chunk
+        assert_eq!([8, 6], png[24..26]);
+        assert_eq!(range, FieldBuffer::from_png(&png).unwrap());
+
+        // 24 bit fields of the Ouster SDK, the first byte is the lowest
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, 2, 1);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(&[0x20, 0xa1, 0x07, 1, 2, 3])
+            .unwrap();
+        assert_eq!(
+            FieldBuffer::U32(FieldImage::new(2, 1, vec![500_000, 0x030201])),
+            FieldBuffer::from_png(&png).unwrap()
+        );
+    }
+
+    #[test]
+    fn gray_alpha_is_unsupported() {
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, 1, 1);
+        encoder.set_color(png::ColorType::GrayscaleAlpha);
+        encoder
+            .write_header()
+            .unwrap()
+            .write_image_data(&[1, 2])
+            .unwrap();
+        assert!(matches!(
+            FieldBuffer::from_png(&png),
+            Err(PngError::UnsupportedFormat)
+        ));
+    }
+}
@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::SizeMismatchError;
+
+/// Standard gravity in m/s², the sensor reports the acceleration in g
+const GRAVITY: f64 = 9.80665;
+
+/// IMU packet of the LEGACY IMU profile, sent to udp_port_imu
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Zeroable, Pod)]
+pub struct ImuPacket {
+    /// Nanoseconds of the system when the packet was sent
+    pub sys_timestamp: u64,
+    /// Nanoseconds of the timestamp_mode clock, when the acceleration was measured
+    pub accel_timestamp: u64,
+    /// Nanoseconds of the timestamp_mode clock, when the angular velocity was measured
+    pub gyro_timestamp: u64,
+    /// g
+    pub acceleration: [f32; 3],
+    /// deg/s
+    pub angular_velocity: [f32; 3],
+}
+
+impl ImuPacket {
+    pub fn from_maybe_unaligned(buffer: &[u8]) -> Result<Self, SizeMismatchError> {
+        if buffer.len() != std::mem::size_of::<Self>() {
+            return Err(SizeMismatchError {
+                expected: std::mem::size_of::<Self>(),
+                actual: buffer.len(),
+            });
+        }
+        Ok(bytemuck::pod_read_unaligned(buffer))
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        bytemuck::bytes_of(self)
+    }
+
+    /// The later one of both measurements
+    pub fn timestamp(&self) -> Duration {
+        Duration::from_nanos(self.accel_timestamp.max(self.gyro_timestamp))
+    }
+
+    /// m/s²
+    pub fn linear_acceleration(&self) -> [f64; 3] {
+        self.acceleration.map(|x| x as f64 * GRAVITY)
+    }
+
+    /// rad/s
+    pub fn angular_velocity_rad(&self) -> [f64; 3] {
+        self.angular_velocity.map(|x| (x as f64).to_radians())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_packet() {
+        let mut buffer = [0; 49];
+        buffer[9..17].copy_from_slice(&10u64.to_le_bytes());
+        buffer[17..25].copy_from_slice(&20u64.to_le_bytes());
+        buffer[33..37].copy_from_slice(&1f32.to_le_bytes());
+        buffer[37..41].copy_from_slice(&180f32.to_le_bytes());
+        assert!(ImuPacket::from_maybe_unaligned(&buffer[..]).is_err());
+
+        let packet = ImuPacket::from_maybe_unaligned(&buffer[1..]).unwrap();
+        assert_eq!(Duration::from_nanos(20), packet.timestamp());
+        assert_eq!([0., 0., GRAVITY], packet.linear_acceleration());
+        assert_eq!(std::f64::consts::PI, packet.angular_velocity_rad()[0]);
+        assert_eq!(&buffer[1..], packet.as_slice());
+    }
+}
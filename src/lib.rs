@@ -1,13 +1,43 @@
 mod aggregator;
 mod cartesian_iterator;
 mod config;
+mod export;
+mod imu;
+#[cfg(feature = "osf")]
+mod osf;
 mod packet;
+mod packet_layout;
+mod pcap;
+mod pcapng;
 mod pixel_position_iterator;
 mod profile;
+mod range_image;
+mod receiver;
+mod replay;
+#[cfg(feature = "http")]
+mod sensor_http;
+mod sensor_tcp;
+mod simulator;
+#[cfg(test)]
+mod test_utils;
 
+pub use self::pcap::*;
 pub use aggregator::*;
 pub use cartesian_iterator::*;
 pub use config::*;
+pub use export::*;
+pub use imu::*;
+#[cfg(feature = "osf")]
+pub use osf::*;
 pub use packet::*;
+pub use packet_layout::*;
+pub use pcapng::*;
 pub use pixel_position_iterator::*;
 pub use profile::*;
+pub use range_image::*;
+pub use receiver::*;
+pub use replay::*;
+#[cfg(feature = "http")]
+pub use sensor_http::*;
+pub use sensor_tcp::*;
+pub use simulator::*;
@@ -0,0 +1,143 @@
+//! Bounds checked access to flatbuffer tables, so corrupted files result in errors instead of UB
+
+use super::OsfError;
+
+pub(crate) trait Scalar: Sized {
+    const SIZE: usize;
+    fn from_le(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_scalar {
+    ($($t:ty),*) => {
+        $(impl Scalar for $t {
+            const SIZE: usize = std::mem::size_of::<$t>();
+
+            fn from_le(bytes: &[u8]) -> Self {
+                <$t>::from_le_bytes(bytes.try_into().expect("Checked length"))
+            }
+        })*
+    };
+}
+
+impl_scalar!(u8, u16, u32, u64, i32, i64);
+
+fn read<T: Scalar>(buf: &[u8], pos: usize) -> Result<T, OsfError> {
+    buf.get(pos..pos + T::SIZE)
+        .map(T::from_le)
+        .ok_or(OsfError::Format("offset out of bounds"))
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct Table<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Table<'a> {
+    /// Root table of a buffer without size prefix
+    pub fn root(buf: &'a [u8], identifier: &[u8; 4]) -> Result<Self, OsfError> {
+        if buf.get(4..8) != Some(identifier) {
+            return Err(OsfError::Format("unexpected file identifier"));
+        }
+        Ok(Self {
+            buf,
+            pos: read::<u32>(buf, 0)? as usize,
+        })
+    }
+
+    /// Root table of a buffer with size prefix and without file identifier
+    pub fn size_prefixed_root(buf: &'a [u8]) -> Result<Self, OsfError> {
+        let size = read::<u32>(buf, 0)? as usize;
+        let inner = buf
+            .get(4..4 + size)
+            .ok_or(OsfError::Format("size prefix exceeds buffer"))?;
+        Ok(Self {
+            buf: inner,
+            pos: read::<u32>(inner, 0)? as usize,
+        })
+    }
+
+    fn field_pos(&self, index: usize) -> Result<Option<usize>, OsfError> {
+        let vtable = (self.pos as i64 - read::<i32>(self.buf, self.pos)? as i64)
+            .try_into()
+            .map_err(|_| OsfError::Format("vtable out of bounds"))?;
+        let vtable_len = read::<u16>(self.buf, vtable)? as usize;
+        let entry = 4 + 2 * index;
+        if entry + 2 > vtable_len {
+            return Ok(None);
+        }
+        Ok(match read::<u16>(self.buf, vtable + entry)? {
+            0 => None,
+            offset => Some(self.pos + offset as usize),
+        })
+    }
+
+    fn indirect(&self, index: usize) -> Result<Option<usize>, OsfError> {
+        self.field_pos(index)?
+            .map(|pos| Ok(pos + read::<u32>(self.buf, pos)? as usize))
+            .transpose()
+    }
+
+    pub fn scalar<T: Scalar>(&self, index: usize, default: T) -> Result<T, OsfError> {
+        self.field_pos(index)?
+            .map_or(Ok(default), |pos| read(self.buf, pos))
+    }
+
+    /// Empty, if the field is absent
+    pub fn bytes(&self, index: usize) -> Result<&'a [u8], OsfError> {
+        self.vector(index, 1)
+    }
+
+    pub fn string(&self, index: usize) -> Result<Option<&'a str>, OsfError> {
+        if self.field_pos(index)?.is_none() {
+            return Ok(None);
+        }
+        std::str::from_utf8(self.bytes(index)?)
+            .map(Some)
+            .map_err(|_| OsfError::Format("string isn't utf8"))
+    }
+
+    pub fn scalars<T: Scalar>(&self, index: usize) -> Result<Vec<T>, OsfError> {
+        Ok(self
+            .vector(index, T::SIZE)?
+            .chunks_exact(T::SIZE)
+            .map(T::from_le)
+            .collect())
+    }
+
+    /// Inline structs of `size` bytes
+    pub fn structs(&self, index: usize, size: usize) -> Result<Vec<&'a [u8]>, OsfError> {
+        Ok(self.vector(index, size)?.chunks_exact(size).collect())
+    }
+
+    pub fn tables(&self, index: usize) -> Result<Vec<Table<'a>>, OsfError> {
+        let Some(start) = self.indirect(index)? else {
+            return Ok(Vec::new());
+        };
+        let len = read::<u32>(self.buf, start)? as usize;
+        (0..len)
+            .map(|i| {
+                let pos = start + 4 + 4 * i;
+                Ok(Table {
+                    buf: self.buf,
+                    pos: pos + read::<u32>(self.buf, pos)? as usize,
+                })
+            })
+            .collect()
+    }
+
+    fn vector(&self, index: usize, element_size: usize) -> Result<&'a [u8], OsfError> {
+        let Some(start) = self.indirect(index)? else {
+            return Ok(&[]);
+        };
+        let len = read::<u32>(self.buf, start)? as usize;
+        self.buf
+            .get(start + 4..start + 4 + len * element_size)
+            .ok_or(OsfError::Format("vector out of bounds"))
+    }
+}
+
+/// Reads a struct field of a [Table::structs] element
+pub(crate) fn struct_field<T: Scalar>(data: &[u8], offset: usize) -> Result<T, OsfError> {
+    read(data, offset)
+}
@@ -0,0 +1,67 @@
+//! OSF recordings as written by the Ouster SDK (version 2 of the format)
+//!
+//! A file consists of size prefixed flatbuffers, each followed by a CRC32 of the prefix and the buffer:
+//! `Header ("OHDR") | Chunk ("OSFC") ... | Metadata ("OSFM")`
+//!
+//! The metadata contains a `LidarSensor` entry with the sensor metadata json and a `LidarScanStream` entry,
+//! whose messages are `LidarScan`s with one PNG compressed, destaggered image per field.
+//!
+//! The layout is reimplemented from the flatbuffer schemas of the SDK. Fields are PNG images as in the SDK:
+//! 8 and 16 bit fields are grayscale, 24 and 32 bit fields like the range are RGB and RGBA.
+
+use std::io;
+
+use crate::{ChanField, ParseMetadataError, PngError};
+
+mod flatbuffer;
+mod reader;
+mod writer;
+
+pub use reader::*;
+pub use writer::*;
+
+const HEADER_IDENTIFIER: &[u8; 4] = b"OHDR";
+const CHUNK_IDENTIFIER: &[u8; 4] = b"OSFC";
+const METADATA_IDENTIFIER: &[u8; 4] = b"OSFM";
+
+/// `OSF_VERSION` of the second generation, which starts with `V_2_0 = 20`
+const SUPPORTED_VERSIONS: std::ops::Range<u64> = 20..30;
+/// `HEADER_STATUS` of the header
+const STATUS_VALID: u8 = 0;
+const STATUS_INVALID: u8 = 1;
+
+const LIDAR_SENSOR_TYPE: &str = "ouster/v1/os_sensor/LidarSensor";
+const LIDAR_SCAN_STREAM_TYPE: &str = "ouster/v1/os_sensor/LidarScanStream";
+
+#[derive(Debug, thiserror::Error)]
+pub enum OsfError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Invalid osf file: {0}")]
+    Format(&'static str),
+    #[error("Checksum mismatch of the block at offset {0}")]
+    Checksum(u64),
+    #[error(transparent)]
+    Png(#[from] PngError),
+    #[error("Invalid sensor metadata: {0}")]
+    Metadata(#[from] ParseMetadataError),
+}
+
+fn chan_field_code(field: ChanField) -> u8 {
+    match field {
+        ChanField::Range => 1,
+        ChanField::Range2 => 2,
+        ChanField::Signal => 3,
+        ChanField::Signal2 => 4,
+        ChanField::Reflectivity => 5,
+        ChanField::Reflectivity2 => 6,
+        ChanField::Nir => 7,
+    }
+}
+
+fn chan_field_from_code(code: u8) -> Option<ChanField> {
+    ChanField::ALL
+        .iter()
+        .copied()
+        .find(|field| chan_field_code(*field) == code)
+}
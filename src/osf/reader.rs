@@ -0,0 +1,304 @@
+use std::path::Path;
+
+use crate::{ChanField, FieldBuffer, OusterConfig, RangeImage};
+
+use super::{
+    chan_field_from_code,
+    flatbuffer::{struct_field, Table},
+    OsfError, CHUNK_IDENTIFIER, HEADER_IDENTIFIER, LIDAR_SCAN_STREAM_TYPE, LIDAR_SENSOR_TYPE,
+    METADATA_IDENTIFIER, STATUS_INVALID, STATUS_VALID, SUPPORTED_VERSIONS,
+};
+
+/// Destaggered frame of an OSF file, equivalent to the images of a [crate::CompleteData]
+#[derive(Debug, Clone, PartialEq)]
+pub struct OsfFrame {
+    pub frame_id: i64,
+    /// Nanoseconds per column
+    pub timestamps: Vec<u64>,
+    pub measurement_ids: Vec<u16>,
+    /// 0 for missing columns
+    pub status: Vec<u32>,
+    pub fields: Vec<(ChanField, FieldBuffer)>,
+}
+
+impl OsfFrame {
+    pub fn field(&self, field: ChanField) -> Option<&FieldBuffer> {
+        self.fields
+            .iter()
+            .find(|(x, _)| *x == field)
+            .map(|(_, image)| image)
+    }
+
+    pub fn range_image(&self) -> Option<RangeImage> {
+        self.field(ChanField::Range).map(FieldBuffer::to_u16)
+    }
+}
+
+/// Reads a complete OSF file into memory
+pub struct OsfReader {
+    data: Vec<u8>,
+    metadata_json: String,
+    stream_id: u32,
+    /// Absolute offsets
+    chunks: Vec<usize>,
+}
+
+impl OsfReader {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, OsfError> {
+        Self::from_bytes(std::fs::read(path)?)
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, OsfError> {
+        let (header, chunks_start) = block(&data, 0, HEADER_IDENTIFIER)?;
+        if !SUPPORTED_VERSIONS.contains(&header.scalar::<u64>(0, 0)?) {
+            return Err(OsfError::Format("unsupported version"));
+        }
+        if header.scalar::<u8>(1, STATUS_INVALID)? != STATUS_VALID {
+            return Err(OsfError::Format("file wasn't finished"));
+        }
+        let metadata_offset = header.scalar::<u64>(2, 0)? as usize;
+        let (metadata, _) = block(&data, metadata_offset, METADATA_IDENTIFIER)?;
+
+        let mut metadata_json = None;
+        let mut stream_id = None;
+        for entry in metadata.tables(4)? {
+            let buffer = entry.bytes(2)?;
+            match entry.string(1)? {
+                Some(LIDAR_SENSOR_TYPE) => {
+                    let sensor = Table::size_prefixed_root(buffer)?;
+                    metadata_json = sensor.string(0)?.map(str::to_owned);
+                }
+                Some(LIDAR_SCAN_STREAM_TYPE) if stream_id.is_none() => {
+                    stream_id = Some(entry.scalar::<u32>(0, 0)?);
+                }
+                _ => {}
+            }
+        }
+
+        let chunks = metadata
+            .structs(3, 24)?
+            .into_iter()
+            .map(|chunk| Ok(chunks_start + struct_field::<u64>(chunk, 16)? as usize))
+            .collect::<Result<Vec<_>, OsfError>>()?;
+
+        Ok(Self {
+            metadata_json: metadata_json.ok_or(OsfError::Format("no LidarSensor"))?,
+            stream_id: stream_id.ok_or(OsfError::Format("no LidarScanStream"))?,
+            data,
+            chunks,
+        })
+    }
+
+    /// Sensor metadata as written by the sensor
+    pub fn metadata_json(&self) -> &str {
+        &self.metadata_json
+    }
+
+    pub fn ouster_config(&self) -> Result<OusterConfig, OsfError> {
+        Ok(OusterConfig::from_metadata_json(
+            self.metadata_json.as_bytes(),
+        )?)
+    }
+
+    /// Frames in the order they were written
+    pub fn frames(&self) -> impl Iterator<Item = Result<OsfFrame, OsfError>> + '_ {
+        self.chunks
+            .iter()
+            .flat_map(move |offset| match self.chunk_messages(*offset) {
+                Ok(messages) => messages,
+                Err(err) => vec![Err(err)],
+            })
+            .map(|message| message.and_then(lidar_scan))
+    }
+
+    fn chunk_messages(&self, offset: usize) -> Result<Vec<Result<&[u8], OsfError>>, OsfError> {
+        let (chunk, _) = block(&self.data, offset, CHUNK_IDENTIFIER)?;
+        let mut messages = Vec::new();
+        for message in chunk.tables(0)? {
+            if message.scalar::<u32>(0, 0)? == self.stream_id {
+                messages.push(message.bytes(2));
+            }
+        }
+        Ok(messages)
+    }
+}
+
+/// Checks the CRC of the block at offset, returns the table and the offset after the block
+fn block<'a>(
+    data: &'a [u8],
+    offset: usize,
+    identifier: &[u8; 4],
+) -> Result<(Table<'a>, usize), OsfError> {
+    let size = data
+        .get(offset..offset + 4)
+        .map(|x| u32::from_le_bytes(x.try_into().expect("4 bytes")) as usize)
+        .ok_or(OsfError::Format("block out of bounds"))?;
+    let end = offset + 4 + size;
+    let checksum = data
+        .get(end..end + 4)
+        .ok_or(OsfError::Format("block out of bounds"))?;
+    if crc32fast::hash(&data[offset..end]).to_le_bytes() != checksum {
+        return Err(OsfError::Checksum(offset as u64));
+    }
+    Ok((Table::root(&data[offset + 4..end], identifier)?, end + 4))
+}
+
+fn lidar_scan(buffer: &[u8]) -> Result<OsfFrame, OsfError> {
+    let scan = Table::size_prefixed_root(buffer)?;
+    let channels = scan.tables(0)?;
+    let field_types = scan.tables(1)?;
+    if channels.len() != field_types.len() {
+        return Err(OsfError::Format("channels don't match field types"));
+    }
+    let fields = channels
+        .iter()
+        .zip(field_types)
+        .filter_map(|(channel, field_type)| {
+            // Fields unknown to this crate are skipped
+            let field = match field_type.scalar::<u8>(0, 0) {
+                Ok(code) => chan_field_from_code(code)?,
+                Err(err) => return Some(Err(err)),
+            };
+            Some(
+                channel
+                    .bytes(0)
+                    .and_then(|png| Ok(FieldBuffer::from_png(png)?))
+                    .map(|image| (field, image)),
+            )
+        })
+        .collect::<Result<Vec<_>, OsfError>>()?;
+    Ok(OsfFrame {
+        frame_id: scan.scalar::<i64>(5, 0)?,
+        timestamps: scan.scalars(2)?,
+        measurement_ids: scan.scalars(3)?,
+        status: scan.scalars(4)?,
+        fields,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, time::Duration};
+
+    use crate::{
+        osf::OsfWriter,
+        test_utils::{complete_frame, test_config},
+        LidarProfile, Single128OusterPacket, SingleProfile,
+    };
+
+    use super::*;
+
+    const METADATA: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/data/os-1-64_v3.0.1_1024x10.json"
+    ));
+
+    fn write_frames(count: u16, chunk_size: usize) -> Vec<u8> {
+        let config = test_config::<SingleProfile<16, 128>>(LidarProfile::SingleReturn, &[0; 128]);
+        let mut writer = OsfWriter::new(
+            Cursor::new(Vec::new()),
+            METADATA,
+            LidarProfile::SingleReturn,
+        )
+        .unwrap()
+        .with_chunk_size(chunk_size);
+        for offset in 0..count {
+            let frame = complete_frame(&config, |frame_id, measurement_id| {
+                let mut x = Single128OusterPacket::default();
+                x.header.frame_id = frame_id + offset;
+                for (i, col) in x.columns.iter_mut().enumerate() {
+                    let measurement_id = measurement_id + i as u16;
+                    col.channels_header.measurement_id = measurement_id;
+                    col.channels_header
+                        .set_timestamp(Duration::from_micros(measurement_id as u64 + 1));
+                    for (row, ch) in col.channels.iter_mut().enumerate() {
+                        ch.range_and_reserved =
+                            measurement_id as u32 * 4 + row as u32 + offset as u32;
+                        ch.reflectifity = row as u8;
+                        ch.signal = 300 + row as u16;
+                        ch.nir = 0x1234 + row as u16;
+                    }
+                }
+                x
+            });
+            writer.write_frame(&frame, &config).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn round_trip() {
+        let data = write_frames(3, 1);
+        let reader = OsfReader::from_bytes(data).unwrap();
+        assert_eq!(METADATA, reader.metadata_json());
+        reader.ouster_config().unwrap();
+
+        let frames = reader.frames().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(3, frames.len());
+        for (offset, frame) in frames.iter().enumerate() {
+            assert_eq!(offset as i64, frame.frame_id);
+            // Stored as RGBA like the 32 bit range of the SDK
+            assert!(matches!(
+                frame.field(ChanField::Range),
+                Some(FieldBuffer::U32(_))
+            ));
+            let range = frame.range_image().unwrap();
+            assert_eq!((1024, 128), (range.width(), range.height()));
+            assert_eq!(Some(&(5 * 4 + 2 + offset as u16)), range.get(2, 5));
+            assert_eq!(
+                Some(&FieldBuffer::U8(crate::FieldImage::new(
+                    1024,
+                    128,
+                    (0..128u8).flat_map(|row| [row; 1024]).collect()
+                ))),
+                frame.field(ChanField::Reflectivity)
+            );
+            assert!(frame.field(ChanField::Signal).is_some());
+            assert_eq!(
+                Some(&FieldBuffer::U16(crate::FieldImage::new(
+                    1024,
+                    128,
+                    (0..128u16).flat_map(|row| [0x1234 + row; 1024]).collect()
+                ))),
+                frame.field(ChanField::Nir)
+            );
+            assert_eq!(17, frame.measurement_ids[17]);
+            assert_eq!(18_000, frame.timestamps[17]);
+            assert!(frame.status.iter().all(|x| *x == 1));
+        }
+    }
+
+    #[test]
+    fn corrupted_file() {
+        let mut data = write_frames(1, usize::MAX);
+        let middle = data.len() / 2;
+        data[middle] ^= 0xff;
+        let reader = OsfReader::from_bytes(data).unwrap();
+        assert!(matches!(
+            reader.frames().next(),
+            Some(Err(OsfError::Checksum(_)))
+        ));
+    }
+
+    #[test]
+    fn unfinished_file() {
+        let mut data = Cursor::new(Vec::new());
+        OsfWriter::new(&mut data, METADATA, LidarProfile::SingleReturn).unwrap();
+        assert!(matches!(
+            OsfReader::from_bytes(data.into_inner()),
+            Err(OsfError::Format(_))
+        ));
+    }
+
+    #[test]
+    fn version_and_status() {
+        let data = write_frames(1, usize::MAX);
+        // The header is the first block, behind its size prefix
+        let header = Table::root(&data[4..], HEADER_IDENTIFIER).unwrap();
+        assert_eq!(20, header.scalar::<u64>(0, 0).unwrap());
+        assert_eq!(
+            STATUS_VALID,
+            header.scalar::<u8>(1, STATUS_INVALID).unwrap()
+        );
+    }
+}
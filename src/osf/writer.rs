@@ -0,0 +1,342 @@
+use std::io::{Seek, SeekFrom, Write};
+
+use flatbuffers::{FlatBufferBuilder, ForwardsUOffset, TableFinishedWIPOffset, Vector, WIPOffset};
+
+use crate::{
+    packet::ColumnHeader, ChanField, CompleteData, FieldBuffer, LidarProfile, Profile,
+    ValidOperationConfig,
+};
+
+use super::{
+    chan_field_code, OsfError, CHUNK_IDENTIFIER, HEADER_IDENTIFIER, LIDAR_SCAN_STREAM_TYPE,
+    LIDAR_SENSOR_TYPE, METADATA_IDENTIFIER, STATUS_INVALID, STATUS_VALID,
+};
+
+/// `OSF_VERSION::V_2_0`
+const VERSION: u64 = 20;
+
+/// `CHAN_FIELD_TYPE` of the SDK
+const CHANNEL_TYPE_U8: u8 = 1;
+const CHANNEL_TYPE_U16: u8 = 2;
+const CHANNEL_TYPE_U32: u8 = 3;
+
+const SENSOR_ID: u32 = 1;
+const STREAM_ID: u32 = 2;
+
+/// A chunk is written, once its messages exceed this size
+const DEFAULT_CHUNK_SIZE: usize = 2 * 1024 * 1024;
+
+struct Message {
+    ts: u64,
+    buffer: Vec<u8>,
+}
+
+struct ChunkOffset {
+    start_ts: u64,
+    end_ts: u64,
+    offset: u64,
+}
+
+/// Writes frames of an [crate::Aggregator] into an OSF file, which can be opened by the Ouster SDK and Studio
+/// [OsfWriter::finish] has to be called, otherwise the file stays invalid
+pub struct OsfWriter<W: Write + Seek> {
+    writer: W,
+    start: u64,
+    chunks_start: u64,
+    metadata_json: String,
+    fields: &'static [ChanField],
+    messages: Vec<Message>,
+    messages_size: usize,
+    chunks: Vec<ChunkOffset>,
+    chunk_size: usize,
+}
+
+impl<W: Write + Seek> OsfWriter<W> {
+    /// `metadata_json` is stored as is, so readers can use it to process the frames
+    pub fn new(
+        mut writer: W,
+        metadata_json: impl Into<String>,
+        profile: LidarProfile,
+    ) -> Result<Self, OsfError> {
+        let start = writer.stream_position()?;
+        // Placeholder until the position of the metadata is known
+        let header = header_block(STATUS_INVALID, 0, 0);
+        writer.write_all(&header)?;
+        Ok(Self {
+            writer,
+            start,
+            chunks_start: start + header.len() as u64,
+            metadata_json: metadata_json.into(),
+            fields: profile.fields(),
+            messages: Vec::new(),
+            messages_size: 0,
+            chunks: Vec::new(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        })
+    }
+
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Stores all fields of the profile as destaggered images, ranges with the 32 bit type of the SDK
+    pub fn write_frame<TProfile: Profile>(
+        &mut self,
+        frame: &CompleteData<TProfile>,
+        config: &ValidOperationConfig<TProfile>,
+    ) -> Result<(), OsfError> {
+        let fields = self
+            .fields
+            .iter()
+            .filter_map(|field| {
+                let image = frame.field(config, *field)?;
+                Some(match field_type(*field) {
+                    CHANNEL_TYPE_U32 => (*field, FieldBuffer::U32(image.to_u32())),
+                    _ => (*field, image),
+                })
+            })
+            .collect::<Vec<_>>();
+        let headers = frame.column_headers().collect::<Vec<_>>();
+        let timestamps = headers
+            .iter()
+            .map(|header| header.timestamp().as_nanos() as u64)
+            .collect::<Vec<_>>();
+        let measurement_ids = headers
+            .iter()
+            .map(|header| header.measurement_id())
+            .collect::<Vec<_>>();
+        // Missing packets are zeroed by the aggregator
+        let status = timestamps
+            .iter()
+            .map(|ts| (*ts != 0) as u32)
+            .collect::<Vec<_>>();
+
+        let buffer = lidar_scan(
+            frame.frame_id(),
+            &fields,
+            &timestamps,
+            &measurement_ids,
+            &status,
+        )?;
+        let ts = timestamps.iter().copied().find(|ts| *ts != 0).unwrap_or(0);
+        self.messages_size += buffer.len();
+        self.messages.push(Message { ts, buffer });
+        if self.messages_size >= self.chunk_size {
+            self.flush_chunk()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the metadata and marks the file as valid
+    pub fn finish(mut self) -> Result<W, OsfError> {
+        self.flush_chunk()?;
+        let metadata_offset = self.writer.stream_position()?;
+        let metadata = self.metadata_block();
+        self.writer.write_all(&metadata)?;
+        let end = self.writer.stream_position()?;
+
+        self.writer.seek(SeekFrom::Start(self.start))?;
+        self.writer.write_all(&header_block(
+            STATUS_VALID,
+            metadata_offset - self.start,
+            end - self.start,
+        ))?;
+        self.writer.seek(SeekFrom::Start(end))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn flush_chunk(&mut self) -> Result<(), OsfError> {
+        if self.messages.is_empty() {
+            return Ok(());
+        }
+        let offset = self.writer.stream_position()? - self.chunks_start;
+        let mut fbb = FlatBufferBuilder::new();
+        let messages = self
+            .messages
+            .iter()
+            .map(|message| {
+                let buffer = fbb.create_vector(&message.buffer);
+                let start = fbb.start_table();
+                fbb.push_slot_always(4, STREAM_ID);
+                fbb.push_slot_always(6, message.ts);
+                fbb.push_slot_always(8, buffer);
+                fbb.end_table(start)
+            })
+            .collect::<Vec<_>>();
+        let messages = fbb.create_vector(&messages);
+        let start = fbb.start_table();
+        fbb.push_slot_always(4, messages);
+        let root = fbb.end_table(start);
+        fbb.finish_size_prefixed(root, Some(as_identifier(CHUNK_IDENTIFIER)));
+        self.writer.write_all(&with_checksum(fbb.finished_data()))?;
+
+        self.chunks.push(ChunkOffset {
+            start_ts: self.messages.iter().map(|m| m.ts).min().unwrap_or(0),
+            end_ts: self.messages.iter().map(|m| m.ts).max().unwrap_or(0),
+            offset,
+        });
+        self.messages.clear();
+        self.messages_size = 0;
+        Ok(())
+    }
+
+    fn metadata_block(&self) -> Vec<u8> {
+        let mut fbb = FlatBufferBuilder::new();
+
+        let sensor = {
+            let mut inner = FlatBufferBuilder::new();
+            let metadata = inner.create_string(&self.metadata_json);
+            let start = inner.start_table();
+            inner.push_slot_always(4, metadata);
+            let root = inner.end_table(start);
+            inner.finish_size_prefixed(root, None);
+            inner.finished_data().to_vec()
+        };
+        let stream = {
+            let mut inner = FlatBufferBuilder::new();
+            let field_types = channel_fields(
+                &mut inner,
+                self.fields.iter().map(|field| (*field, field_type(*field))),
+            );
+            let start = inner.start_table();
+            inner.push_slot_always(4, SENSOR_ID);
+            inner.push_slot_always(6, field_types);
+            let root = inner.end_table(start);
+            inner.finish_size_prefixed(root, None);
+            inner.finished_data().to_vec()
+        };
+
+        let entries = [
+            (SENSOR_ID, LIDAR_SENSOR_TYPE, sensor),
+            (STREAM_ID, LIDAR_SCAN_STREAM_TYPE, stream),
+        ]
+        .iter()
+        .map(|(id, entry_type, buffer)| {
+            let entry_type = fbb.create_string(entry_type);
+            let buffer = fbb.create_vector(buffer);
+            let start = fbb.start_table();
+            fbb.push_slot_always(4, *id);
+            fbb.push_slot_always(6, entry_type);
+            fbb.push_slot_always(8, buffer);
+            fbb.end_table(start)
+        })
+        .collect::<Vec<_>>();
+        let entries = fbb.create_vector(&entries);
+
+        // Vector of struct ChunkOffset { start_ts, end_ts, offset }, pushed back to front
+        fbb.start_vector::<u64>(self.chunks.len() * 3);
+        for chunk in self.chunks.iter().rev() {
+            fbb.push(chunk.offset);
+            fbb.push(chunk.end_ts);
+            fbb.push(chunk.start_ts);
+        }
+        let chunks = fbb.end_vector::<u64>(self.chunks.len());
+
+        let id = fbb.create_string("ouster-rs-ce");
+        let start_ts = self.chunks.iter().map(|c| c.start_ts).min().unwrap_or(0);
+        let end_ts = self.chunks.iter().map(|c| c.end_ts).max().unwrap_or(0);
+        let start = fbb.start_table();
+        fbb.push_slot_always(4, id);
+        fbb.push_slot_always(6, start_ts);
+        fbb.push_slot_always(8, end_ts);
+        fbb.push_slot_always(10, chunks);
+        fbb.push_slot_always(12, entries);
+        let root = fbb.end_table(start);
+        fbb.finish_size_prefixed(root, Some(as_identifier(METADATA_IDENTIFIER)));
+        with_checksum(fbb.finished_data())
+    }
+}
+
+fn field_type(field: ChanField) -> u8 {
+    match field {
+        ChanField::Range | ChanField::Range2 => CHANNEL_TYPE_U32,
+        ChanField::Reflectivity | ChanField::Reflectivity2 => CHANNEL_TYPE_U8,
+        _ => CHANNEL_TYPE_U16,
+    }
+}
+
+fn channel_fields<'a>(
+    fbb: &mut FlatBufferBuilder<'a>,
+    fields: impl Iterator<Item = (ChanField, u8)>,
+) -> WIPOffset<Vector<'a, ForwardsUOffset<TableFinishedWIPOffset>>> {
+    let fields = fields
+        .map(|(field, field_type)| {
+            let start = fbb.start_table();
+            fbb.push_slot_always(4, chan_field_code(field));
+            fbb.push_slot_always(6, field_type);
+            fbb.end_table(start)
+        })
+        .collect::<Vec<_>>();
+    fbb.create_vector(&fields)
+}
+
+fn lidar_scan(
+    frame_id: u16,
+    fields: &[(ChanField, FieldBuffer)],
+    timestamps: &[u64],
+    measurement_ids: &[u16],
+    status: &[u32],
+) -> Result<Vec<u8>, OsfError> {
+    let mut fbb = FlatBufferBuilder::new();
+    let channels = fields
+        .iter()
+        .map(|(_, image)| {
+            let png = image.to_png()?;
+            let buffer = fbb.create_vector(&png);
+            let start = fbb.start_table();
+            fbb.push_slot_always(4, buffer);
+            Ok(fbb.end_table(start))
+        })
+        .collect::<Result<Vec<_>, OsfError>>()?;
+    let channels = fbb.create_vector(&channels);
+    let field_types = channel_fields(
+        &mut fbb,
+        fields.iter().map(|(field, image)| {
+            let field_type = match image {
+                FieldBuffer::U8(_) => CHANNEL_TYPE_U8,
+                FieldBuffer::U16(_) => CHANNEL_TYPE_U16,
+                FieldBuffer::U32(_) => CHANNEL_TYPE_U32,
+            };
+            (*field, field_type)
+        }),
+    );
+    let timestamps = fbb.create_vector(timestamps);
+    let measurement_ids = fbb.create_vector(measurement_ids);
+    let status = fbb.create_vector(status);
+
+    let start = fbb.start_table();
+    fbb.push_slot_always(4, channels);
+    fbb.push_slot_always(6, field_types);
+    fbb.push_slot_always(8, timestamps);
+    fbb.push_slot_always(10, measurement_ids);
+    fbb.push_slot_always(12, status);
+    fbb.push_slot_always(14, frame_id as i64);
+    let root = fbb.end_table(start);
+    fbb.finish_size_prefixed(root, None);
+    Ok(fbb.finished_data().to_vec())
+}
+
+/// All fields are always written, so the header keeps its size when it's rewritten
+fn header_block(status: u8, metadata_offset: u64, file_length: u64) -> Vec<u8> {
+    let mut fbb = FlatBufferBuilder::new();
+    let start = fbb.start_table();
+    fbb.push_slot_always(4, VERSION);
+    fbb.push_slot_always(6, status);
+    fbb.push_slot_always(8, metadata_offset);
+    fbb.push_slot_always(10, file_length);
+    let root = fbb.end_table(start);
+    fbb.finish_size_prefixed(root, Some(as_identifier(HEADER_IDENTIFIER)));
+    with_checksum(fbb.finished_data())
+}
+
+fn with_checksum(data: &[u8]) -> Vec<u8> {
+    let mut block = data.to_vec();
+    block.extend_from_slice(&crc32fast::hash(data).to_le_bytes());
+    block
+}
+
+fn as_identifier(identifier: &[u8; 4]) -> &str {
+    std::str::from_utf8(identifier).expect("Ascii identifier")
+}
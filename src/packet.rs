@@ -4,25 +4,41 @@ use bytemuck::Zeroable;
 
 use crate::{
     profile::{DualProfile, Profile},
-    SingleProfile,
+    FiveWordPixelProfile, LegacyProfile, ProfileMut, SingleProfile,
 };
 
 pub type Dual128OusterPacket = OusterPacket<DualProfile<16, 128>>;
 pub type Single128OusterPacket = OusterPacket<SingleProfile<16, 128>>;
 pub type Dual64OusterPacket = OusterPacket<DualProfile<16, 64>>;
+pub type Legacy64OusterPacket = OusterPacket<LegacyProfile<16, 64>>;
+pub type FiveWordPixel128OusterPacket = OusterPacket<FiveWordPixelProfile<16, 128>>;
 
 #[repr(C)]
 #[derive(Debug, Clone, Zeroable)]
-pub struct OusterPacket<TProfile: Profile> {
+pub struct OusterPacket<TProfile>
+where
+    TProfile: Profile,
+{
     pub header: TProfile::Header,
     pub columns: TProfile::Columns,
-    pub reserved: [u32; 8],
+    pub reserved: TProfile::Footer,
 }
 
 pub trait PacketHeader {
     fn frame_id(&self) -> u16;
 }
 
+pub trait ColumnHeader {
+    fn measurement_id(&self) -> u16;
+    fn timestamp(&self) -> Duration;
+}
+
+/// Setters of the column headers, which are used to encode packets
+pub trait ColumnHeaderMut: ColumnHeader {
+    fn set_measurement_id(&mut self, measurement_id: u16);
+    fn set_timestamp(&mut self, timestamp: Duration);
+}
+
 impl PacketHeader for OusterPacketHeader {
     fn frame_id(&self) -> u16 {
         self.frame_id
@@ -46,6 +62,12 @@ pub struct OusterPacketHeader {
     _reserved_2: [u32; 3],
 }
 
+impl OusterPacketHeader {
+    pub fn set_frame_id(&mut self, frame_id: u16) {
+        self.frame_id = frame_id;
+    }
+}
+
 #[repr(C)]
 #[derive(Debug, Default, Clone, Zeroable)]
 pub struct OusterPacketHeaderSafety {
@@ -72,23 +94,37 @@ impl PacketHeader for OusterPacketHeaderSafety {
     }
 }
 
+impl OusterPacketHeaderSafety {
+    pub fn set_frame_id(&mut self, frame_id: u16) {
+        self.frame_id = frame_id as u32;
+    }
+}
+
 impl<TProfile: Profile> Default for OusterPacket<TProfile> {
     fn default() -> Self {
         Self {
             header: Default::default(),
             columns: TProfile::initialize_columns(),
-            reserved: [0; 8],
+            reserved: Default::default(),
         }
     }
 }
 impl<TProfile: Profile> OusterPacket<TProfile> {
+    pub fn frame_id(&self) -> u16 {
+        TProfile::frame_id(self)
+    }
+
+    pub fn measurement_id(&self) -> u16 {
+        self.columns.as_ref()[0].channels_header.measurement_id()
+    }
+
     /// Not yet aware of Endianness... The buffer needs to be modified in that case and data_accessors of irregular bitsizes have to be adapted too
     /// mut allows to implement this in the future without breaking changes
     /// # Safety
     /// Memory has to be aligned with OusterPacket<TProfile>
     #[cfg(target_endian = "little")]
     pub unsafe fn from_aligned_memory(buffer: &[u8]) -> &Self {
-        if (buffer.as_ptr()) as usize % 32 != 0 {
+        if !(buffer.as_ptr() as usize).is_multiple_of(32) {
             panic!("Buffer has to be aligned");
         }
 
@@ -118,6 +154,13 @@ impl<TProfile: Profile> OusterPacket<TProfile> {
     }
 }
 
+impl<TProfile: ProfileMut> OusterPacket<TProfile> {
+    /// Legacy packets store the frame_id in every column
+    pub fn set_frame_id(&mut self, frame_id: u16) {
+        TProfile::set_frame_id(self, frame_id)
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 #[error("Expected {expected}, got {actual}")]
 pub struct SizeMismatchError {
@@ -127,16 +170,26 @@ pub struct SizeMismatchError {
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Zeroable)]
-pub struct Column<TProfile: Profile> {
-    pub channels_header: ChannelsHeader,
+pub struct Column<TProfile>
+where
+    TProfile: Profile,
+{
+    pub channels_header: TProfile::ChannelsHeader,
     pub channels: TProfile::Channels,
     phantom: PhantomData<TProfile>,
 }
 
+impl<TProfile: ProfileMut> Column<TProfile> {
+    /// Columns of missing or blocked measurements are invalid
+    pub fn set_valid(&mut self, valid: bool) {
+        TProfile::set_column_valid(self, valid)
+    }
+}
+
 impl<TProfile: Profile> Default for Column<TProfile> {
     fn default() -> Self {
         Self {
-            channels_header: ChannelsHeader::default(),
+            channels_header: Default::default(),
             channels: TProfile::initialize_channels(),
             phantom: PhantomData,
         }
@@ -155,14 +208,103 @@ pub struct ChannelsHeader {
 
 impl ChannelsHeader {
     pub fn timestamp(&self) -> Duration {
-        let mut bytes = [0; 8];
+        timestamp_from_parts(self.timestamp_a, self.timestamp_b)
+    }
+
+    pub fn set_timestamp(&mut self, timestamp: Duration) {
+        (self.timestamp_a, self.timestamp_b) = timestamp_to_parts(timestamp);
+    }
+
+    /// First bit of the status
+    pub fn is_valid(&self) -> bool {
+        self.status_and_reserve & 1 == 1
+    }
+
+    pub fn set_valid(&mut self, valid: bool) {
+        self.status_and_reserve = (self.status_and_reserve & !1) | valid as u16;
+    }
+}
+
+impl ColumnHeader for ChannelsHeader {
+    fn measurement_id(&self) -> u16 {
+        self.measurement_id
+    }
+
+    fn timestamp(&self) -> Duration {
+        self.timestamp()
+    }
+}
+
+impl ColumnHeaderMut for ChannelsHeader {
+    fn set_measurement_id(&mut self, measurement_id: u16) {
+        self.measurement_id = measurement_id;
+    }
 
-        bytes[0..4].copy_from_slice(&self.timestamp_a.to_le_bytes());
-        bytes[4..8].copy_from_slice(&self.timestamp_b.to_le_bytes());
-        Duration::from_nanos(u64::from_le_bytes(bytes))
+    fn set_timestamp(&mut self, timestamp: Duration) {
+        self.set_timestamp(timestamp)
     }
 }
 
+/// Column header of the LEGACY profile, which doesn't have a packet header
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Zeroable)]
+pub struct LegacyChannelsHeader {
+    timestamp_a: u32,
+    timestamp_b: u32,
+    pub measurement_id: u16,
+    pub frame_id: u16,
+    pub encoder_count: u32,
+}
+
+impl LegacyChannelsHeader {
+    pub fn timestamp(&self) -> Duration {
+        timestamp_from_parts(self.timestamp_a, self.timestamp_b)
+    }
+
+    pub fn set_timestamp(&mut self, timestamp: Duration) {
+        (self.timestamp_a, self.timestamp_b) = timestamp_to_parts(timestamp);
+    }
+}
+
+impl ColumnHeader for LegacyChannelsHeader {
+    fn measurement_id(&self) -> u16 {
+        self.measurement_id
+    }
+
+    fn timestamp(&self) -> Duration {
+        self.timestamp()
+    }
+}
+
+impl ColumnHeaderMut for LegacyChannelsHeader {
+    fn set_measurement_id(&mut self, measurement_id: u16) {
+        self.measurement_id = measurement_id;
+    }
+
+    fn set_timestamp(&mut self, timestamp: Duration) {
+        self.set_timestamp(timestamp)
+    }
+}
+
+fn timestamp_to_parts(timestamp: Duration) -> (u32, u32) {
+    let bytes = (timestamp.as_nanos() as u64).to_le_bytes();
+    (
+        u32::from_le_bytes(bytes[0..4].try_into().expect("4 bytes")),
+        u32::from_le_bytes(bytes[4..8].try_into().expect("4 bytes")),
+    )
+}
+
+fn timestamp_from_parts(timestamp_a: u32, timestamp_b: u32) -> Duration {
+    let mut bytes = [0; 8];
+
+    bytes[0..4].copy_from_slice(&timestamp_a.to_le_bytes());
+    bytes[4..8].copy_from_slice(&timestamp_b.to_le_bytes());
+    Duration::from_nanos(u64::from_le_bytes(bytes))
+}
+
+/// Lower 20 bits of the range word
+pub(crate) const RANGE_MASK: u32 = (1 << 20) - 1;
+
 #[repr(C)]
 #[derive(Debug, Default, Clone, Copy, Zeroable)]
 pub struct RangeData {
@@ -171,7 +313,7 @@ pub struct RangeData {
 
 impl RangeData {
     pub fn get_distance(&self, n_vec: u32) -> u16 {
-        (self.raw & ((1 << 20) - 1))
+        (self.raw & RANGE_MASK)
             .saturating_sub(n_vec)
             .min(u16::MAX as _) as u16
     }
@@ -179,6 +321,15 @@ impl RangeData {
     pub fn get_reflectifity(&self) -> u8 {
         (self.raw >> 24) as u8
     }
+
+    /// Raw range in mm, get_distance subtracts n_vec
+    pub fn set_distance(&mut self, distance: u32) {
+        self.raw = (self.raw & !RANGE_MASK) | distance.min(RANGE_MASK);
+    }
+
+    pub fn set_reflectivity(&mut self, reflectivity: u8) {
+        self.raw = (self.raw & !(0xff << 24)) | (reflectivity as u32) << 24;
+    }
 }
 
 #[cfg(test)]
@@ -196,5 +347,10 @@ mod tests {
         assert_eq!(64 / 8, std::mem::size_of::<crate::DualLowChannel>());
         assert_eq!(33024, std::mem::size_of::<Dual128OusterPacket>());
         assert_eq!(24832, std::mem::size_of::<Single128OusterPacket>());
+        assert_eq!(128 / 8, std::mem::size_of::<LegacyChannelsHeader>());
+        assert_eq!(96 / 8, std::mem::size_of::<crate::LegacyChannel>());
+        assert_eq!(160 / 8, std::mem::size_of::<crate::FiveWordPixelChannel>());
+        assert_eq!(12608, std::mem::size_of::<Legacy64OusterPacket>());
+        assert_eq!(41216, std::mem::size_of::<FiveWordPixel128OusterPacket>());
     }
 }
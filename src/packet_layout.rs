@@ -0,0 +1,330 @@
+use std::{fmt::Display, mem::size_of};
+
+use crate::{
+    ChannelsHeader, DualChannel, DualLowChannel, FiveWordPixelChannel, LegacyChannel,
+    LegacyChannelsHeader, LidarDataFormat, LidarProfile, LowDataChannel, OusterPacketHeader,
+    SingleChannel,
+};
+
+/// Measurement ids are below the horizontal resolution of the largest lidar mode
+const MAX_MEASUREMENT_ID: u16 = 4096;
+
+/// Profile and dimensions of a lidar packet, which determine its size
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PacketLayout {
+    pub udp_profile_lidar: LidarProfile,
+    pub columns_per_packet: u8,
+    pub pixels_per_column: u8,
+}
+
+impl PacketLayout {
+    pub const PIXELS_PER_COLUMN: [u8; 4] = [16, 32, 64, 128];
+    pub const COLUMNS_PER_PACKET: [u8; 5] = [1, 2, 4, 8, 16];
+
+    /// Every layout a sensor can send, legacy firmwares always send 16 columns per packet
+    pub fn all() -> impl Iterator<Item = Self> {
+        LidarProfile::ALL.into_iter().flat_map(|profile| {
+            Self::COLUMNS_PER_PACKET
+                .into_iter()
+                .filter(move |columns| profile != LidarProfile::Legacy || *columns == 16)
+                .flat_map(move |columns_per_packet| {
+                    Self::PIXELS_PER_COLUMN
+                        .into_iter()
+                        .map(move |pixels_per_column| Self {
+                            udp_profile_lidar: profile,
+                            columns_per_packet,
+                            pixels_per_column,
+                        })
+                })
+        })
+    }
+
+    /// Layouts of the given datagram size, without looking at the content
+    pub fn candidates(datagram_size: usize) -> Vec<Self> {
+        Self::all()
+            .filter(|layout| layout.packet_size() == datagram_size)
+            .collect()
+    }
+
+    /// The only layout, which matches size and content of the datagram
+    pub fn detect(datagram: &[u8]) -> Result<Self, DetectionError> {
+        let mut detector = LayoutDetector::default();
+        if !detector.push(datagram) {
+            return Err(DetectionError::UnknownSize(datagram.len()));
+        }
+        detector.result()
+    }
+
+    pub fn packet_size(&self) -> usize {
+        let (header, footer) = self.header_and_footer_size();
+        header + self.columns_per_packet as usize * self.column_size() + footer
+    }
+
+    /// Size and content checks, use it to sanity check a datagram against metadata
+    /// Every column has to carry consecutive measurement ids and timestamps
+    pub fn matches(&self, datagram: &[u8]) -> bool {
+        if datagram.len() != self.packet_size() {
+            return false;
+        }
+        let (header, _) = self.header_and_footer_size();
+        let columns = (0..self.columns_per_packet as usize)
+            .map(|i| &datagram[header + i * self.column_size()..][..self.column_size()])
+            .collect::<Vec<_>>();
+        let u16_at =
+            |bytes: &[u8], offset: usize| u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
+        let u64_at = |bytes: &[u8], offset: usize| {
+            u64::from_le_bytes(bytes[offset..offset + 8].try_into().expect("8 bytes"))
+        };
+
+        let first_measurement_id = u16_at(columns[0], 8);
+        if first_measurement_id >= MAX_MEASUREMENT_ID
+            || !(first_measurement_id as usize).is_multiple_of(columns.len())
+        {
+            return false;
+        }
+        let consecutive = columns
+            .iter()
+            .zip(first_measurement_id..)
+            .all(|(column, id)| u16_at(column, 8) == id);
+        let timestamps_increase = columns.windows(2).all(|pair| {
+            let (a, b) = (u64_at(pair[0], 0), u64_at(pair[1], 0));
+            a == 0 || b == 0 || a <= b
+        });
+        let legacy_consistent = self.udp_profile_lidar != LidarProfile::Legacy
+            || columns.iter().all(|column| {
+                let status = &column[column.len() - 4..];
+                u16_at(column, 10) == u16_at(columns[0], 10)
+                    && (status == [0; 4] || status == [0xff; 4])
+            });
+        consecutive && timestamps_increase && legacy_consistent
+    }
+
+    fn header_and_footer_size(&self) -> (usize, usize) {
+        match self.udp_profile_lidar {
+            LidarProfile::Legacy => (0, 0),
+            // The safety header of the dual low data profile has the same size
+            _ => (size_of::<OusterPacketHeader>(), size_of::<[u32; 8]>()),
+        }
+    }
+
+    fn column_size(&self) -> usize {
+        let pixels = self.pixels_per_column as usize;
+        match self.udp_profile_lidar {
+            LidarProfile::SingleReturn => {
+                size_of::<ChannelsHeader>() + pixels * size_of::<SingleChannel>()
+            }
+            LidarProfile::DualReturn => {
+                size_of::<ChannelsHeader>() + pixels * size_of::<DualChannel>()
+            }
+            LidarProfile::LowData => {
+                size_of::<ChannelsHeader>() + pixels * size_of::<LowDataChannel>()
+            }
+            LidarProfile::DualLowData => {
+                size_of::<ChannelsHeader>() + pixels * size_of::<DualLowChannel>()
+            }
+            LidarProfile::FiveWordPixel => {
+                size_of::<ChannelsHeader>() + pixels * size_of::<FiveWordPixelChannel>()
+            }
+            // Followed by the column status
+            LidarProfile::Legacy => {
+                size_of::<LegacyChannelsHeader>()
+                    + pixels * size_of::<LegacyChannel>()
+                    + size_of::<u32>()
+            }
+        }
+    }
+}
+
+impl From<&LidarDataFormat> for PacketLayout {
+    fn from(value: &LidarDataFormat) -> Self {
+        Self {
+            udp_profile_lidar: value.udp_profile_lidar,
+            columns_per_packet: value.columns_per_packet,
+            pixels_per_column: value.pixels_per_column,
+        }
+    }
+}
+
+impl Display for PacketLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} with {} beams and {} columns per packet",
+            self.udp_profile_lidar, self.pixels_per_column, self.columns_per_packet
+        )
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum DetectionError {
+    #[error("No lidar packet has {0} bytes")]
+    UnknownSize(usize),
+    #[error("The datagrams don't match a single packet layout")]
+    NoMatch,
+    #[error("The datagrams match several packet layouts: {}", display_layouts(.0))]
+    Ambiguous(Vec<PacketLayout>),
+}
+
+fn display_layouts(layouts: &[PacketLayout]) -> String {
+    layouts
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Narrows down the layout with every datagram, single datagrams can be ambiguous
+#[derive(Debug, Default)]
+pub struct LayoutDetector {
+    candidates: Option<Vec<PacketLayout>>,
+}
+
+impl LayoutDetector {
+    /// False if no layout has the size of the datagram, like IMU packets. These datagrams are ignored
+    pub fn push(&mut self, datagram: &[u8]) -> bool {
+        let sized = PacketLayout::candidates(datagram.len());
+        if sized.is_empty() {
+            return false;
+        }
+        let matching = sized.into_iter().filter(|layout| layout.matches(datagram));
+        match &mut self.candidates {
+            None => self.candidates = Some(matching.collect()),
+            Some(candidates) => {
+                let matching = matching.collect::<Vec<_>>();
+                candidates.retain(|layout| matching.contains(layout));
+            }
+        }
+        true
+    }
+
+    /// Layouts which match every pushed datagram
+    pub fn candidates(&self) -> &[PacketLayout] {
+        self.candidates.as_deref().unwrap_or_default()
+    }
+
+    pub fn result(&self) -> Result<PacketLayout, DetectionError> {
+        match self.candidates() {
+            [] => Err(DetectionError::NoMatch),
+            [layout] => Ok(*layout),
+            layouts => Err(DetectionError::Ambiguous(layouts.to_vec())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        ColumnHeaderMut, Dual128OusterPacket, OusterPacket, ProfileMut, Single128OusterPacket,
+    };
+
+    use super::*;
+
+    /// Packet with consecutive measurement ids, like a sensor sends them
+    fn packet<TProfile: ProfileMut>(first_measurement_id: u16) -> OusterPacket<TProfile> {
+        let mut packet = OusterPacket::<TProfile>::default();
+        packet.set_frame_id(3);
+        for (column, id) in packet
+            .columns
+            .as_mut()
+            .iter_mut()
+            .zip(first_measurement_id..)
+        {
+            column.channels_header.set_measurement_id(id);
+            column
+                .channels_header
+                .set_timestamp(Duration::from_micros(id as u64 * 100));
+            column.set_valid(true);
+        }
+        packet
+    }
+
+    #[test]
+    fn sizes_match_profiles() {
+        for layout in PacketLayout::all() {
+            let size = layout.packet_size();
+            assert!(PacketLayout::candidates(size).contains(&layout), "{layout}");
+        }
+        assert_eq!(
+            vec![PacketLayout {
+                udp_profile_lidar: LidarProfile::DualReturn,
+                columns_per_packet: 16,
+                pixels_per_column: 128
+            }],
+            PacketLayout::candidates(33024)
+        );
+        assert_eq!(
+            LidarProfile::SingleReturn,
+            PacketLayout::candidates(24832)[0].udp_profile_lidar
+        );
+        assert!(PacketLayout::candidates(48).is_empty());
+    }
+
+    #[test]
+    fn detect_packets() {
+        let dual = packet::<crate::DualProfile<16, 128>>(32);
+        assert_eq!(
+            Ok(std::mem::size_of::<Dual128OusterPacket>()),
+            PacketLayout::detect(dual.as_slice()).map(|x| x.packet_size())
+        );
+        let legacy = packet::<crate::LegacyProfile<16, 64>>(1008);
+        assert_eq!(
+            LidarProfile::Legacy,
+            PacketLayout::detect(legacy.as_slice())
+                .unwrap()
+                .udp_profile_lidar
+        );
+
+        // Measurement ids aren't consecutive
+        let zeroed = Single128OusterPacket::default();
+        assert_eq!(
+            Err(DetectionError::NoMatch),
+            PacketLayout::detect(zeroed.as_slice())
+        );
+        assert_eq!(
+            Err(DetectionError::UnknownSize(100)),
+            PacketLayout::detect(&[0; 100])
+        );
+    }
+
+    #[test]
+    fn ambiguous_sizes() {
+        // 128 low data pixels take as much space as 32 dual return pixels
+        let low = packet::<crate::LowDataProfile<16, 128>>(16);
+        let Err(DetectionError::Ambiguous(layouts)) = PacketLayout::detect(low.as_slice()) else {
+            panic!("Expected ambiguity");
+        };
+        let profiles = layouts
+            .iter()
+            .map(|x| (x.udp_profile_lidar, x.pixels_per_column))
+            .collect::<Vec<_>>();
+        assert!(profiles.contains(&(LidarProfile::LowData, 128)));
+        assert!(profiles.contains(&(LidarProfile::DualReturn, 32)));
+
+        // Metadata resolves the ambiguity
+        let format = LidarDataFormat {
+            column_window: (0, 1023),
+            columns_per_frame: 1024,
+            columns_per_packet: 16,
+            pixel_shift_by_row: vec![0; 128].into(),
+            pixels_per_column: 128,
+            udp_profile_lidar: LidarProfile::LowData,
+        };
+        assert!(PacketLayout::from(&format).matches(low.as_slice()));
+        assert!(!PacketLayout::from(&format).matches(&low.as_slice()[1..]));
+    }
+
+    #[test]
+    fn detector_ignores_other_datagrams() {
+        let mut detector = LayoutDetector::default();
+        assert!(!detector.push(&[0; 48]));
+        assert_eq!(Err(DetectionError::NoMatch), detector.result());
+        let packet = packet::<crate::FiveWordPixelProfile<16, 64>>(0);
+        assert!(detector.push(packet.as_slice()));
+        assert_eq!(
+            LidarProfile::FiveWordPixel,
+            detector.result().unwrap().udp_profile_lidar
+        );
+    }
+}
@@ -0,0 +1,663 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufReader, Read},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::Path,
+    time::Duration,
+};
+
+use crate::{
+    pcapng::{self, Block, TimestampResolution},
+    Aggregator, CompleteData, OusterConfig, OusterPacket, ParseMetadataError, Profile,
+};
+
+const MAGIC_MICROS: u32 = 0xa1b2_c3d4;
+const MAGIC_NANOS: u32 = 0xa1b2_3c4d;
+
+const LINKTYPE_NULL: u32 = 0;
+pub(crate) const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+pub(crate) const ETHERTYPE_IPV4: u16 = 0x0800;
+pub(crate) const ETHERTYPE_IPV6: u16 = 0x86dd;
+const ETHERTYPE_VLAN: [u16; 2] = [0x8100, 0x88a8];
+
+pub(crate) const PROTOCOL_UDP: u8 = 17;
+const IPV6_FRAGMENT_HEADER: u8 = 44;
+
+/// Largest IP packet with link layer headers, longer records are corrupted
+const MAX_CAPTURE_LEN: usize = u16::MAX as usize + 64;
+
+/// Incomplete datagrams are dropped, once more are pending
+const MAX_PENDING_FRAGMENTED: usize = 64;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PcapError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Invalid pcap file: {0}")]
+    Format(&'static str),
+    #[error("Unsupported link type {0}")]
+    UnsupportedLinkType(u32),
+    #[error("Can't write {0}")]
+    Unwritable(&'static str),
+}
+
+/// UDP payload with the addresses and the capture time of its (last) IP packet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UdpDatagram {
+    pub timestamp: Duration,
+    pub source: SocketAddr,
+    pub destination: SocketAddr,
+    pub payload: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct FragmentKey {
+    source: IpAddr,
+    destination: IpAddr,
+    identification: u32,
+}
+
+#[derive(Default)]
+struct Fragments {
+    /// (offset, payload)
+    parts: Vec<(usize, Vec<u8>)>,
+    total_len: Option<usize>,
+    /// Order of arrival, to drop the oldest datagram
+    sequence: u64,
+}
+
+impl Fragments {
+    /// The reassembled IP payload, once all parts arrived
+    fn reassemble(&mut self) -> Option<Vec<u8>> {
+        let total_len = self.total_len?;
+        self.parts.sort_by_key(|(offset, _)| *offset);
+        let mut end = 0;
+        for (offset, part) in &self.parts {
+            if *offset > end {
+                return None;
+            }
+            end = end.max(offset + part.len());
+        }
+        if end < total_len {
+            return None;
+        }
+        let mut out = vec![0; total_len];
+        // Parts behind the last fragment contradict its length and are dropped
+        for (offset, part) in self.parts.iter().filter(|(offset, _)| *offset < total_len) {
+            let len = part.len().min(total_len - offset);
+            out[*offset..offset + len].copy_from_slice(&part[..len]);
+        }
+        Some(out)
+    }
+}
+
+enum FileFormat {
+    Pcap {
+        nanos: bool,
+        link_type: u32,
+    },
+    /// (link type, resolution) by interface id
+    PcapNg {
+        interfaces: Vec<(u32, TimestampResolution)>,
+    },
+}
+
+/// Reads UDP datagrams from a pcap or pcapng file without libpcap
+///
+/// Supports Ethernet (with VLAN tags), raw IP, loopback and Linux cooked captures with IPv4 and IPv6.
+/// Fragmented IP packets, as created by lidar packets exceeding the MTU, are reassembled.
+/// Metadata, which is embedded by the [PcapNgWriter](crate::PcapNgWriter), is available right after opening.
+pub struct PcapReader<R> {
+    reader: R,
+    big_endian: bool,
+    format: FileFormat,
+    metadata_json: Option<String>,
+    fragments: HashMap<FragmentKey, Fragments>,
+    sequence: u64,
+}
+
+impl PcapReader<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, PcapError> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> PcapReader<R> {
+    pub fn new(mut reader: R) -> Result<Self, PcapError> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if u32::from_le_bytes(magic) == pcapng::SECTION_HEADER {
+            return Self::new_pcapng(reader);
+        }
+        let mut header = [0; 20];
+        reader.read_exact(&mut header)?;
+        let magic = u32::from_le_bytes(magic);
+        let (big_endian, nanos) = match magic {
+            MAGIC_MICROS => (false, false),
+            MAGIC_NANOS => (false, true),
+            _ if magic.swap_bytes() == MAGIC_MICROS => (true, false),
+            _ if magic.swap_bytes() == MAGIC_NANOS => (true, true),
+            _ => return Err(PcapError::Format("unknown magic number")),
+        };
+        let link_type = read_u32(&header[16..20], big_endian);
+        Ok(Self {
+            reader,
+            big_endian,
+            format: FileFormat::Pcap {
+                nanos,
+                link_type: link_type & 0xffff,
+            },
+            metadata_json: None,
+            fragments: HashMap::new(),
+            sequence: 0,
+        })
+    }
+
+    /// Reads the blocks up to the first interface, which has to precede all packets
+    fn new_pcapng(reader: R) -> Result<Self, PcapError> {
+        let mut this = Self {
+            reader,
+            big_endian: false,
+            format: FileFormat::PcapNg {
+                interfaces: Vec::new(),
+            },
+            metadata_json: None,
+            fragments: HashMap::new(),
+            sequence: 0,
+        };
+        this.read_section_header()?;
+        loop {
+            match this.read_block()? {
+                Some(Block::Interface {
+                    link_type,
+                    resolution,
+                }) => {
+                    this.push_interface(link_type, resolution);
+                    return Ok(this);
+                }
+                Some(Block::Section) => this.read_section_header()?,
+                Some(Block::Packet { .. }) => {
+                    return Err(PcapError::Format("packet before interface description"))
+                }
+                Some(Block::Other) => {}
+                None => return Err(PcapError::Format("missing interface description")),
+            }
+        }
+    }
+
+    /// Link type of the pcap header or of the first interface of a pcapng file
+    pub fn link_type(&self) -> u32 {
+        match &self.format {
+            FileFormat::Pcap { link_type, .. } => *link_type,
+            FileFormat::PcapNg { interfaces } => interfaces[0].0,
+        }
+    }
+
+    /// Metadata JSON of the section header comment of a pcapng file
+    pub fn metadata_json(&self) -> Option<&str> {
+        self.metadata_json.as_deref()
+    }
+
+    /// None, if the recording doesn't embed metadata
+    pub fn ouster_config(&self) -> Option<Result<OusterConfig, ParseMetadataError>> {
+        self.metadata_json
+            .as_ref()
+            .map(|json| OusterConfig::from_metadata_json(json.as_bytes()))
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// None at the end of the file. Packets which aren't UDP are skipped
+    pub fn next_datagram(&mut self) -> Result<Option<UdpDatagram>, PcapError> {
+        loop {
+            let (link_type, timestamp, data) = match self.format {
+                FileFormat::Pcap { nanos, link_type } => {
+                    let mut header = [0; 16];
+                    if !read_exact_or_eof(&mut self.reader, &mut header)? {
+                        return Ok(None);
+                    }
+                    let seconds = read_u32(&header[0..4], self.big_endian) as u64;
+                    let fraction = read_u32(&header[4..8], self.big_endian) as u64;
+                    let timestamp = Duration::from_secs(seconds)
+                        + if nanos {
+                            Duration::from_nanos(fraction)
+                        } else {
+                            Duration::from_micros(fraction)
+                        };
+                    let captured_len = read_u32(&header[8..12], self.big_endian) as usize;
+                    if captured_len > MAX_CAPTURE_LEN {
+                        return Err(PcapError::Format(
+                            "record exceeds the maximum capture length",
+                        ));
+                    }
+                    let mut data = vec![0; captured_len];
+                    self.reader.read_exact(&mut data)?;
+                    (link_type, timestamp, data)
+                }
+                FileFormat::PcapNg { .. } => match self.read_block()? {
+                    None => return Ok(None),
+                    Some(Block::Packet {
+                        interface,
+                        timestamp,
+                        data,
+                    }) => {
+                        let FileFormat::PcapNg { interfaces } = &self.format else {
+                            unreachable!("Format doesn't change")
+                        };
+                        let (link_type, resolution) = interfaces
+                            .get(interface as usize)
+                            .ok_or(PcapError::Format("unknown interface id"))?;
+                        (*link_type, resolution.to_duration(timestamp), data)
+                    }
+                    Some(Block::Interface {
+                        link_type,
+                        resolution,
+                    }) => {
+                        self.push_interface(link_type, resolution);
+                        continue;
+                    }
+                    Some(Block::Section) => {
+                        self.read_section_header()?;
+                        continue;
+                    }
+                    Some(Block::Other) => continue,
+                },
+            };
+
+            if let Some(datagram) = self.parse_link_layer(link_type, timestamp, &data)? {
+                return Ok(Some(datagram));
+            }
+        }
+    }
+
+    /// Body of a section header block, after its block type. Interfaces are only valid within their section
+    fn read_section_header(&mut self) -> Result<(), PcapError> {
+        let mut header = [0; 8];
+        self.reader.read_exact(&mut header)?;
+        self.big_endian = match u32::from_le_bytes(header[4..8].try_into().expect("4 bytes")) {
+            pcapng::BYTE_ORDER_MAGIC => false,
+            x if x.swap_bytes() == pcapng::BYTE_ORDER_MAGIC => true,
+            _ => return Err(PcapError::Format("unknown byte order magic")),
+        };
+        let total_len = read_u32(&header[0..4], self.big_endian) as usize;
+        if total_len > pcapng::MAX_BLOCK_LEN {
+            return Err(PcapError::Format("block exceeds the maximum size"));
+        }
+        // Byte order magic, version, section length, options, total length
+        let mut body = vec![
+            0;
+            total_len
+                .checked_sub(12)
+                .filter(|len| *len >= 16)
+                .ok_or(PcapError::Format("block too short"))?
+        ];
+        self.reader.read_exact(&mut body)?;
+        let options = &body[12..body.len() - 4];
+        if let Some(comment) = pcapng::options(options, self.big_endian)
+            .find(|(code, _)| *code == pcapng::OPT_COMMENT)
+            .and_then(|(_, value)| std::str::from_utf8(value).ok())
+            .filter(|comment| comment.trim_start().starts_with('{'))
+        {
+            self.metadata_json = Some(comment.to_owned());
+        }
+        if let FileFormat::PcapNg { interfaces } = &mut self.format {
+            interfaces.clear();
+        }
+        Ok(())
+    }
+
+    /// None at the end of the file. Section headers are read with [Self::read_section_header]
+    fn read_block(&mut self) -> Result<Option<Block>, PcapError> {
+        let mut header = [0; 4];
+        if !read_exact_or_eof(&mut self.reader, &mut header)? {
+            return Ok(None);
+        }
+        if u32::from_le_bytes(header) == pcapng::SECTION_HEADER {
+            return Ok(Some(Block::Section));
+        }
+        let block_type = read_u32(&header, self.big_endian);
+        self.reader.read_exact(&mut header)?;
+        let total_len = read_u32(&header, self.big_endian) as usize;
+        if total_len > pcapng::MAX_BLOCK_LEN {
+            return Err(PcapError::Format("block exceeds the maximum size"));
+        }
+        let mut body = vec![
+            0;
+            total_len
+                .checked_sub(8)
+                .ok_or(PcapError::Format("block too short"))?
+        ];
+        self.reader.read_exact(&mut body)?;
+        // Without the trailing total length
+        body.truncate(body.len().saturating_sub(4));
+        pcapng::parse_block(block_type, &body, self.big_endian).map(Some)
+    }
+
+    fn push_interface(&mut self, link_type: u32, resolution: TimestampResolution) {
+        if let FileFormat::PcapNg { interfaces } = &mut self.format {
+            interfaces.push((link_type, resolution));
+        }
+    }
+
+    /// Feeds the lidar packets into the aggregator until a frame is complete, None at the end of the file
+    /// The pending frames of the aggregator are flushed at the end of the file
+    /// Datagrams, which are sent to another port than `udp_port_lidar` or don't match the size of the packet, are ignored
+    pub fn next_frame<TProfile: Profile>(
+        &mut self,
+        aggregator: &mut Aggregator<TProfile>,
+        udp_port_lidar: Option<u16>,
+    ) -> Result<Option<CompleteData<TProfile>>, PcapError> {
+        while let Some(datagram) = self.next_datagram()? {
+            if udp_port_lidar.is_some_and(|port| port != datagram.destination.port())
+                || datagram.payload.len() != std::mem::size_of::<OusterPacket<TProfile>>()
+            {
+                continue;
+            }
+            aggregator.next_buffer().copy_from_slice(&datagram.payload);
+            if let Some(frame) = aggregator.process_tmp() {
+                return Ok(Some(frame));
+            }
+        }
+        Ok(aggregator.flush())
+    }
+
+    fn parse_link_layer(
+        &mut self,
+        link_type: u32,
+        timestamp: Duration,
+        data: &[u8],
+    ) -> Result<Option<UdpDatagram>, PcapError> {
+        let (ethertype, ip) = match link_type {
+            LINKTYPE_ETHERNET => {
+                let mut offset = 12;
+                let mut ethertype = read_be16(data, offset);
+                while ethertype.is_some_and(|x| ETHERTYPE_VLAN.contains(&x)) {
+                    offset += 4;
+                    ethertype = read_be16(data, offset);
+                }
+                (ethertype, data.get(offset + 2..))
+            }
+            LINKTYPE_LINUX_SLL => (read_be16(data, 14), data.get(16..)),
+            LINKTYPE_LINUX_SLL2 => (read_be16(data, 0), data.get(20..)),
+            // The version of the IP header is checked instead of the address family
+            LINKTYPE_NULL => (None, data.get(4..)),
+            LINKTYPE_RAW => (None, Some(data)),
+            link_type => return Err(PcapError::UnsupportedLinkType(link_type)),
+        };
+        let Some(ip) = ip else {
+            return Ok(None);
+        };
+        let version = ip.first().map(|x| x >> 4);
+        Ok(match (ethertype, version) {
+            (Some(ETHERTYPE_IPV4) | None, Some(4)) => self.parse_ipv4(timestamp, ip),
+            (Some(ETHERTYPE_IPV6) | None, Some(6)) => self.parse_ipv6(timestamp, ip),
+            _ => None,
+        })
+    }
+
+    fn parse_ipv4(&mut self, timestamp: Duration, ip: &[u8]) -> Option<UdpDatagram> {
+        let header_len = (*ip.first()? as usize & 0x0f) * 4;
+        let total_len = read_be16(ip, 2)? as usize;
+        if ip.get(9) != Some(&PROTOCOL_UDP) || total_len < header_len {
+            return None;
+        }
+        let source = IpAddr::from(Ipv4Addr::from(<[u8; 4]>::try_from(ip.get(12..16)?).ok()?));
+        let destination = IpAddr::from(Ipv4Addr::from(<[u8; 4]>::try_from(ip.get(16..20)?).ok()?));
+        let payload = ip.get(header_len..total_len)?;
+        let flags_and_offset = read_be16(ip, 6)?;
+        let more_fragments = flags_and_offset & 0x2000 != 0;
+        let offset = (flags_and_offset & 0x1fff) as usize * 8;
+        let key = FragmentKey {
+            source,
+            destination,
+            identification: read_be16(ip, 4)? as u32,
+        };
+        let udp = self.defragment(key, offset, more_fragments, payload)?;
+        parse_udp(timestamp, source, destination, &udp)
+    }
+
+    fn parse_ipv6(&mut self, timestamp: Duration, ip: &[u8]) -> Option<UdpDatagram> {
+        let payload_len = read_be16(ip, 4)? as usize;
+        let source = IpAddr::from(Ipv6Addr::from(<[u8; 16]>::try_from(ip.get(8..24)?).ok()?));
+        let destination = IpAddr::from(Ipv6Addr::from(<[u8; 16]>::try_from(ip.get(24..40)?).ok()?));
+        let payload = ip.get(40..40 + payload_len)?;
+        match *ip.get(6)? {
+            PROTOCOL_UDP => parse_udp(timestamp, source, destination, payload),
+            IPV6_FRAGMENT_HEADER => {
+                if *payload.first()? != PROTOCOL_UDP {
+                    return None;
+                }
+                let offset_and_flag = read_be16(payload, 2)?;
+                let key = FragmentKey {
+                    source,
+                    destination,
+                    identification: u32::from_be_bytes(payload.get(4..8)?.try_into().ok()?),
+                };
+                let udp = self.defragment(
+                    key,
+                    (offset_and_flag & !0x7) as usize,
+                    offset_and_flag & 1 != 0,
+                    payload.get(8..)?,
+                )?;
+                parse_udp(timestamp, source, destination, &udp)
+            }
+            // Other extension headers aren't sent by the sensor
+            _ => None,
+        }
+    }
+
+    /// The complete payload, once all fragments arrived
+    fn defragment(
+        &mut self,
+        key: FragmentKey,
+        offset: usize,
+        more_fragments: bool,
+        payload: &[u8],
+    ) -> Option<Vec<u8>> {
+        if offset == 0 && !more_fragments {
+            return Some(payload.to_vec());
+        }
+        if !self.fragments.contains_key(&key) && self.fragments.len() >= MAX_PENDING_FRAGMENTED {
+            let oldest = self
+                .fragments
+                .iter()
+                .min_by_key(|(_, fragments)| fragments.sequence)
+                .map(|(key, _)| key.clone())?;
+            self.fragments.remove(&oldest);
+        }
+        self.sequence += 1;
+        let sequence = self.sequence;
+        let fragments = self
+            .fragments
+            .entry(key.clone())
+            .or_insert_with(|| Fragments {
+                sequence,
+                ..Default::default()
+            });
+        fragments.parts.push((offset, payload.to_vec()));
+        if !more_fragments {
+            fragments.total_len = Some(offset + payload.len());
+        }
+        let reassembled = fragments.reassemble()?;
+        self.fragments.remove(&key);
+        Some(reassembled)
+    }
+}
+
+impl<R: Read> Iterator for PcapReader<R> {
+    type Item = Result<UdpDatagram, PcapError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_datagram().transpose()
+    }
+}
+
+fn parse_udp(
+    timestamp: Duration,
+    source: IpAddr,
+    destination: IpAddr,
+    udp: &[u8],
+) -> Option<UdpDatagram> {
+    let len = read_be16(udp, 4)? as usize;
+    Some(UdpDatagram {
+        timestamp,
+        source: SocketAddr::new(source, read_be16(udp, 0)?),
+        destination: SocketAddr::new(destination, read_be16(udp, 2)?),
+        payload: udp.get(8..len)?.to_vec(),
+    })
+}
+
+/// False if the reader is at its end
+fn read_exact_or_eof(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<bool> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+pub(crate) fn read_u32(bytes: &[u8], big_endian: bool) -> u32 {
+    let bytes = bytes.try_into().expect("4 bytes");
+    if big_endian {
+        u32::from_be_bytes(bytes)
+    } else {
+        u32::from_le_bytes(bytes)
+    }
+}
+
+fn read_be16(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2)
+        .map(|x| u16::from_be_bytes([x[0], x[1]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_utils::{frame_packets, pcap_file, test_config},
+        DualProfile, LidarProfile,
+    };
+
+    use super::*;
+
+    #[test]
+    fn reassembles_fragments() {
+        let payload = (0..4000).map(|x| x as u8).collect::<Vec<_>>();
+        let data = pcap_file(&[
+            (Duration::from_millis(1500), 7502, &payload[..]),
+            (Duration::from_millis(1600), 7503, &[1, 2, 3]),
+        ]);
+        let datagrams = PcapReader::new(data.as_slice())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(2, datagrams.len());
+        assert_eq!(payload, datagrams[0].payload);
+        assert_eq!(7502, datagrams[0].destination.port());
+        assert_eq!(Duration::from_millis(1500), datagrams[0].timestamp);
+        assert_eq!(vec![1, 2, 3], datagrams[1].payload);
+    }
+
+    fn raw_reader() -> (PcapReader<io::Empty>, FragmentKey) {
+        let reader = PcapReader {
+            reader: io::empty(),
+            big_endian: false,
+            format: FileFormat::Pcap {
+                nanos: false,
+                link_type: LINKTYPE_RAW,
+            },
+            metadata_json: None,
+            fragments: HashMap::new(),
+            sequence: 0,
+        };
+        let key = FragmentKey {
+            source: Ipv4Addr::LOCALHOST.into(),
+            destination: Ipv4Addr::LOCALHOST.into(),
+            identification: 1,
+        };
+        (reader, key)
+    }
+
+    #[test]
+    fn reassembles_out_of_order_fragments() {
+        let (mut reader, key) = raw_reader();
+        assert_eq!(None, reader.defragment(key.clone(), 8, false, &[3, 4]));
+        assert_eq!(
+            Some(vec![1, 0, 0, 0, 0, 0, 0, 2, 3, 4]),
+            reader.defragment(key, 0, true, &[1, 0, 0, 0, 0, 0, 0, 2])
+        );
+        assert!(reader.fragments.is_empty());
+    }
+
+    #[test]
+    fn fragments_past_the_end() {
+        let (mut reader, key) = raw_reader();
+        assert_eq!(None, reader.defragment(key.clone(), 32, true, &[9; 8]));
+        assert_eq!(None, reader.defragment(key.clone(), 24, true, &[9; 8]));
+        assert_eq!(None, reader.defragment(key.clone(), 0, true, &[1; 16]));
+        // The last fragment ends at 24, the overlapping and later parts are cut off
+        assert_eq!(
+            Some([[1; 16], [2; 16]].concat()[..24].to_vec()),
+            reader.defragment(key, 16, false, &[2; 8])
+        );
+    }
+
+    #[test]
+    fn frames_from_pcap() {
+        let config = test_config::<DualProfile<16, 64>>(LidarProfile::DualReturn, &[0; 64]);
+        let packets = frame_packets::<DualProfile<16, 64>>(2);
+        let datagrams = packets
+            .iter()
+            .map(|x| (Duration::ZERO, 7502, x.as_slice()))
+            .chain([(Duration::ZERO, 7502, &[0u8; 10][..])])
+            .collect::<Vec<_>>();
+        let data = pcap_file(&datagrams);
+
+        let mut aggregator = Aggregator::new(&config.lidar_data_format.column_window);
+        let mut reader = PcapReader::new(data.as_slice()).unwrap();
+        let frame = reader
+            .next_frame(&mut aggregator, Some(7502))
+            .unwrap()
+            .unwrap();
+        assert_eq!((0, 64), (frame.frame_id(), frame.len()));
+        // The last frame is flushed at the end of the file
+        let frame = reader
+            .next_frame(&mut aggregator, Some(7502))
+            .unwrap()
+            .unwrap();
+        assert_eq!((1, 64), (frame.frame_id(), frame.len()));
+        assert!(reader
+            .next_frame(&mut aggregator, Some(7502))
+            .unwrap()
+            .is_none());
+
+        let mut reader = PcapReader::new(data.as_slice()).unwrap();
+        assert!(reader
+            .next_frame(&mut aggregator, Some(7503))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn oversized_record() {
+        let mut data = pcap_file(&[(Duration::ZERO, 7502, &[1, 2, 3])]);
+        // Captured length of the first record behind the file header
+        data[32..36].copy_from_slice(&u32::MAX.to_le_bytes());
+        let mut reader = PcapReader::new(data.as_slice()).unwrap();
+        assert!(matches!(reader.next_datagram(), Err(PcapError::Format(_))));
+    }
+
+    #[test]
+    fn invalid_magic() {
+        assert!(matches!(
+            PcapReader::new(&[0u8; 24][..]),
+            Err(PcapError::Format(_))
+        ));
+    }
+}
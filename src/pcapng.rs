@@ -0,0 +1,479 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    net::IpAddr,
+    path::Path,
+    time::Duration,
+};
+
+use crate::{
+    pcap::{read_u32, ETHERTYPE_IPV4, ETHERTYPE_IPV6, LINKTYPE_ETHERNET, PROTOCOL_UDP},
+    PcapError, UdpDatagram,
+};
+
+/// Block type of the section header, which is the same in both byte orders
+pub(crate) const SECTION_HEADER: u32 = 0x0a0d_0d0a;
+pub(crate) const BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+
+const INTERFACE_DESCRIPTION: u32 = 1;
+const OBSOLETE_PACKET: u32 = 2;
+const SIMPLE_PACKET: u32 = 3;
+const ENHANCED_PACKET: u32 = 6;
+
+const OPT_END: u16 = 0;
+pub(crate) const OPT_COMMENT: u16 = 1;
+const OPT_SHB_USERAPPL: u16 = 4;
+const OPT_IF_TSRESOL: u16 = 9;
+
+/// Larger blocks are rejected as corrupted instead of being allocated, the same limit as Wireshark
+pub(crate) const MAX_BLOCK_LEN: usize = 16 * 1024 * 1024;
+
+/// Largest UDP payload in an IPv4 packet
+const MAX_PAYLOAD: usize = u16::MAX as usize - 20 - 8;
+
+pub(crate) enum Block {
+    /// Only the block type is read
+    Section,
+    Interface {
+        link_type: u32,
+        resolution: TimestampResolution,
+    },
+    Packet {
+        interface: u32,
+        /// In units of the resolution of the interface
+        timestamp: u64,
+        data: Vec<u8>,
+    },
+    Other,
+}
+
+/// Fraction of a second of a timestamp unit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TimestampResolution {
+    /// 10^-n
+    Decimal(u8),
+    /// 2^-n
+    Binary(u8),
+}
+
+impl TimestampResolution {
+    pub(crate) fn to_duration(self, timestamp: u64) -> Duration {
+        let nanos = match self {
+            TimestampResolution::Decimal(exponent) if exponent <= 9 => {
+                timestamp as u128 * 10u128.pow(9 - exponent as u32)
+            }
+            TimestampResolution::Decimal(exponent) => {
+                timestamp as u128 / 10u128.pow((exponent as u32 - 9).min(38))
+            }
+            TimestampResolution::Binary(exponent) => {
+                (timestamp as u128 * 1_000_000_000) >> exponent.min(127)
+            }
+        };
+        Duration::from_nanos(nanos.min(u64::MAX as u128) as u64)
+    }
+}
+
+/// (code, value) of an option list, up to the end of options
+pub(crate) fn options(mut data: &[u8], big_endian: bool) -> impl Iterator<Item = (u16, &[u8])> {
+    std::iter::from_fn(move || {
+        let code = read_u16(data.get(0..2)?, big_endian);
+        let len = read_u16(data.get(2..4)?, big_endian) as usize;
+        let value = data.get(4..4 + len)?;
+        data = data.get(4 + len.next_multiple_of(4)..).unwrap_or_default();
+        (code != OPT_END).then_some((code, value))
+    })
+}
+
+/// Body without block type, length and trailing length
+pub(crate) fn parse_block(
+    block_type: u32,
+    body: &[u8],
+    big_endian: bool,
+) -> Result<Block, PcapError> {
+    let u32_at = |offset: usize| {
+        body.get(offset..offset + 4)
+            .map(|x| read_u32(x, big_endian))
+            .ok_or(PcapError::Format("block too short"))
+    };
+    let packet = |interface, timestamp_offset: usize| -> Result<Block, PcapError> {
+        let timestamp =
+            (u32_at(timestamp_offset)? as u64) << 32 | u32_at(timestamp_offset + 4)? as u64;
+        let captured_len = u32_at(timestamp_offset + 8)? as usize;
+        let data_offset = timestamp_offset + 16;
+        let data = body
+            .get(data_offset..data_offset + captured_len)
+            .ok_or(PcapError::Format("packet exceeds block"))?;
+        Ok(Block::Packet {
+            interface,
+            timestamp,
+            data: data.to_vec(),
+        })
+    };
+    match block_type {
+        INTERFACE_DESCRIPTION => {
+            let link_type = read_u16(
+                body.get(0..2).ok_or(PcapError::Format("block too short"))?,
+                big_endian,
+            );
+            let resolution = options(body.get(8..).unwrap_or_default(), big_endian)
+                .find(|(code, _)| *code == OPT_IF_TSRESOL)
+                .and_then(|(_, value)| value.first())
+                .map_or(TimestampResolution::Decimal(6), |x| {
+                    if x & 0x80 == 0 {
+                        TimestampResolution::Decimal(*x)
+                    } else {
+                        TimestampResolution::Binary(x & 0x7f)
+                    }
+                });
+            Ok(Block::Interface {
+                link_type: link_type as u32,
+                resolution,
+            })
+        }
+        ENHANCED_PACKET => packet(u32_at(0)?, 4),
+        OBSOLETE_PACKET => packet(
+            read_u16(
+                body.get(0..2).ok_or(PcapError::Format("block too short"))?,
+                big_endian,
+            ) as u32,
+            4,
+        ),
+        // Without timestamp and captured length, the data is padded to 32 bits
+        SIMPLE_PACKET => {
+            let len = (u32_at(0)? as usize).min(body.len().saturating_sub(4));
+            Ok(Block::Packet {
+                interface: 0,
+                timestamp: 0,
+                data: body[4..4 + len].to_vec(),
+            })
+        }
+        _ => Ok(Block::Other),
+    }
+}
+
+fn read_u16(bytes: &[u8], big_endian: bool) -> u16 {
+    let bytes = bytes.try_into().expect("2 bytes");
+    if big_endian {
+        u16::from_be_bytes(bytes)
+    } else {
+        u16::from_le_bytes(bytes)
+    }
+}
+
+/// Writes UDP datagrams into a pcapng file with a single Ethernet interface and nanosecond timestamps
+///
+/// The metadata JSON is stored as comment of the section header, so it can't be separated from the recording.
+/// Options are limited to 65535 bytes, larger metadata is [PcapError::Unwritable].
+/// Wireshark shows it as capture file comment, [PcapReader](crate::PcapReader) reads it on opening.
+pub struct PcapNgWriter<W: Write> {
+    writer: W,
+    identification: u16,
+}
+
+impl PcapNgWriter<BufWriter<File>> {
+    pub fn create(path: impl AsRef<Path>, metadata_json: Option<&str>) -> Result<Self, PcapError> {
+        Self::new(BufWriter::new(File::create(path)?), metadata_json)
+    }
+}
+
+impl<W: Write> PcapNgWriter<W> {
+    /// Writes section header and interface description
+    pub fn new(mut writer: W, metadata_json: Option<&str>) -> Result<Self, PcapError> {
+        let mut section = Vec::new();
+        section.extend_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+        // Version 1.0 and unknown section length
+        section.extend_from_slice(&[1, 0, 0, 0]);
+        section.extend_from_slice(&(-1i64).to_le_bytes());
+        if let Some(json) = metadata_json {
+            if json.len() > u16::MAX as usize {
+                return Err(PcapError::Unwritable("metadata exceeding 65535 bytes"));
+            }
+            push_option(&mut section, OPT_COMMENT, json.as_bytes());
+        }
+        push_option(
+            &mut section,
+            OPT_SHB_USERAPPL,
+            env!("CARGO_PKG_NAME").as_bytes(),
+        );
+        push_option(&mut section, OPT_END, &[]);
+        write_block(&mut writer, SECTION_HEADER, &section)?;
+
+        let mut interface = Vec::new();
+        interface.extend_from_slice(&(LINKTYPE_ETHERNET as u16).to_le_bytes());
+        // Reserved and unlimited snap length
+        interface.extend_from_slice(&[0; 6]);
+        push_option(&mut interface, OPT_IF_TSRESOL, &[9]);
+        push_option(&mut interface, OPT_END, &[]);
+        write_block(&mut writer, INTERFACE_DESCRIPTION, &interface)?;
+
+        Ok(Self {
+            writer,
+            identification: 0,
+        })
+    }
+
+    /// The datagram isn't fragmented, so jumbo packets are written like captured on the loopback interface
+    pub fn write_datagram(&mut self, datagram: &UdpDatagram) -> Result<(), PcapError> {
+        if datagram.payload.len() > MAX_PAYLOAD {
+            return Err(PcapError::Unwritable("UDP payload exceeding 65507 bytes"));
+        }
+        let udp_len = datagram.payload.len() + 8;
+        let mut frame = vec![0; 12];
+        match (datagram.source.ip(), datagram.destination.ip()) {
+            (IpAddr::V4(source), IpAddr::V4(destination)) => {
+                frame.extend_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+                let mut header = [0; 20];
+                header[0] = 0x45;
+                header[2..4].copy_from_slice(&(udp_len as u16 + 20).to_be_bytes());
+                header[4..6].copy_from_slice(&self.identification.to_be_bytes());
+                header[8] = 64;
+                header[9] = PROTOCOL_UDP;
+                header[12..16].copy_from_slice(&source.octets());
+                header[16..20].copy_from_slice(&destination.octets());
+                let checksum = !ones_complement_sum(&header, 0);
+                header[10..12].copy_from_slice(&checksum.to_be_bytes());
+                frame.extend_from_slice(&header);
+                self.identification = self.identification.wrapping_add(1);
+            }
+            (IpAddr::V6(source), IpAddr::V6(destination)) => {
+                frame.extend_from_slice(&ETHERTYPE_IPV6.to_be_bytes());
+                frame.extend_from_slice(&[0x60, 0, 0, 0]);
+                frame.extend_from_slice(&(udp_len as u16).to_be_bytes());
+                frame.extend_from_slice(&[PROTOCOL_UDP, 64]);
+                frame.extend_from_slice(&source.octets());
+                frame.extend_from_slice(&destination.octets());
+            }
+            _ => return Err(PcapError::Unwritable("datagram between IPv4 and IPv6")),
+        }
+
+        let mut udp = Vec::with_capacity(udp_len);
+        udp.extend_from_slice(&datagram.source.port().to_be_bytes());
+        udp.extend_from_slice(&datagram.destination.port().to_be_bytes());
+        udp.extend_from_slice(&(udp_len as u16).to_be_bytes());
+        udp.extend_from_slice(&[0, 0]);
+        udp.extend_from_slice(&datagram.payload);
+        // Pseudo header of source, destination, protocol and length
+        let addresses = match (datagram.source.ip(), datagram.destination.ip()) {
+            (IpAddr::V4(s), IpAddr::V4(d)) => [s.octets(), d.octets()].concat(),
+            (IpAddr::V6(s), IpAddr::V6(d)) => [s.octets(), d.octets()].concat(),
+            _ => unreachable!("Checked above"),
+        };
+        let pseudo = ones_complement_sum(&addresses, PROTOCOL_UDP as u32 + udp_len as u32);
+        let checksum = match !ones_complement_sum(&udp, pseudo as u32) {
+            // Zero means no checksum
+            0 => 0xffff,
+            x => x,
+        };
+        udp[6..8].copy_from_slice(&checksum.to_be_bytes());
+        frame.extend_from_slice(&udp);
+
+        let timestamp = datagram.timestamp.as_nanos() as u64;
+        let mut packet = Vec::with_capacity(frame.len() + 24);
+        packet.extend_from_slice(&0u32.to_le_bytes());
+        packet.extend_from_slice(&((timestamp >> 32) as u32).to_le_bytes());
+        packet.extend_from_slice(&(timestamp as u32).to_le_bytes());
+        packet.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        packet.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        packet.extend_from_slice(&frame);
+        packet.resize(packet.len().next_multiple_of(4), 0);
+        write_block(&mut self.writer, ENHANCED_PACKET, &packet)?;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Body has to be padded to 32 bits
+fn write_block(writer: &mut impl Write, block_type: u32, body: &[u8]) -> io::Result<()> {
+    let total_len = (body.len() + 12) as u32;
+    writer.write_all(&block_type.to_le_bytes())?;
+    writer.write_all(&total_len.to_le_bytes())?;
+    writer.write_all(body)?;
+    writer.write_all(&total_len.to_le_bytes())
+}
+
+fn push_option(buf: &mut Vec<u8>, code: u16, value: &[u8]) {
+    buf.extend_from_slice(&code.to_le_bytes());
+    buf.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    buf.extend_from_slice(value);
+    buf.resize(buf.len().next_multiple_of(4), 0);
+}
+
+/// Internet checksum of big endian 16 bit words, started with `initial`
+fn ones_complement_sum(data: &[u8], initial: u32) -> u16 {
+    let mut sum = data.chunks(2).fold(initial, |sum, chunk| {
+        sum + u16::from_be_bytes([chunk[0], chunk.get(1).copied().unwrap_or_default()]) as u32
+    });
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    sum as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Cursor,
+        net::{Ipv6Addr, SocketAddr},
+    };
+
+    use crate::{PcapReader, PcapReplayer};
+
+    use super::*;
+
+    const METADATA: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/data/os-1-64_v3.0.1_1024x10.json"
+    ));
+
+    fn datagram(timestamp: Duration, destination: &str, payload: Vec<u8>) -> UdpDatagram {
+        let destination: SocketAddr = destination.parse().unwrap();
+        let source = match destination {
+            SocketAddr::V4(_) => "192.168.1.2:7502".parse().unwrap(),
+            SocketAddr::V6(_) => "[fe80::2]:7502".parse().unwrap(),
+        };
+        UdpDatagram {
+            timestamp,
+            source,
+            destination,
+            payload,
+        }
+    }
+
+    #[test]
+    fn roundtrip_with_metadata() {
+        let datagrams = [
+            datagram(
+                Duration::new(1_700_000_000, 123_456_789),
+                "192.168.1.1:7502",
+                (0..33024).map(|x| x as u8).collect(),
+            ),
+            datagram(Duration::from_nanos(5), "[fe80::1]:7503", vec![1, 2, 3]),
+        ];
+        let mut writer = PcapNgWriter::new(Vec::new(), Some(METADATA)).unwrap();
+        for datagram in &datagrams {
+            writer.write_datagram(datagram).unwrap();
+        }
+        let data = writer.finish().unwrap();
+
+        let mut reader = PcapReader::new(data.as_slice()).unwrap();
+        assert_eq!(Some(METADATA), reader.metadata_json());
+        let config = reader.ouster_config().unwrap().unwrap();
+        assert_eq!(64, config.lidar_data_format.pixels_per_column);
+        assert_eq!(LINKTYPE_ETHERNET, reader.link_type());
+        let read = reader.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(datagrams.to_vec(), read);
+    }
+
+    #[test]
+    fn checksums() {
+        let mut writer = PcapNgWriter::new(Vec::new(), None).unwrap();
+        writer
+            .write_datagram(&datagram(Duration::ZERO, "192.168.1.1:7502", vec![1, 2, 3]))
+            .unwrap();
+        let data = writer.finish().unwrap();
+        // Frame of 45 bytes padded to 48, followed by the total length
+        let frame = &data[data.len() - 4 - 48..][..45];
+        let (ip, udp) = (&frame[14..34], &frame[34..]);
+        assert_eq!(0xffff, ones_complement_sum(ip, 0));
+        let pseudo = ones_complement_sum(&ip[12..20], PROTOCOL_UDP as u32 + udp.len() as u32);
+        assert_eq!(0xffff, ones_complement_sum(udp, pseudo as u32));
+
+        let reader = PcapReader::new(data.as_slice()).unwrap();
+        assert_eq!(None, reader.metadata_json());
+        assert!(reader.ouster_config().is_none());
+    }
+
+    #[test]
+    fn resolutions() {
+        assert_eq!(
+            Duration::from_micros(3),
+            TimestampResolution::Decimal(6).to_duration(3)
+        );
+        assert_eq!(
+            Duration::from_nanos(1),
+            TimestampResolution::Decimal(10).to_duration(10)
+        );
+        assert_eq!(
+            Duration::from_millis(500),
+            TimestampResolution::Binary(1).to_duration(1)
+        );
+    }
+
+    #[test]
+    fn unwritable() {
+        let mut writer = PcapNgWriter::new(Vec::new(), None).unwrap();
+        let mut mixed = datagram(Duration::ZERO, "192.168.1.1:7502", vec![]);
+        mixed.source = SocketAddr::new(Ipv6Addr::LOCALHOST.into(), 1);
+        assert!(matches!(
+            writer.write_datagram(&mixed),
+            Err(PcapError::Unwritable(_))
+        ));
+        assert!(matches!(
+            PcapNgWriter::new(Vec::new(), Some(&" ".repeat(70_000))),
+            Err(PcapError::Unwritable(_))
+        ));
+    }
+
+    #[test]
+    fn truncated_blocks() {
+        let mut section = SECTION_HEADER.to_le_bytes().to_vec();
+        section.extend_from_slice(&12u32.to_le_bytes());
+        section.extend_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+        assert!(matches!(
+            PcapReader::new(section.as_slice()),
+            Err(PcapError::Format(_))
+        ));
+
+        for (block_type, body) in [(OBSOLETE_PACKET, &[0u8][..]), (SIMPLE_PACKET, &[0; 2])] {
+            let mut data = PcapNgWriter::new(Vec::new(), None)
+                .unwrap()
+                .finish()
+                .unwrap();
+            write_block(&mut data, block_type, body).unwrap();
+            let mut reader = PcapReader::new(data.as_slice()).unwrap();
+            assert!(matches!(reader.next_datagram(), Err(PcapError::Format(_))));
+        }
+    }
+
+    #[test]
+    fn oversized_blocks() {
+        let mut section = SECTION_HEADER.to_le_bytes().to_vec();
+        section.extend_from_slice(&u32::MAX.to_le_bytes());
+        section.extend_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+        assert!(matches!(
+            PcapReader::new(section.as_slice()),
+            Err(PcapError::Format(_))
+        ));
+
+        let mut data = PcapNgWriter::new(Vec::new(), None)
+            .unwrap()
+            .finish()
+            .unwrap();
+        data.extend_from_slice(&ENHANCED_PACKET.to_le_bytes());
+        data.extend_from_slice(&u32::MAX.to_le_bytes());
+        let mut reader = PcapReader::new(data.as_slice()).unwrap();
+        assert!(matches!(reader.next_datagram(), Err(PcapError::Format(_))));
+    }
+
+    #[test]
+    fn replayer_reads_metadata() {
+        let mut writer = PcapNgWriter::new(Vec::new(), Some(METADATA)).unwrap();
+        writer
+            .write_datagram(&datagram(Duration::ZERO, "127.0.0.1:7502", vec![1]))
+            .unwrap();
+        let replayer = PcapReplayer::new(Cursor::new(writer.finish().unwrap())).unwrap();
+        assert_eq!(
+            7502,
+            replayer
+                .ouster_config()
+                .unwrap()
+                .unwrap()
+                .config_params
+                .udp_port_lidar
+        );
+    }
+}
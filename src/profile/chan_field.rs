@@ -0,0 +1,79 @@
+use std::{fmt::Display, str::FromStr};
+
+/// Fields a pixel can contain. Which fields are available depends on the profile
+/// Names are the ones used by the ouster sdk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ChanField {
+    Range,
+    Range2,
+    Reflectivity,
+    Reflectivity2,
+    Signal,
+    Signal2,
+    Nir,
+}
+
+impl ChanField {
+    pub const ALL: [ChanField; 7] = [
+        ChanField::Range,
+        ChanField::Range2,
+        ChanField::Reflectivity,
+        ChanField::Reflectivity2,
+        ChanField::Signal,
+        ChanField::Signal2,
+        ChanField::Nir,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChanField::Range => "RANGE",
+            ChanField::Range2 => "RANGE2",
+            ChanField::Reflectivity => "REFLECTIVITY",
+            ChanField::Reflectivity2 => "REFLECTIVITY2",
+            ChanField::Signal => "SIGNAL",
+            ChanField::Signal2 => "SIGNAL2",
+            ChanField::Nir => "NEAR_IR",
+        }
+    }
+
+    /// 0 for the strongest return, 1 for the second return and None for fields which aren't bound to a return
+    pub fn return_index(&self) -> Option<usize> {
+        match self {
+            ChanField::Range | ChanField::Reflectivity | ChanField::Signal => Some(0),
+            ChanField::Range2 | ChanField::Reflectivity2 | ChanField::Signal2 => Some(1),
+            ChanField::Nir => None,
+        }
+    }
+}
+
+impl Display for ChanField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ChanField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|x| x.name() == s || (s == "NIR" && *x == ChanField::Nir))
+            .ok_or_else(|| format!("Can't parse '{s}' into ChanField"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_names() {
+        for field in ChanField::ALL {
+            assert_eq!(Ok(field), field.name().parse());
+        }
+        assert_eq!(Ok(ChanField::Nir), "NIR".parse());
+        assert!("FLAGS".parse::<ChanField>().is_err());
+    }
+}
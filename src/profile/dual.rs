@@ -1,20 +1,29 @@
 use bytemuck::Zeroable;
 
-use crate::{Column, OusterPacketHeader, Profile, RangeData};
+use crate::{
+    ChannelsHeader, Column, LidarProfile, OusterPacket, OusterPacketHeader, PacketHeader, Profile,
+    ProfileMut, RangeData,
+};
 
-use super::{PointChannelInfo, PointInfo, PointInfos, PrimaryPointInfo};
+use super::{
+    clamp_u16, clamp_u8, ChanField, PointChannelInfo, PointInfo, PointInfos, PointInfosMut,
+    PrimaryPointInfo,
+};
 
 #[derive(Clone, Copy, Zeroable)]
 pub struct DualProfile<const COLUMNS: usize, const LAYERS: usize>;
 impl<const COLUMNS: usize, const LAYERS: usize> Profile for DualProfile<COLUMNS, LAYERS> {
     type Array<T> = [T; COLUMNS];
     type Header = OusterPacketHeader;
+    type Footer = [u32; 8];
+    type ChannelsHeader = ChannelsHeader;
     type Columns = [Column<Self>; COLUMNS];
     type Channel = DualChannel;
     type Channels = [Self::Channel; LAYERS];
 
     const COLUMNS: usize = COLUMNS;
     const LAYERS: usize = LAYERS;
+    const LIDAR_PROFILE: LidarProfile = LidarProfile::DualReturn;
 
     fn initialize_channels() -> Self::Channels {
         [Self::Channel::default(); LAYERS]
@@ -22,6 +31,18 @@ impl<const COLUMNS: usize, const LAYERS: usize> Profile for DualProfile<COLUMNS,
     fn initialize_columns() -> Self::Columns {
         [Column::<Self>::default(); COLUMNS]
     }
+    fn frame_id(packet: &OusterPacket<Self>) -> u16 {
+        packet.header.frame_id()
+    }
+}
+
+impl<const COLUMNS: usize, const LAYERS: usize> ProfileMut for DualProfile<COLUMNS, LAYERS> {
+    fn set_frame_id(packet: &mut OusterPacket<Self>, frame_id: u16) {
+        packet.header.set_frame_id(frame_id)
+    }
+    fn set_column_valid(column: &mut Column<Self>, valid: bool) {
+        column.channels_header.set_valid(valid)
+    }
 }
 
 #[repr(C)]
@@ -37,6 +58,15 @@ pub struct DualChannel {
 impl PointInfos for DualChannel {
     type Signal = u16;
     type Infos = [PointChannelInfo<Self::Signal>; 2];
+    const FIELDS: &'static [ChanField] = &[
+        ChanField::Range,
+        ChanField::Range2,
+        ChanField::Reflectivity,
+        ChanField::Reflectivity2,
+        ChanField::Signal,
+        ChanField::Signal2,
+        ChanField::Nir,
+    ];
     fn get_primary_infos(&self, n_vec: u32) -> crate::PrimaryPointInfo<Self::Signal> {
         PrimaryPointInfo {
             distance: self.info_ret1.get_distance(n_vec),
@@ -64,4 +94,23 @@ impl PointInfos for DualChannel {
             nir: primary.nir,
         }
     }
+
+    fn get_nir(&self) -> u16 {
+        self.nir
+    }
+}
+
+impl PointInfosMut for DualChannel {
+    fn set_field(&mut self, field: ChanField, value: u32) -> bool {
+        match field {
+            ChanField::Range => self.info_ret1.set_distance(value),
+            ChanField::Range2 => self.info_ret2.set_distance(value),
+            ChanField::Reflectivity => self.info_ret1.set_reflectivity(clamp_u8(value)),
+            ChanField::Reflectivity2 => self.info_ret2.set_reflectivity(clamp_u8(value)),
+            ChanField::Signal => self.signal_ret_1 = clamp_u16(value),
+            ChanField::Signal2 => self.signal_ret_2 = clamp_u16(value),
+            ChanField::Nir => self.nir = clamp_u16(value),
+        }
+        true
+    }
 }
@@ -1,20 +1,29 @@
 use bytemuck::Zeroable;
 
-use crate::{Column, OusterPacketHeaderSafety, Profile};
+use crate::{
+    ChannelsHeader, Column, LidarProfile, OusterPacket, OusterPacketHeaderSafety, PacketHeader,
+    Profile, ProfileMut,
+};
 
-use super::{PointChannelInfo, PointInfo, PointInfos, PrimaryPointInfo};
+use super::{
+    clamp_u8, set_low_data_range, ChanField, PointChannelInfo, PointInfo, PointInfos,
+    PointInfosMut, PrimaryPointInfo,
+};
 
 #[derive(Clone, Copy, Zeroable)]
 pub struct DualLowProfile<const COLUMNS: usize, const LAYERS: usize>;
 impl<const COLUMNS: usize, const LAYERS: usize> Profile for DualLowProfile<COLUMNS, LAYERS> {
     type Array<T> = [T; COLUMNS];
     type Header = OusterPacketHeaderSafety;
+    type Footer = [u32; 8];
+    type ChannelsHeader = ChannelsHeader;
     type Columns = [Column<Self>; COLUMNS];
     type Channel = DualLowChannel;
     type Channels = [Self::Channel; LAYERS];
 
     const COLUMNS: usize = COLUMNS;
     const LAYERS: usize = LAYERS;
+    const LIDAR_PROFILE: LidarProfile = LidarProfile::DualLowData;
 
     fn initialize_channels() -> Self::Channels {
         [Self::Channel::default(); LAYERS]
@@ -22,6 +31,18 @@ impl<const COLUMNS: usize, const LAYERS: usize> Profile for DualLowProfile<COLUM
     fn initialize_columns() -> Self::Columns {
         [Column::<Self>::default(); COLUMNS]
     }
+    fn frame_id(packet: &OusterPacket<Self>) -> u16 {
+        packet.header.frame_id()
+    }
+}
+
+impl<const COLUMNS: usize, const LAYERS: usize> ProfileMut for DualLowProfile<COLUMNS, LAYERS> {
+    fn set_frame_id(packet: &mut OusterPacket<Self>, frame_id: u16) {
+        packet.header.set_frame_id(frame_id)
+    }
+    fn set_column_valid(column: &mut Column<Self>, valid: bool) {
+        column.channels_header.set_valid(valid)
+    }
 }
 
 #[repr(C)]
@@ -37,6 +58,13 @@ pub struct DualLowChannel {
 impl PointInfos for DualLowChannel {
     type Signal = ();
     type Infos = [PointChannelInfo<Self::Signal>; 2];
+    const FIELDS: &'static [ChanField] = &[
+        ChanField::Range,
+        ChanField::Range2,
+        ChanField::Reflectivity,
+        ChanField::Reflectivity2,
+        ChanField::Nir,
+    ];
     fn get_primary_infos(&self, n_vec: u32) -> crate::PrimaryPointInfo<Self::Signal> {
         PrimaryPointInfo {
             distance: (((self.range_ret1.overflowing_mul(2).0) / 2) as u32 * 8)
@@ -68,4 +96,22 @@ impl PointInfos for DualLowChannel {
             nir: primary.nir,
         }
     }
+
+    fn get_nir(&self) -> u16 {
+        self.nir as u16
+    }
+}
+
+impl PointInfosMut for DualLowChannel {
+    fn set_field(&mut self, field: ChanField, value: u32) -> bool {
+        match field {
+            ChanField::Range => set_low_data_range(&mut self.range_ret1, value),
+            ChanField::Range2 => set_low_data_range(&mut self.range_ret2, value),
+            ChanField::Reflectivity => self.reflect_ret_1 = clamp_u8(value),
+            ChanField::Reflectivity2 => self.reflect_ret_2 = clamp_u8(value),
+            ChanField::Nir => self.nir = clamp_u8(value),
+            _ => return false,
+        }
+        true
+    }
 }
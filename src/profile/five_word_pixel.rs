@@ -0,0 +1,175 @@
+use bytemuck::Zeroable;
+
+use crate::{
+    ChannelsHeader, Column, LidarProfile, OusterPacket, OusterPacketHeader, PacketHeader, Profile,
+    ProfileMut,
+};
+
+use super::{
+    clamp_u16, clamp_u8, ChanField, PointChannelInfo, PointInfo, PointInfos, PointInfosMut,
+    PrimaryPointInfo,
+};
+
+#[derive(Clone, Copy, Zeroable)]
+pub struct FiveWordPixelProfile<const COLUMNS: usize, const LAYERS: usize>;
+impl<const COLUMNS: usize, const LAYERS: usize> Profile for FiveWordPixelProfile<COLUMNS, LAYERS> {
+    type Array<T> = [T; COLUMNS];
+    type Header = OusterPacketHeader;
+    type Footer = [u32; 8];
+    type ChannelsHeader = ChannelsHeader;
+    type Columns = [Column<Self>; COLUMNS];
+    type Channel = FiveWordPixelChannel;
+    type Channels = [Self::Channel; LAYERS];
+
+    const COLUMNS: usize = COLUMNS;
+    const LAYERS: usize = LAYERS;
+    const LIDAR_PROFILE: LidarProfile = LidarProfile::FiveWordPixel;
+
+    fn initialize_channels() -> Self::Channels {
+        [Self::Channel::default(); LAYERS]
+    }
+    fn initialize_columns() -> Self::Columns {
+        [Column::<Self>::default(); COLUMNS]
+    }
+    fn frame_id(packet: &OusterPacket<Self>) -> u16 {
+        packet.header.frame_id()
+    }
+}
+
+impl<const COLUMNS: usize, const LAYERS: usize> ProfileMut
+    for FiveWordPixelProfile<COLUMNS, LAYERS>
+{
+    fn set_frame_id(packet: &mut OusterPacket<Self>, frame_id: u16) {
+        packet.header.set_frame_id(frame_id)
+    }
+    fn set_column_valid(column: &mut Column<Self>, valid: bool) {
+        column.channels_header.set_valid(valid)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Zeroable)]
+pub struct FiveWordPixelChannel {
+    pub range_ret1: u32,
+    pub range_ret2: u32,
+    pub signal_ret_1: u16,
+    pub signal_ret_2: u16,
+    pub reflect_ret_1: u8,
+    pub reflect_ret_2: u8,
+    pub nir: u16,
+    pub flags_ret_1: u8,
+    pub flags_ret_2: u8,
+    _reserved: u16,
+}
+
+impl FiveWordPixelChannel {
+    /// Lower 19 bits of the range word
+    const RANGE_MASK: u32 = (1 << 19) - 1;
+
+    fn distance(range: u32, n_vec: u32) -> u16 {
+        (range & Self::RANGE_MASK)
+            .saturating_sub(n_vec)
+            .min(u16::MAX as _) as u16
+    }
+
+    fn set_distance(range: &mut u32, distance: u32) {
+        *range = (*range & !Self::RANGE_MASK) | distance.min(Self::RANGE_MASK);
+    }
+}
+
+impl PointInfos for FiveWordPixelChannel {
+    type Signal = u16;
+    type Infos = [PointChannelInfo<Self::Signal>; 2];
+    const FIELDS: &'static [ChanField] = &[
+        ChanField::Range,
+        ChanField::Range2,
+        ChanField::Reflectivity,
+        ChanField::Reflectivity2,
+        ChanField::Signal,
+        ChanField::Signal2,
+        ChanField::Nir,
+    ];
+
+    fn get_primary_infos(&self, n_vec: u32) -> crate::PrimaryPointInfo<Self::Signal> {
+        PrimaryPointInfo {
+            distance: Self::distance(self.range_ret1, n_vec),
+            reflectifity: self.reflect_ret_1,
+            nir: (self.nir >> 8) as u8,
+            signal: self.signal_ret_1,
+        }
+    }
+
+    fn get_infos(&self, n_vec: u32) -> PointInfo<Self::Infos> {
+        let primary = self.get_primary_infos(n_vec);
+        PointInfo {
+            channel_info: [
+                PointChannelInfo {
+                    distance: primary.distance,
+                    reflectifity: primary.reflectifity,
+                    signal: primary.signal,
+                },
+                PointChannelInfo {
+                    distance: Self::distance(self.range_ret2, n_vec),
+                    reflectifity: self.reflect_ret_2,
+                    signal: self.signal_ret_2,
+                },
+            ],
+            nir: primary.nir,
+        }
+    }
+
+    fn get_nir(&self) -> u16 {
+        self.nir
+    }
+}
+
+impl PointInfosMut for FiveWordPixelChannel {
+    fn set_field(&mut self, field: ChanField, value: u32) -> bool {
+        match field {
+            ChanField::Range => Self::set_distance(&mut self.range_ret1, value),
+            ChanField::Range2 => Self::set_distance(&mut self.range_ret2, value),
+            ChanField::Reflectivity => self.reflect_ret_1 = clamp_u8(value),
+            ChanField::Reflectivity2 => self.reflect_ret_2 = clamp_u8(value),
+            ChanField::Signal => self.signal_ret_1 = clamp_u16(value),
+            ChanField::Signal2 => self.signal_ret_2 = clamp_u16(value),
+            ChanField::Nir => self.nir = clamp_u16(value),
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ChanField, FiveWordPixel128OusterPacket, PointInfos};
+
+    #[test]
+    fn parse_from_bytes() {
+        let mut buffer = vec![0u8; 41216];
+        buffer[2..4].copy_from_slice(&3u16.to_le_bytes());
+        let pixel = 32 + 12;
+        buffer[pixel..pixel + 4].copy_from_slice(&1000u32.to_le_bytes());
+        buffer[pixel + 4..pixel + 8].copy_from_slice(&2000u32.to_le_bytes());
+        buffer[pixel + 8..pixel + 10].copy_from_slice(&10u16.to_le_bytes());
+        buffer[pixel + 10..pixel + 12].copy_from_slice(&20u16.to_le_bytes());
+        buffer[pixel + 12] = 1;
+        buffer[pixel + 13] = 2;
+        buffer[pixel + 14..pixel + 16].copy_from_slice(&0x300u16.to_le_bytes());
+
+        let packet = FiveWordPixel128OusterPacket::from_maybe_unaligned(&buffer).unwrap();
+        assert_eq!(3, packet.frame_id());
+        let channel = &packet.columns[0].channels[0];
+        assert_eq!(
+            [1000, 2000, 1, 2, 10, 20, 0x300],
+            [
+                ChanField::Range,
+                ChanField::Range2,
+                ChanField::Reflectivity,
+                ChanField::Reflectivity2,
+                ChanField::Signal,
+                ChanField::Signal2,
+                ChanField::Nir
+            ]
+            .map(|f| channel.get_field(f, 0).unwrap())
+        );
+    }
+}
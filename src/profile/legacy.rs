@@ -0,0 +1,170 @@
+use bytemuck::Zeroable;
+
+use crate::{
+    packet::RANGE_MASK, Column, LegacyChannelsHeader, LidarProfile, OusterPacket, Profile,
+    ProfileMut,
+};
+
+use super::{
+    clamp_u16, clamp_u8, ChanField, PointChannelInfo, PointInfo, PointInfos, PointInfosMut,
+    PrimaryPointInfo,
+};
+
+/// Packet format of firmware < 2.0. There is no packet header and footer, the frame_id is part of every column
+#[derive(Clone, Copy, Zeroable)]
+pub struct LegacyProfile<const COLUMNS: usize, const LAYERS: usize>;
+impl<const COLUMNS: usize, const LAYERS: usize> Profile for LegacyProfile<COLUMNS, LAYERS> {
+    type Array<T> = [T; COLUMNS];
+    type Header = ();
+    type Footer = ();
+    type ChannelsHeader = LegacyChannelsHeader;
+    type Columns = [Column<Self>; COLUMNS];
+    type Channel = LegacyChannel;
+    type Channels = LegacyChannels<LAYERS>;
+
+    const COLUMNS: usize = COLUMNS;
+    const LAYERS: usize = LAYERS;
+    const LIDAR_PROFILE: LidarProfile = LidarProfile::Legacy;
+
+    fn initialize_channels() -> Self::Channels {
+        LegacyChannels::default()
+    }
+    fn initialize_columns() -> Self::Columns {
+        [Column::<Self>::default(); COLUMNS]
+    }
+    fn frame_id(packet: &OusterPacket<Self>) -> u16 {
+        packet.columns[0].channels_header.frame_id
+    }
+}
+
+impl<const COLUMNS: usize, const LAYERS: usize> ProfileMut for LegacyProfile<COLUMNS, LAYERS> {
+    fn set_frame_id(packet: &mut OusterPacket<Self>, frame_id: u16) {
+        for column in &mut packet.columns {
+            column.channels_header.frame_id = frame_id;
+        }
+    }
+    fn set_column_valid(column: &mut Column<Self>, valid: bool) {
+        column.channels.status = if valid { u32::MAX } else { 0 };
+    }
+}
+
+/// Channels of a column followed by the column status
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Zeroable)]
+pub struct LegacyChannels<const LAYERS: usize> {
+    pub channels: [LegacyChannel; LAYERS],
+    /// 0xffffffff if the column is valid
+    pub status: u32,
+}
+
+impl<const LAYERS: usize> Default for LegacyChannels<LAYERS> {
+    fn default() -> Self {
+        Self {
+            channels: [LegacyChannel::default(); LAYERS],
+            status: 0,
+        }
+    }
+}
+
+impl<const LAYERS: usize> AsRef<[LegacyChannel]> for LegacyChannels<LAYERS> {
+    fn as_ref(&self) -> &[LegacyChannel] {
+        &self.channels
+    }
+}
+
+impl<const LAYERS: usize> AsMut<[LegacyChannel]> for LegacyChannels<LAYERS> {
+    fn as_mut(&mut self) -> &mut [LegacyChannel] {
+        &mut self.channels
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, Zeroable)]
+pub struct LegacyChannel {
+    pub range_and_reserved: u32,
+    pub reflectifity: u16,
+    pub signal: u16,
+    pub nir: u16,
+    _reserved: u16,
+}
+
+impl PointInfos for LegacyChannel {
+    type Signal = u16;
+    type Infos = [PointChannelInfo<Self::Signal>; 1];
+    const FIELDS: &'static [ChanField] = &[
+        ChanField::Range,
+        ChanField::Reflectivity,
+        ChanField::Signal,
+        ChanField::Nir,
+    ];
+
+    fn get_primary_infos(&self, n_vec: u32) -> crate::PrimaryPointInfo<Self::Signal> {
+        PrimaryPointInfo {
+            distance: ((self.range_and_reserved & RANGE_MASK).saturating_sub(n_vec))
+                .min(u16::MAX as _) as u16,
+            reflectifity: self.reflectifity.min(u8::MAX as _) as u8,
+            nir: (self.nir >> 8) as u8,
+            signal: self.signal,
+        }
+    }
+
+    fn get_infos(&self, n_vec: u32) -> PointInfo<Self::Infos> {
+        let primary = self.get_primary_infos(n_vec);
+        PointInfo {
+            nir: primary.nir,
+            channel_info: [PointChannelInfo {
+                distance: primary.distance,
+                reflectifity: primary.reflectifity,
+                signal: primary.signal,
+            }],
+        }
+    }
+
+    fn get_nir(&self) -> u16 {
+        self.nir
+    }
+}
+
+impl PointInfosMut for LegacyChannel {
+    fn set_field(&mut self, field: ChanField, value: u32) -> bool {
+        match field {
+            ChanField::Range => {
+                self.range_and_reserved =
+                    (self.range_and_reserved & !RANGE_MASK) | value.min(RANGE_MASK)
+            }
+            ChanField::Reflectivity => self.reflectifity = clamp_u8(value) as u16,
+            ChanField::Signal => self.signal = clamp_u16(value),
+            ChanField::Nir => self.nir = clamp_u16(value),
+            _ => return false,
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Legacy64OusterPacket, PointInfos};
+
+    #[test]
+    fn parse_from_bytes() {
+        let mut buffer = vec![0u8; 12608];
+        buffer[8..10].copy_from_slice(&32u16.to_le_bytes());
+        buffer[10..12].copy_from_slice(&7u16.to_le_bytes());
+        // Second channel of the first column
+        buffer[28..32].copy_from_slice(&1234u32.to_le_bytes());
+        buffer[32..34].copy_from_slice(&100u16.to_le_bytes());
+        buffer[34..36].copy_from_slice(&300u16.to_le_bytes());
+        buffer[36..38].copy_from_slice(&0x500u16.to_le_bytes());
+        buffer[16 + 12 * 64..20 + 12 * 64].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let packet = Legacy64OusterPacket::from_maybe_unaligned(&buffer).unwrap();
+        assert_eq!(7, packet.frame_id());
+        assert_eq!(32, packet.measurement_id());
+        assert_eq!(u32::MAX, packet.columns[0].channels.status);
+        let info = packet.columns[0].channels.channels[1].get_primary_infos(4);
+        assert_eq!(
+            (1230, 100, 300, 5),
+            (info.distance, info.reflectifity, info.signal, info.nir)
+        );
+    }
+}
@@ -1,20 +1,29 @@
 use bytemuck::Zeroable;
 
-use crate::{Column, OusterPacketHeader, Profile};
+use crate::{
+    ChannelsHeader, Column, LidarProfile, OusterPacket, OusterPacketHeader, PacketHeader, Profile,
+    ProfileMut,
+};
 
-use super::{PointChannelInfo, PointInfo, PointInfos, PrimaryPointInfo};
+use super::{
+    clamp_u8, set_low_data_range, ChanField, PointChannelInfo, PointInfo, PointInfos,
+    PointInfosMut, PrimaryPointInfo,
+};
 
 #[derive(Clone, Copy, Zeroable)]
 pub struct LowDataProfile<const COLUMNS: usize, const LAYERS: usize>;
 impl<const COLUMNS: usize, const LAYERS: usize> Profile for LowDataProfile<COLUMNS, LAYERS> {
     type Array<T> = [T; COLUMNS];
     type Header = OusterPacketHeader;
+    type Footer = [u32; 8];
+    type ChannelsHeader = ChannelsHeader;
     type Columns = [Column<Self>; COLUMNS];
     type Channel = LowDataChannel;
     type Channels = [Self::Channel; LAYERS];
 
     const COLUMNS: usize = COLUMNS;
     const LAYERS: usize = LAYERS;
+    const LIDAR_PROFILE: LidarProfile = LidarProfile::LowData;
 
     fn initialize_channels() -> Self::Channels {
         [Self::Channel::default(); LAYERS]
@@ -22,6 +31,18 @@ impl<const COLUMNS: usize, const LAYERS: usize> Profile for LowDataProfile<COLUM
     fn initialize_columns() -> Self::Columns {
         [Column::<Self>::default(); COLUMNS]
     }
+    fn frame_id(packet: &OusterPacket<Self>) -> u16 {
+        packet.header.frame_id()
+    }
+}
+
+impl<const COLUMNS: usize, const LAYERS: usize> ProfileMut for LowDataProfile<COLUMNS, LAYERS> {
+    fn set_frame_id(packet: &mut OusterPacket<Self>, frame_id: u16) {
+        packet.header.set_frame_id(frame_id)
+    }
+    fn set_column_valid(column: &mut Column<Self>, valid: bool) {
+        column.channels_header.set_valid(valid)
+    }
 }
 
 #[repr(C)]
@@ -35,6 +56,8 @@ pub struct LowDataChannel {
 impl PointInfos for LowDataChannel {
     type Signal = ();
     type Infos = [PointChannelInfo<Self::Signal>; 1];
+    const FIELDS: &'static [ChanField] =
+        &[ChanField::Range, ChanField::Reflectivity, ChanField::Nir];
     fn get_primary_infos(&self, n_vec: u32) -> crate::PrimaryPointInfo<Self::Signal> {
         PrimaryPointInfo {
             distance: (((self.distance_and_reserve.overflowing_mul(2).0) / 2) as u32 * 8)
@@ -57,4 +80,20 @@ impl PointInfos for LowDataChannel {
             }],
         }
     }
+
+    fn get_nir(&self) -> u16 {
+        self.nir as u16
+    }
+}
+
+impl PointInfosMut for LowDataChannel {
+    fn set_field(&mut self, field: ChanField, value: u32) -> bool {
+        match field {
+            ChanField::Range => set_low_data_range(&mut self.distance_and_reserve, value),
+            ChanField::Reflectivity => self.reflectifity = clamp_u8(value),
+            ChanField::Nir => self.nir = clamp_u8(value),
+            _ => return false,
+        }
+        true
+    }
 }
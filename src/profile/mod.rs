@@ -2,37 +2,107 @@ use std::{any::Any, fmt::Debug};
 
 use bytemuck::Zeroable;
 
-use crate::Column;
+use crate::{Column, ColumnHeaderMut, OusterPacket};
 
+mod chan_field;
 mod dual;
 mod dual_low;
+mod five_word_pixel;
+mod legacy;
 mod low;
 mod single;
 
+pub use chan_field::*;
 pub use dual::*;
 pub use dual_low::*;
+pub use five_word_pixel::*;
+pub use legacy::*;
 pub use low::*;
 pub use single::*;
 
 pub trait Profile: Clone + Zeroable + Send + Sync + 'static {
     type Array<T>: AsRef<[T]>;
-    type Header: Zeroable + Default + crate::PacketHeader + Clone;
+    type Header: Zeroable + Default + Clone;
+    type Footer: Zeroable + Default + Clone + Debug;
+    type ChannelsHeader: Zeroable + Default + Copy + Debug + crate::ColumnHeader;
     type Columns: AsRef<[Column<Self>]> + Clone + Zeroable + Send + Sync + 'static;
     type Channel: Default + Debug + PointInfos + Send + Sync + 'static;
     type Channels: AsRef<[Self::Channel]> + Zeroable + Debug + Send + Sync + 'static;
 
     const COLUMNS: usize;
     const LAYERS: usize;
+    const LIDAR_PROFILE: crate::LidarProfile;
 
     fn initialize_channels() -> Self::Channels;
     fn initialize_columns() -> Self::Columns;
+    fn frame_id(packet: &OusterPacket<Self>) -> u16;
+}
+
+/// Profiles, whose packets can be written, for example by the [crate::Simulator]
+/// Separate from [Profile], so profiles implemented outside of this crate don't need to support it
+pub trait ProfileMut:
+    Profile<
+    Columns: AsMut<[Column<Self>]>,
+    Channel: PointInfosMut,
+    Channels: AsMut<[Self::Channel]>,
+    ChannelsHeader: ColumnHeaderMut,
+>
+{
+    fn set_frame_id(packet: &mut OusterPacket<Self>, frame_id: u16);
+    fn set_column_valid(column: &mut Column<Self>, valid: bool);
 }
 
 pub trait PointInfos {
     type Signal: Any;
-    type Infos: AsRef<[PointChannelInfo<Self::Signal>]>;
+    type Infos: AsRef<[PointChannelInfo<Self::Signal>]>
+        + IntoIterator<Item = PointChannelInfo<Self::Signal>>;
+    /// Fields which are provided by get_field, range, reflectivity and nir are part of every profile
+    const FIELDS: &'static [ChanField] =
+        &[ChanField::Range, ChanField::Reflectivity, ChanField::Nir];
     fn get_primary_infos(&self, n_vec: u32) -> PrimaryPointInfo<Self::Signal>;
     fn get_infos(&self, n_vec: u32) -> PointInfo<Self::Infos>;
+
+    /// None if the field isn't part of the profile
+    fn get_field(&self, field: ChanField, n_vec: u32) -> Option<u16> {
+        if !Self::FIELDS.contains(&field) {
+            return None;
+        }
+        let Some(return_index) = field.return_index() else {
+            return Some(self.get_nir());
+        };
+        let infos = self.get_infos(n_vec);
+        let channel = infos.channel_info.as_ref().get(return_index)?;
+        match field {
+            ChanField::Range | ChanField::Range2 => Some(channel.distance),
+            ChanField::Reflectivity | ChanField::Reflectivity2 => Some(channel.reflectifity as _),
+            _ => channel.get_signal(),
+        }
+    }
+
+    /// Near infrared with the resolution of the packet, the infos only contain its upper 8 bits
+    fn get_nir(&self) -> u16 {
+        (self.get_primary_infos(0).nir as u16) << 8
+    }
+}
+
+pub trait PointInfosMut: PointInfos {
+    /// Inverse of get_field with n_vec 0: The range is the raw range in mm, nir has the resolution of the packet
+    /// Values exceeding the resolution of the profile are clamped, false if the field isn't part of the profile
+    fn set_field(&mut self, field: ChanField, value: u32) -> bool;
+}
+
+/// Low data profiles store the range in units of 8mm in the lower 15 bits
+fn set_low_data_range(raw: &mut u16, range: u32) {
+    let scaled = (range.saturating_add(4) / 8).min(0x7fff) as u16;
+    *raw = (*raw & 0x8000) | scaled;
+}
+
+fn clamp_u8(value: u32) -> u8 {
+    value.min(u8::MAX as _) as u8
+}
+
+fn clamp_u16(value: u32) -> u16 {
+    value.min(u16::MAX as _) as u16
 }
 
 pub struct PointInfo<T> {
@@ -47,9 +117,14 @@ pub struct PointChannelInfo<TSignal> {
 }
 
 impl<TSignal: Any> PointChannelInfo<TSignal> {
+    /// None, if the profile doesn't contain a signal
+    pub fn get_signal(&self) -> Option<u16> {
+        <dyn std::any::Any>::downcast_ref::<u16>(&self.signal).copied()
+    }
+
     pub fn unwrap_signal(&self) -> u16 {
-        if let Some(x) = <dyn std::any::Any>::downcast_ref::<u16>(&self.signal) {
-            *x
+        if let Some(x) = self.get_signal() {
+            x
         } else {
             panic!(
                 "Signal was unwrapped, but there was no signal: {}",
@@ -67,9 +142,14 @@ pub struct PrimaryPointInfo<TSignal: Any> {
 }
 
 impl<TSignal: Any> PrimaryPointInfo<TSignal> {
+    /// None, if the profile doesn't contain a signal
+    pub fn get_signal(&self) -> Option<u16> {
+        <dyn std::any::Any>::downcast_ref::<u16>(&self.signal).copied()
+    }
+
     pub fn unwrap_signal(&self) -> u16 {
-        if let Some(x) = <dyn std::any::Any>::downcast_ref::<u16>(&self.signal) {
-            *x
+        if let Some(x) = self.get_signal() {
+            x
         } else {
             panic!(
                 "Signal was unwrapped, but there was no signal: {}",
@@ -78,3 +158,106 @@ impl<TSignal: Any> PrimaryPointInfo<TSignal> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{packet::ColumnHeader, OusterPacket};
+
+    use super::*;
+
+    fn roundtrip<TProfile: ProfileMut>() {
+        let mut packet = OusterPacket::<TProfile>::default();
+        packet.set_frame_id(7);
+        let column = &mut packet.columns.as_mut()[1];
+        column.channels_header.set_measurement_id(17);
+        column
+            .channels_header
+            .set_timestamp(Duration::from_nanos(123));
+        column.set_valid(true);
+        let channel = &mut column.channels.as_mut()[2];
+        let values = ChanField::ALL.map(|field| match field {
+            ChanField::Range | ChanField::Range2 => 4000 + field.return_index().unwrap() as u32 * 8,
+            ChanField::Signal | ChanField::Signal2 => 1000,
+            _ => 200,
+        });
+        for (field, value) in ChanField::ALL.into_iter().zip(values) {
+            assert_eq!(
+                TProfile::Channel::FIELDS.contains(&field),
+                channel.set_field(field, value)
+            );
+        }
+
+        let packet = OusterPacket::<TProfile>::from_maybe_unaligned(packet.as_slice()).unwrap();
+        assert_eq!(7, packet.frame_id());
+        let column = &packet.columns.as_ref()[1];
+        assert_eq!(17, column.channels_header.measurement_id());
+        assert_eq!(
+            Duration::from_nanos(123),
+            column.channels_header.timestamp()
+        );
+        let channel = &column.channels.as_ref()[2];
+        for (field, value) in ChanField::ALL.into_iter().zip(values) {
+            if let Some(actual) = channel.get_field(field, 0) {
+                assert_eq!(value, actual as u32, "{field}");
+            }
+        }
+        // Clamped to the 8 bit resolution
+        let mut channel = TProfile::Channel::default();
+        channel.set_field(ChanField::Reflectivity, 1000);
+        assert_eq!(Some(255), channel.get_field(ChanField::Reflectivity, 0));
+    }
+
+    #[test]
+    fn set_and_get_fields() {
+        roundtrip::<SingleProfile<16, 16>>();
+        roundtrip::<DualProfile<16, 16>>();
+        roundtrip::<LowDataProfile<16, 16>>();
+        roundtrip::<DualLowProfile<16, 16>>();
+        roundtrip::<FiveWordPixelProfile<16, 16>>();
+        roundtrip::<LegacyProfile<16, 16>>();
+    }
+
+    #[test]
+    fn column_status() {
+        let mut column = Column::<LegacyProfile<16, 16>>::default();
+        column.set_valid(true);
+        assert_eq!(u32::MAX, column.channels.status);
+
+        let mut column = Column::<DualProfile<16, 16>>::default();
+        column.channels_header.status_and_reserve = 0x100;
+        column.set_valid(true);
+        assert!(column.channels_header.is_valid());
+        column.set_valid(false);
+        assert_eq!(0x100, column.channels_header.status_and_reserve);
+    }
+
+    #[test]
+    fn range_keeps_reflectivity() {
+        let mut channel = DualChannel::default();
+        channel.set_field(ChanField::Reflectivity, 9);
+        channel.set_field(ChanField::Range, 1234);
+        channel.set_field(ChanField::Range, 4321);
+        assert_eq!(Some(9), channel.get_field(ChanField::Reflectivity, 0));
+        assert_eq!(Some(4321), channel.get_field(ChanField::Range, 0));
+    }
+
+    #[test]
+    fn full_resolution_of_the_packet() {
+        let mut channel = DualChannel::default();
+        channel.set_field(ChanField::Range, 100_000);
+        channel.set_field(ChanField::Nir, 0x1234);
+        assert_eq!(100_000, channel.info_ret1.raw & crate::packet::RANGE_MASK);
+        assert_eq!(Some(0x1234), channel.get_field(ChanField::Nir, 0));
+        channel.set_field(ChanField::Range, u32::MAX);
+        assert_eq!(
+            crate::packet::RANGE_MASK,
+            channel.info_ret1.raw & crate::packet::RANGE_MASK
+        );
+
+        let mut channel = LowDataChannel::default();
+        channel.set_field(ChanField::Range, 200_000);
+        assert_eq!(25_000, channel.distance_and_reserve & 0x7fff);
+    }
+}
@@ -1,20 +1,29 @@
 use bytemuck::Zeroable;
 
-use crate::{Column, OusterPacketHeader, Profile};
+use crate::{
+    packet::RANGE_MASK, ChannelsHeader, Column, LidarProfile, OusterPacket, OusterPacketHeader,
+    PacketHeader, Profile, ProfileMut,
+};
 
-use super::{PointChannelInfo, PointInfo, PointInfos, PrimaryPointInfo};
+use super::{
+    clamp_u16, clamp_u8, ChanField, PointChannelInfo, PointInfo, PointInfos, PointInfosMut,
+    PrimaryPointInfo,
+};
 
 #[derive(Clone, Copy, Zeroable)]
 pub struct SingleProfile<const COLUMNS: usize, const LAYERS: usize>;
 impl<const COLUMNS: usize, const LAYERS: usize> Profile for SingleProfile<COLUMNS, LAYERS> {
     type Array<T> = [T; COLUMNS];
     type Header = OusterPacketHeader;
+    type Footer = [u32; 8];
+    type ChannelsHeader = ChannelsHeader;
     type Columns = [Column<Self>; COLUMNS];
     type Channel = SingleChannel;
     type Channels = [Self::Channel; LAYERS];
 
     const COLUMNS: usize = COLUMNS;
     const LAYERS: usize = LAYERS;
+    const LIDAR_PROFILE: LidarProfile = LidarProfile::SingleReturn;
 
     fn initialize_channels() -> Self::Channels {
         [Self::Channel::default(); LAYERS]
@@ -22,6 +31,18 @@ impl<const COLUMNS: usize, const LAYERS: usize> Profile for SingleProfile<COLUMN
     fn initialize_columns() -> Self::Columns {
         [Column::<Self>::default(); COLUMNS]
     }
+    fn frame_id(packet: &OusterPacket<Self>) -> u16 {
+        packet.header.frame_id()
+    }
+}
+
+impl<const COLUMNS: usize, const LAYERS: usize> ProfileMut for SingleProfile<COLUMNS, LAYERS> {
+    fn set_frame_id(packet: &mut OusterPacket<Self>, frame_id: u16) {
+        packet.header.set_frame_id(frame_id)
+    }
+    fn set_column_valid(column: &mut Column<Self>, valid: bool) {
+        column.channels_header.set_valid(valid)
+    }
 }
 
 #[repr(C)]
@@ -38,10 +59,16 @@ pub struct SingleChannel {
 impl PointInfos for SingleChannel {
     type Signal = u16;
     type Infos = [PointChannelInfo<Self::Signal>; 1];
+    const FIELDS: &'static [ChanField] = &[
+        ChanField::Range,
+        ChanField::Reflectivity,
+        ChanField::Signal,
+        ChanField::Nir,
+    ];
 
     fn get_primary_infos(&self, n_vec: u32) -> crate::PrimaryPointInfo<Self::Signal> {
         PrimaryPointInfo {
-            distance: ((self.range_and_reserved & ((1 << 20) - 1)).saturating_sub(n_vec))
+            distance: ((self.range_and_reserved & RANGE_MASK).saturating_sub(n_vec))
                 .min(u16::MAX as _) as u16,
             reflectifity: self.reflectifity,
             nir: (self.nir >> 8) as u8,
@@ -60,4 +87,24 @@ impl PointInfos for SingleChannel {
             }],
         }
     }
+
+    fn get_nir(&self) -> u16 {
+        self.nir
+    }
+}
+
+impl PointInfosMut for SingleChannel {
+    fn set_field(&mut self, field: ChanField, value: u32) -> bool {
+        match field {
+            ChanField::Range => {
+                self.range_and_reserved =
+                    (self.range_and_reserved & !RANGE_MASK) | value.min(RANGE_MASK)
+            }
+            ChanField::Reflectivity => self.reflectifity = clamp_u8(value),
+            ChanField::Signal => self.signal = clamp_u16(value),
+            ChanField::Nir => self.nir = clamp_u16(value),
+            _ => return false,
+        }
+        true
+    }
 }
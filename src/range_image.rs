@@ -0,0 +1,450 @@
+use std::any::{Any, TypeId};
+
+use crate::{
+    CartesianIterator, ChanField, CompleteData, PixelPositionIterator, PointInfos, Profile,
+    ValidOperationConfig,
+};
+
+pub type RangeImage = FieldImage<u16>;
+
+/// Row major image with one value per pixel
+/// Images created from CompleteData have height = Profile::LAYERS and width = column_window.len()
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldImage<T> {
+    width: usize,
+    height: usize,
+    data: Vec<T>,
+}
+
+impl<T> FieldImage<T> {
+    /// Panics if data.len() != width * height
+    pub fn new(width: usize, height: usize, data: Vec<T>) -> Self {
+        assert_eq!(
+            width * height,
+            data.len(),
+            "Data doesn't match image size {width}x{height}"
+        );
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.data
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+        if col < self.width {
+            self.data.get(row * self.width + col)
+        } else {
+            None
+        }
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.data[row * self.width..(row + 1) * self.width]
+    }
+
+    /// Keeps only the columns start..start + len
+    pub fn crop_cols(&self, start: usize, len: usize) -> Self
+    where
+        T: Clone,
+    {
+        assert!(
+            start + len <= self.width,
+            "Crop exceeds width {}",
+            self.width
+        );
+        Self {
+            width: len,
+            height: self.height,
+            data: self
+                .data
+                .chunks_exact(self.width.max(1))
+                .flat_map(|row| row[start..start + len].iter().cloned())
+                .collect(),
+        }
+    }
+
+    pub fn map<U>(&self, map: impl FnMut(&T) -> U) -> FieldImage<U> {
+        FieldImage {
+            width: self.width,
+            height: self.height,
+            data: self.data.iter().map(map).collect(),
+        }
+    }
+}
+
+impl<T: Copy> FieldImage<T> {
+    /// Shifts every row by pixel_shift_by_row, so neighbouring pixels are geometric neighbours
+    pub fn destagger(&self, pixel_shift_by_row: &[i8]) -> Self {
+        self.shift_rows(pixel_shift_by_row, 1)
+    }
+
+    /// Inverse of destagger: Restores the order in which pixels are measured
+    pub fn stagger(&self, pixel_shift_by_row: &[i8]) -> Self {
+        self.shift_rows(pixel_shift_by_row, -1)
+    }
+
+    fn shift_rows(&self, pixel_shift_by_row: &[i8], direction: isize) -> Self {
+        assert_eq!(self.height, pixel_shift_by_row.len());
+        if self.width == 0 {
+            return self.clone();
+        }
+        let mut data = self.data.clone();
+        let width = self.width as isize;
+        for (row, &shift) in data.chunks_exact_mut(self.width).zip(pixel_shift_by_row) {
+            row.rotate_right((shift as isize * direction).rem_euclid(width) as usize);
+        }
+        Self {
+            width: self.width,
+            height: self.height,
+            data,
+        }
+    }
+}
+
+/// Image of a ChanField with the smallest type which can hold all values
+/// U32 is used for fields read from other tools, e.g. the 32 bit range of the Ouster SDK
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldBuffer {
+    U8(FieldImage<u8>),
+    U16(FieldImage<u16>),
+    U32(FieldImage<u32>),
+}
+
+impl FieldBuffer {
+    pub fn width(&self) -> usize {
+        match self {
+            FieldBuffer::U8(x) => x.width(),
+            FieldBuffer::U16(x) => x.width(),
+            FieldBuffer::U32(x) => x.width(),
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        match self {
+            FieldBuffer::U8(x) => x.height(),
+            FieldBuffer::U16(x) => x.height(),
+            FieldBuffer::U32(x) => x.height(),
+        }
+    }
+
+    /// Converts U8 losslessly, U32 values are saturated
+    pub fn to_u16(&self) -> FieldImage<u16> {
+        match self {
+            FieldBuffer::U8(x) => x.map(|&x| x as u16),
+            FieldBuffer::U16(x) => x.clone(),
+            FieldBuffer::U32(x) => x.map(|&x| x.min(u16::MAX as u32) as u16),
+        }
+    }
+
+    /// Converts all variants losslessly
+    pub fn to_u32(&self) -> FieldImage<u32> {
+        match self {
+            FieldBuffer::U8(x) => x.map(|&x| x as u32),
+            FieldBuffer::U16(x) => x.map(|&x| x as u32),
+            FieldBuffer::U32(x) => x.clone(),
+        }
+    }
+}
+
+impl<TProfile: Profile> CompleteData<TProfile> {
+    /// Destaggered image of any value which can be extracted from a channel
+    pub fn field_image<T: Copy + Default>(
+        &self,
+        config: &ValidOperationConfig<TProfile>,
+        map: impl FnMut(&TProfile::Channel, u32) -> T,
+    ) -> FieldImage<T> {
+        let format = &config.lidar_data_format;
+        let width = format.column_window.len();
+        let mut data = vec![T::default(); width * TProfile::LAYERS];
+        for (value, (col, row)) in self
+            .iter_flat(config, map)
+            .zip(PixelPositionIterator::from_config(format))
+        {
+            data[row * width + col] = value;
+        }
+        FieldImage::new(width, TProfile::LAYERS, data)
+    }
+
+    /// Distance of the primary return in mm
+    pub fn range_image(&self, config: &ValidOperationConfig<TProfile>) -> RangeImage {
+        self.field_image(config, |channel, n_vec| {
+            channel.get_primary_infos(n_vec).distance
+        })
+    }
+
+    pub fn reflectivity_image(&self, config: &ValidOperationConfig<TProfile>) -> FieldImage<u8> {
+        self.field_image(config, |channel, n_vec| {
+            channel.get_primary_infos(n_vec).reflectifity
+        })
+    }
+
+    pub fn nir_image(&self, config: &ValidOperationConfig<TProfile>) -> FieldImage<u8> {
+        self.field_image(config, |channel, n_vec| {
+            channel.get_primary_infos(n_vec).nir
+        })
+    }
+
+    /// None, if the profile doesn't contain a signal
+    pub fn signal_image(&self, config: &ValidOperationConfig<TProfile>) -> Option<FieldImage<u16>> {
+        if TypeId::of::<<TProfile::Channel as PointInfos>::Signal>() != TypeId::of::<u16>() {
+            return None;
+        }
+        Some(self.field_image(config, |channel, n_vec| {
+            let signal = channel.get_primary_infos(n_vec).signal;
+            *<dyn Any>::downcast_ref::<u16>(&signal).expect("Checked by TypeId")
+        }))
+    }
+
+    /// None, if the profile doesn't contain the field
+    pub fn field(
+        &self,
+        config: &ValidOperationConfig<TProfile>,
+        field: ChanField,
+    ) -> Option<FieldBuffer> {
+        if !<TProfile::Channel as PointInfos>::FIELDS.contains(&field) {
+            return None;
+        }
+        let get = move |channel: &TProfile::Channel, n_vec| {
+            channel.get_field(field, n_vec).unwrap_or_default()
+        };
+        Some(match field {
+            ChanField::Reflectivity | ChanField::Reflectivity2 => FieldBuffer::U8(
+                self.field_image(config, move |channel, n_vec| get(channel, n_vec) as u8),
+            ),
+            _ => FieldBuffer::U16(self.field_image(config, get)),
+        })
+    }
+
+    /// Destaggered H x W cloud in mm, where H = Profile::LAYERS
+    /// Pixels without a measurement (zero range or missing packets) are NaN
+    /// Only the complete columns according to ValidLidarDataFormat::calc_complete_cols_aligned are contained
+    /// The CartesianIterator has to be created from the same config
+    pub fn organized_cloud<TSlice>(
+        &self,
+        config: &ValidOperationConfig<TProfile>,
+        cartesian: CartesianIterator<TProfile, TSlice>,
+        alignment: usize,
+    ) -> FieldImage<(f32, f32, f32)>
+    where
+        TSlice: AsRef<[(f32, f32)]>,
+    {
+        let format = &config.lidar_data_format;
+        let width = format.column_window.len();
+        let mut data = vec![(f32::NAN, f32::NAN, f32::NAN); width * TProfile::LAYERS];
+        for ((info, polar_point), (col, row)) in self
+            .iter_infos_primary(config)
+            .zip(cartesian)
+            .zip(PixelPositionIterator::from_config(format))
+        {
+            if info.distance != 0 {
+                data[row * width + col] = polar_point.calc_xyz(info.distance as f32);
+            }
+        }
+        let (skip_first, take) = format.calc_complete_cols_aligned(alignment);
+        FieldImage::new(width, TProfile::LAYERS, data).crop_cols(skip_first, take)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_utils::{complete_frame, test_config},
+        Aggregator, Dual64OusterPacket, DualProfile, LidarProfile, LowDataProfile, OusterPacket,
+        Single128OusterPacket, SingleProfile,
+    };
+
+    use super::*;
+
+    #[test]
+    fn destagger_shifts_rows() {
+        let image = FieldImage::new(3, 2, vec![1, 2, 3, 4, 5, 6]);
+        let destaggered = image.destagger(&[1, -1]);
+        assert_eq!(&[3, 1, 2, 5, 6, 4], destaggered.as_slice());
+    }
+
+    #[test]
+    fn stagger_is_inverse() {
+        let shifts = [12, 4, -4, -12];
+        let image = FieldImage::new(16, 4, (0..64).collect());
+        assert_eq!(image, image.destagger(&shifts).stagger(&shifts));
+        assert_ne!(image, image.destagger(&shifts));
+    }
+
+    #[test]
+    fn destagger_matches_pixel_position_iterator() {
+        let shifts = [1, -1, 3];
+        let staggered = FieldImage::new(4, 3, (0..12).collect::<Vec<_>>());
+        let mut expected = vec![0; 12];
+        for ((col, row), (input_col, input_row)) in PixelPositionIterator::new(&shifts, 4)
+            .zip((0..4).flat_map(|col| (0..3).map(move |row| (col, row))))
+        {
+            expected[row * 4 + col] = *staggered.get(input_row, input_col).unwrap();
+        }
+        assert_eq!(expected, staggered.destagger(&shifts).into_vec());
+    }
+
+    #[test]
+    fn range_image_is_destaggered() {
+        let shifts = [[3, -3]; 64].concat();
+        let config = test_config::<SingleProfile<16, 128>>(LidarProfile::SingleReturn, &shifts);
+        let frame = complete_frame(&config, |frame_id, measurement_id| {
+            let mut x = Single128OusterPacket::default();
+            x.header.frame_id = frame_id;
+            x.columns[0].channels_header.measurement_id = measurement_id;
+            for (i, col) in x.columns.iter_mut().enumerate() {
+                for ch in col.channels.iter_mut() {
+                    ch.range_and_reserved = measurement_id as u32 + i as u32 + 1;
+                    ch.signal = 7;
+                }
+            }
+            x
+        });
+
+        let range = frame.range_image(&config);
+        assert_eq!((1024, 128), (range.width(), range.height()));
+        assert_eq!(Some(&1), range.get(0, 3));
+        assert_eq!(Some(&4), range.get(1, 0));
+        assert_eq!(Some(&3), range.get(1, 1024 - 1));
+        assert_eq!(
+            range.stagger(&shifts).row(5),
+            (1..=1024).collect::<Vec<u16>>()
+        );
+        assert!(frame
+            .signal_image(&config)
+            .unwrap()
+            .as_slice()
+            .iter()
+            .all(|&x| x == 7));
+    }
+
+    #[test]
+    fn no_signal_image_for_low_data() {
+        let config = test_config::<LowDataProfile<16, 64>>(LidarProfile::LowData, &[0; 64]);
+        let frame = complete_frame(&config, |frame_id, measurement_id| {
+            let mut x = OusterPacket::<LowDataProfile<16, 64>>::default();
+            x.header.frame_id = frame_id;
+            x.columns[0].channels_header.measurement_id = measurement_id;
+            x
+        });
+        assert!(frame.signal_image(&config).is_none());
+        assert_eq!(1024 * 64, frame.nir_image(&config).as_slice().len());
+    }
+
+    #[test]
+    fn crop_cols() {
+        let image = FieldImage::new(4, 2, (0..8).collect());
+        assert_eq!(&[1, 2, 5, 6], image.crop_cols(1, 2).as_slice());
+    }
+
+    #[test]
+    fn organized_cloud_marks_invalid_points() {
+        let shifts = [[2, -2]; 32].concat();
+        let config = test_config::<DualProfile<16, 64>>(LidarProfile::DualReturn, &shifts);
+        let mut aggregator = Aggregator::new(&config.lidar_data_format.column_window);
+        // Packet 1 is never sent and row 1 doesn't get a return
+        let frame = (0..)
+            .filter(|i: &usize| i % 64 != 1)
+            .find_map(|i| {
+                let mut x = Dual64OusterPacket::default();
+                x.header.frame_id = (i / 64) as u16;
+                x.columns[0].channels_header.measurement_id = (i % 64 * 16) as u16;
+                for col in x.columns.iter_mut() {
+                    col.channels[0].info_ret1.raw = 1000;
+                }
+                aggregator.put_data_value(x)
+            })
+            .unwrap();
+        assert_eq!(1, aggregator.get_statistics().missing_packets[1]);
+        let cartesian = CartesianIterator::new_cheap_cloneable_from_config(&config);
+        let cloud = frame.organized_cloud(&config, cartesian, 16);
+
+        assert_eq!(
+            config.lidar_data_format.calc_complete_cols_aligned(16).1,
+            cloud.width()
+        );
+        assert_eq!(64, cloud.height());
+        assert!(cloud.row(1).iter().all(|p| p.0.is_nan()));
+        let valid = cloud.row(0).iter().filter(|p| !p.0.is_nan()).count();
+        assert_eq!(cloud.width() - 16, valid);
+        let (x, y, z) = *cloud.get(0, 100).unwrap();
+        assert!(((x * x + y * y + z * z).sqrt() - 1000.).abs() < 0.1);
+    }
+
+    #[test]
+    fn field_for_every_profile_field() {
+        let config = test_config::<DualProfile<16, 64>>(LidarProfile::DualReturn, &[0; 64]);
+        let frame = complete_frame(&config, |frame_id, measurement_id| {
+            let mut x = Dual64OusterPacket::default();
+            x.header.frame_id = frame_id;
+            x.columns[0].channels_header.measurement_id = measurement_id;
+            for col in x.columns.iter_mut() {
+                for ch in col.channels.iter_mut() {
+                    ch.info_ret1.raw = 10 | (1 << 24);
+                    ch.info_ret2.raw = 20 | (2 << 24);
+                    ch.signal_ret_1 = 3;
+                    ch.signal_ret_2 = 4;
+                    ch.nir = (5 << 8) + 7;
+                }
+            }
+            x
+        });
+        for (field, expected) in [
+            (ChanField::Range, 10),
+            (ChanField::Range2, 20),
+            (ChanField::Reflectivity, 1),
+            (ChanField::Reflectivity2, 2),
+            (ChanField::Signal, 3),
+            (ChanField::Signal2, 4),
+            (ChanField::Nir, (5 << 8) + 7),
+        ] {
+            let buffer = frame.field(&config, field).unwrap();
+            assert_eq!((1024, 64), (buffer.width(), buffer.height()));
+            assert!(
+                buffer.to_u16().as_slice().iter().all(|&x| x == expected),
+                "{field}"
+            );
+        }
+        assert!(matches!(
+            frame.field(&config, ChanField::Nir),
+            Some(FieldBuffer::U16(_))
+        ));
+    }
+
+    #[test]
+    fn missing_field_is_none() {
+        let config = test_config::<LowDataProfile<16, 64>>(LidarProfile::LowData, &[0; 64]);
+        let frame = complete_frame(&config, |frame_id, measurement_id| {
+            let mut x = OusterPacket::<LowDataProfile<16, 64>>::default();
+            x.header.frame_id = frame_id;
+            x.columns[0].channels_header.measurement_id = measurement_id;
+            x
+        });
+        for field in ChanField::ALL {
+            assert_eq!(
+                LidarProfile::LowData.fields().contains(&field),
+                frame.field(&config, field).is_some()
+            );
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket},
+};
+
+use crate::{Aggregator, CompleteData, ConfigParams, OusterPacket, Profile, UdpDest};
+
+/// Largest possible UDP payload
+const MAX_DATAGRAM_SIZE: usize = 65_536;
+
+/// Receives lidar packets from the sensor and feeds them into an [Aggregator]
+pub struct LidarReceiver {
+    socket: UdpSocket,
+    buf: Box<[u8]>,
+}
+
+impl LidarReceiver {
+    /// Listens on `udp_port_lidar` and joins `udp_dest`, if it's a multicast group
+    pub fn from_config(config_params: &ConfigParams) -> io::Result<Self> {
+        Self::bind(
+            config_params.udp_port_lidar,
+            config_params.udp_dest.as_ref(),
+        )
+    }
+
+    /// Listens on all interfaces. Several hosts can consume the same stream if `udp_dest` is a multicast group
+    pub fn bind(port: u16, udp_dest: Option<&UdpDest>) -> io::Result<Self> {
+        let unspecified: IpAddr = match udp_dest.and_then(UdpDest::ip) {
+            Some(IpAddr::V6(_)) => Ipv6Addr::UNSPECIFIED.into(),
+            _ => Ipv4Addr::UNSPECIFIED.into(),
+        };
+        let socket = UdpSocket::bind(SocketAddr::new(unspecified, port))?;
+        match udp_dest.and_then(UdpDest::multicast_group) {
+            Some(IpAddr::V4(group)) => socket.join_multicast_v4(&group, &Ipv4Addr::UNSPECIFIED)?,
+            Some(IpAddr::V6(group)) => socket.join_multicast_v6(&group, 0)?,
+            None => {}
+        }
+        Ok(Self::from_socket(socket))
+    }
+
+    pub fn from_socket(socket: UdpSocket) -> Self {
+        Self {
+            socket,
+            buf: vec![0; MAX_DATAGRAM_SIZE].into_boxed_slice(),
+        }
+    }
+
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    /// Blocks until a datagram arrives. Datagrams which don't match the size of the packet are ignored
+    pub fn recv<TProfile: Profile>(
+        &mut self,
+        aggregator: &mut Aggregator<TProfile>,
+    ) -> io::Result<Option<CompleteData<TProfile>>> {
+        let len = self.socket.recv(&mut self.buf)?;
+        if len != std::mem::size_of::<OusterPacket<TProfile>>() {
+            return Ok(None);
+        }
+        aggregator.next_buffer().copy_from_slice(&self.buf[..len]);
+        Ok(aggregator.process_tmp())
+    }
+
+    /// Blocks until the next frame is complete
+    pub fn recv_frame<TProfile: Profile>(
+        &mut self,
+        aggregator: &mut Aggregator<TProfile>,
+    ) -> io::Result<CompleteData<TProfile>> {
+        loop {
+            if let Some(frame) = self.recv(aggregator)? {
+                return Ok(frame);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{test_utils::test_config, LidarProfile, Single128OusterPacket, SingleProfile};
+
+    use super::*;
+
+    #[test]
+    fn receive_frame_over_loopback() {
+        let config = test_config::<SingleProfile<16, 128>>(LidarProfile::SingleReturn, &[0; 128]);
+        let window = &config.lidar_data_format.column_window;
+        let mut aggregator = Aggregator::new(window);
+        let mut receiver =
+            LidarReceiver::from_socket(UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap());
+        receiver
+            .socket()
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let target = receiver.socket().local_addr().unwrap();
+        let sender = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+
+        // Datagrams of another size, e.g. from a misconfigured port, are skipped instead of failing the receiver
+        sender.send_to(&[0; 48], target).unwrap();
+        assert!(receiver.recv(&mut aggregator).unwrap().is_none());
+
+        let packets_per_frame = window.len() / 16;
+        let frame = (0..)
+            .find_map(|i: usize| {
+                let mut x = Single128OusterPacket::default();
+                x.header.frame_id = (i / packets_per_frame) as u16;
+                x.columns[0].channels_header.measurement_id = ((i % packets_per_frame) * 16) as u16;
+                x.columns[0].channels[0].range_and_reserved = 42;
+                sender.send_to(x.as_slice(), target).unwrap();
+                receiver.recv(&mut aggregator).unwrap()
+            })
+            .unwrap();
+        assert_eq!(packets_per_frame, frame.len());
+        assert!(frame
+            .iter()
+            .all(|p| p.columns[0].channels[0].range_and_reserved == 42));
+    }
+}
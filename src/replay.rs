@@ -0,0 +1,376 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{OusterConfig, ParseMetadataError, PcapError, PcapReader};
+
+/// Longest sleep between checks, whether the replay was stopped
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Counters of a finished replay
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayStatistics {
+    pub sent: u64,
+    /// Dropped by the injected loss
+    pub dropped: u64,
+    pub loops: usize,
+}
+
+/// Stops a running [PcapReplayer] from another thread
+#[derive(Debug, Clone)]
+pub struct ReplayStopHandle(Arc<AtomicBool>);
+
+impl ReplayStopHandle {
+    /// [PcapReplayer::run] returns after the current datagram, a stopped replayer doesn't send again
+    pub fn stop(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Sends the UDP datagrams of a pcap file to a local address, as if the sensor was live
+///
+/// Datagrams keep their destination port unless it's remapped with [PcapReplayer::map_port],
+/// so lidar and IMU packets arrive on the ports of the recording.
+pub struct PcapReplayer<R> {
+    reader: Option<PcapReader<R>>,
+    socket: UdpSocket,
+    destination: IpAddr,
+    port_mapping: Vec<(u16, u16)>,
+    speed: f64,
+    loops: Option<usize>,
+    jitter: Duration,
+    loss: f64,
+    rng: XorShift,
+    stopped: Arc<AtomicBool>,
+}
+
+impl PcapReplayer<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, PcapError> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> PcapReplayer<R> {
+    /// Sends to localhost at the original speed, once
+    pub fn new(reader: R) -> Result<Self, PcapError> {
+        Ok(Self {
+            reader: Some(PcapReader::new(reader)?),
+            socket: UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?,
+            destination: Ipv4Addr::LOCALHOST.into(),
+            port_mapping: Vec::new(),
+            speed: 1.,
+            loops: Some(1),
+            jitter: Duration::ZERO,
+            loss: 0.,
+            rng: XorShift::new(0x2545_f491_4f6c_dd1d),
+            stopped: Default::default(),
+        })
+    }
+
+    pub fn with_destination(mut self, destination: IpAddr) -> io::Result<Self> {
+        if destination.is_ipv4() != self.destination.is_ipv4() {
+            self.socket = match destination {
+                IpAddr::V4(_) => UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?,
+                IpAddr::V6(_) => UdpSocket::bind((Ipv6Addr::UNSPECIFIED, 0))?,
+            };
+        }
+        self.destination = destination;
+        Ok(self)
+    }
+
+    /// Datagrams sent to `from` in the recording are sent to `to`
+    pub fn map_port(mut self, from: u16, to: u16) -> Self {
+        self.port_mapping.push((from, to));
+        self
+    }
+
+    /// 2 replays twice as fast as recorded, infinity sends without waiting
+    pub fn with_speed(mut self, speed: f64) -> Self {
+        assert!(speed > 0., "Speed has to be positive");
+        self.speed = speed;
+        self
+    }
+
+    /// None loops until the replay is stopped with a [ReplayStopHandle]
+    pub fn with_loops(mut self, loops: Option<usize>) -> Self {
+        self.loops = loops;
+        self
+    }
+
+    /// Every datagram is delayed by a random duration up to `jitter`
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Probability between 0 and 1, that a datagram isn't sent
+    pub fn with_loss(mut self, loss: f64) -> Self {
+        assert!((0. ..=1.).contains(&loss), "Loss has to be between 0 and 1");
+        self.loss = loss;
+        self
+    }
+
+    /// The same seed results in the same jitter and loss
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = XorShift::new(seed);
+        self
+    }
+
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    pub fn stop_handle(&self) -> ReplayStopHandle {
+        ReplayStopHandle(self.stopped.clone())
+    }
+
+    /// Metadata embedded in a pcapng recording, see [PcapReader::metadata_json]
+    pub fn metadata_json(&self) -> Option<&str> {
+        self.reader().metadata_json()
+    }
+
+    /// Configures receivers of the replay without a separate metadata file
+    pub fn ouster_config(&self) -> Option<Result<OusterConfig, ParseMetadataError>> {
+        self.reader().ouster_config()
+    }
+
+    fn reader(&self) -> &PcapReader<R> {
+        self.reader.as_ref().expect("Only taken while rewinding")
+    }
+
+    /// Blocks until all loops are sent or the replay is stopped
+    /// Stops after the first loop, if the recording contains no datagrams
+    pub fn run(&mut self) -> Result<ReplayStatistics, PcapError> {
+        let mut statistics = ReplayStatistics::default();
+        while self.loops.is_none_or(|loops| statistics.loops < loops) {
+            let previous = statistics.sent + statistics.dropped;
+            if !self.replay_once(&mut statistics)? {
+                break;
+            }
+            statistics.loops += 1;
+            self.rewind()?;
+            if statistics.sent + statistics.dropped == previous {
+                break;
+            }
+        }
+        Ok(statistics)
+    }
+
+    /// False if the replay was stopped before the end of the recording
+    fn replay_once(&mut self, statistics: &mut ReplayStatistics) -> Result<bool, PcapError> {
+        let reader = self.reader.as_mut().expect("Only taken while rewinding");
+        let mut start = None;
+        while let Some(datagram) = reader.next_datagram()? {
+            let (start_instant, start_timestamp) =
+                *start.get_or_insert((Instant::now(), datagram.timestamp));
+            if self.speed.is_finite() {
+                let offset = datagram.timestamp.saturating_sub(start_timestamp);
+                let jitter = self.jitter.mul_f64(self.rng.next_f64());
+                let target = start_instant + offset.div_f64(self.speed) + jitter;
+                while let Some(wait) = target.checked_duration_since(Instant::now()) {
+                    if self.stopped.load(Ordering::Relaxed) {
+                        return Ok(false);
+                    }
+                    std::thread::sleep(wait.min(STOP_POLL_INTERVAL));
+                }
+            }
+            if self.stopped.load(Ordering::Relaxed) {
+                return Ok(false);
+            }
+            if self.loss > 0. && self.rng.next_f64() < self.loss {
+                statistics.dropped += 1;
+                continue;
+            }
+            let port = datagram.destination.port();
+            let port = self
+                .port_mapping
+                .iter()
+                .find(|(from, _)| *from == port)
+                .map_or(port, |(_, to)| *to);
+            self.socket
+                .send_to(&datagram.payload, SocketAddr::new(self.destination, port))?;
+            statistics.sent += 1;
+        }
+        Ok(!self.stopped.load(Ordering::Relaxed))
+    }
+
+    fn rewind(&mut self) -> Result<(), PcapError> {
+        let mut inner = self
+            .reader
+            .take()
+            .expect("Only taken while rewinding")
+            .into_inner();
+        inner.seek(SeekFrom::Start(0))?;
+        self.reader = Some(PcapReader::new(inner)?);
+        Ok(())
+    }
+}
+
+/// Small PRNG, the quality is sufficient for injecting jitter and loss
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        // Zero would stay zero forever
+        Self(seed.max(1))
+    }
+
+    /// Uniform in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{
+        test_utils::{frame_packets, pcap_file, test_config},
+        Aggregator, DualProfile, LidarProfile, LidarReceiver,
+    };
+
+    use super::*;
+
+    fn receiver() -> UdpSocket {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        socket
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        socket
+    }
+
+    #[test]
+    fn original_timing_and_loops() {
+        let data = pcap_file(&[
+            (Duration::from_millis(1000), 7502, &[1]),
+            (Duration::from_millis(1040), 7503, &[2; 3000]),
+            (Duration::from_millis(1080), 7502, &[3]),
+        ]);
+        let lidar = receiver();
+        let imu = receiver();
+        let mut replayer = PcapReplayer::new(Cursor::new(data))
+            .unwrap()
+            .map_port(7502, lidar.local_addr().unwrap().port())
+            .map_port(7503, imu.local_addr().unwrap().port())
+            .with_speed(2.)
+            .with_loops(Some(2));
+
+        let start = Instant::now();
+        let statistics = replayer.run().unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(80));
+        assert_eq!(
+            ReplayStatistics {
+                sent: 6,
+                dropped: 0,
+                loops: 2
+            },
+            statistics
+        );
+
+        let mut buf = [0; 4096];
+        for expected in [1, 3, 1, 3] {
+            assert_eq!(1, lidar.recv(&mut buf).unwrap());
+            assert_eq!(expected, buf[0]);
+        }
+        assert_eq!(3000, imu.recv(&mut buf).unwrap());
+    }
+
+    #[test]
+    fn loss_injection() {
+        let datagrams = (0..1000u64)
+            .map(|i| (Duration::from_micros(i), 7502, &[0u8][..]))
+            .collect::<Vec<_>>();
+        let lidar = receiver();
+        let mut replayer = PcapReplayer::new(Cursor::new(pcap_file(&datagrams)))
+            .unwrap()
+            .map_port(7502, lidar.local_addr().unwrap().port())
+            .with_speed(f64::INFINITY)
+            .with_loss(0.3)
+            .with_seed(7);
+        let statistics = replayer.run().unwrap();
+        assert_eq!(1000, statistics.sent + statistics.dropped);
+        assert!((200..400).contains(&statistics.dropped), "{statistics:?}");
+    }
+
+    #[test]
+    fn stop_endless_loops() {
+        let lidar = receiver();
+        let mut replayer = PcapReplayer::new(Cursor::new(pcap_file(&[
+            (Duration::ZERO, 7502, &[1]),
+            (Duration::from_millis(5), 7502, &[2]),
+        ])))
+        .unwrap()
+        .map_port(7502, lidar.local_addr().unwrap().port())
+        .with_loops(None);
+        let stop = replayer.stop_handle();
+        let replay = std::thread::spawn(move || replayer.run().unwrap());
+        let mut buf = [0; 16];
+        for _ in 0..6 {
+            lidar.recv(&mut buf).unwrap();
+        }
+        stop.stop();
+        assert!(replay.join().unwrap().loops >= 2);
+    }
+
+    #[test]
+    fn endless_loops_of_empty_recording() {
+        let mut replayer = PcapReplayer::new(Cursor::new(pcap_file(&[])))
+            .unwrap()
+            .with_loops(None);
+        assert_eq!(
+            ReplayStatistics {
+                sent: 0,
+                dropped: 0,
+                loops: 1
+            },
+            replayer.run().unwrap()
+        );
+    }
+
+    #[test]
+    #[should_panic = "Loss has to be between 0 and 1"]
+    fn invalid_loss() {
+        PcapReplayer::new(Cursor::new(pcap_file(&[])))
+            .unwrap()
+            .with_loss(1.5);
+    }
+
+    #[test]
+    fn frames_for_aggregator() {
+        let config = test_config::<DualProfile<16, 64>>(LidarProfile::DualReturn, &[0; 64]);
+        let packets = frame_packets::<DualProfile<16, 64>>(2);
+        let data = pcap_file(
+            &packets
+                .iter()
+                .enumerate()
+                .map(|(i, x)| (Duration::from_micros(500 * i as u64), 7502, x.as_slice()))
+                .collect::<Vec<_>>(),
+        );
+
+        let mut receiver = LidarReceiver::from_socket(receiver());
+        let port = receiver.socket().local_addr().unwrap().port();
+        let replay = std::thread::spawn(move || {
+            PcapReplayer::new(Cursor::new(data))
+                .unwrap()
+                .map_port(7502, port)
+                .run()
+                .unwrap()
+        });
+        let mut aggregator = Aggregator::new(&config.lidar_data_format.column_window);
+        let frame = receiver.recv_frame(&mut aggregator).unwrap();
+        assert_eq!((0, 64), (frame.frame_id(), frame.len()));
+        assert_eq!(128, replay.join().unwrap().sent);
+    }
+}
@@ -0,0 +1,163 @@
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+};
+
+use serde_json::{json, Value};
+
+/// Minimal HTTP server, which mimics the sensor API to test tooling without hardware
+///
+/// Posted config params are staged and become active on reinitialize
+pub struct MockSensor {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+struct MockState {
+    metadata: Value,
+    staged_config: Option<Value>,
+}
+
+impl MockSensor {
+    /// Serves `metadata` on a free port of localhost
+    pub fn start(metadata: &str) -> io::Result<Self> {
+        let metadata = serde_json::from_str(metadata)?;
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(MockState {
+            metadata,
+            staged_config: None,
+        }));
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let state = state.clone();
+            let stop = stop.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        if let Err(err) = handle_connection(stream, &state) {
+                            log::warn!("Mock sensor: {err}");
+                        }
+                    }
+                }
+            })
+        };
+        Ok(Self {
+            addr,
+            state,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Host with port as expected by the clients
+    pub fn host(&self) -> String {
+        self.addr.to_string()
+    }
+
+    /// Metadata including the active config params
+    pub fn metadata(&self) -> Value {
+        self.state.lock().unwrap().metadata.clone()
+    }
+}
+
+impl Drop for MockSensor {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // Wake up the blocking accept
+        let _ = TcpStream::connect(self.addr);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn handle_connection(stream: TcpStream, state: &Mutex<MockState>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_owned();
+    let path = parts.next().unwrap_or_default().to_owned();
+
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or_default();
+            }
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    let response = {
+        let mut state = state.lock().unwrap();
+        route(&mut state, &method, &path, &body)
+    };
+    let (status, body) = match response {
+        Some(value) => ("200 OK", value.to_string()),
+        None => ("404 Not Found", String::new()),
+    };
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+fn route(state: &mut MockState, method: &str, path: &str, body: &[u8]) -> Option<Value> {
+    match (method, path) {
+        ("GET", "/api/v1/sensor/metadata") => Some(state.metadata.clone()),
+        ("GET", "/api/v1/sensor/config") => Some(state.metadata["config_params"].clone()),
+        ("POST", "/api/v1/sensor/config") => {
+            let posted = serde_json::from_slice::<Value>(body).ok()?;
+            let mut staged = state.metadata["config_params"].clone();
+            for (key, value) in posted.as_object()? {
+                staged[key] = value.clone();
+            }
+            state.staged_config = Some(staged);
+            Some(Value::Object(Default::default()))
+        }
+        ("POST", "/api/v1/sensor/cmd/reinitialize") => {
+            if let Some(staged) = state.staged_config.take() {
+                state.metadata["config_params"] = staged;
+            }
+            Some(Value::Object(Default::default()))
+        }
+        ("GET", "/api/v1/system/firmware") => {
+            Some(json!({ "fw": state.metadata["sensor_info"]["image_rev"] }))
+        }
+        ("GET", "/api/v1/system/network") => Some(json!({
+            "carrier": true,
+            "duplex": "full",
+            "hostname": format!("os-{}", state.metadata["sensor_info"]["prod_sn"].as_str().unwrap_or_default()),
+            "ipv4": { "addr": "127.0.0.1/8", "link_local": "169.254.0.1/16", "override": null },
+            "ipv6": { "link_local": "fe80::1/64" },
+            "speed": 1000
+        })),
+        _ => None,
+    }
+}
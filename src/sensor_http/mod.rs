@@ -0,0 +1,173 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{ConfigParams, OusterConfig, ParseMetadataError, SensorMetadata};
+
+mod mock;
+
+pub use mock::*;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, thiserror::Error)]
+pub enum SensorHttpError {
+    #[error("Request failed: {0}")]
+    Http(#[from] Box<ureq::Error>),
+    #[error("Reading the response failed: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid json: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid metadata: {0}")]
+    Metadata(#[from] ParseMetadataError),
+}
+
+impl From<ureq::Error> for SensorHttpError {
+    fn from(value: ureq::Error) -> Self {
+        Self::Http(Box::new(value))
+    }
+}
+
+/// Client for the HTTP API of the sensor (firmware >= 2.0)
+pub struct SensorHttpClient {
+    base_url: String,
+    agent: ureq::Agent,
+}
+
+impl SensorHttpClient {
+    /// `host` is the hostname or ip of the sensor, optionally with port
+    pub fn new(host: &str) -> Self {
+        Self::with_timeout(host, DEFAULT_TIMEOUT)
+    }
+
+    pub fn with_timeout(host: &str, timeout: Duration) -> Self {
+        Self {
+            base_url: format!("http://{host}"),
+            agent: ureq::AgentBuilder::new().timeout(timeout).build(),
+        }
+    }
+
+    /// Complete metadata, only available for the nested layout of firmware >= 2.x
+    pub fn metadata(&self) -> Result<SensorMetadata, SensorHttpError> {
+        Ok(serde_json::from_str(&self.get("/api/v1/sensor/metadata")?)?)
+    }
+
+    /// Config for processing the data, independent of the metadata layout of the firmware
+    pub fn ouster_config(&self) -> Result<OusterConfig, SensorHttpError> {
+        let metadata = self.get("/api/v1/sensor/metadata")?;
+        Ok(OusterConfig::from_metadata_json(metadata.as_bytes())?)
+    }
+
+    /// Active config params
+    pub fn config_params(&self) -> Result<ConfigParams, SensorHttpError> {
+        Ok(serde_json::from_str(&self.get("/api/v1/sensor/config")?)?)
+    }
+
+    /// Depending on the firmware, the config params become active directly or with [SensorHttpClient::reinitialize]
+    pub fn set_config_params(&self, config_params: &ConfigParams) -> Result<(), SensorHttpError> {
+        self.agent
+            .post(&self.url("/api/v1/sensor/config"))
+            .set("Content-Type", "application/json")
+            .send_string(&serde_json::to_string(config_params)?)?;
+        Ok(())
+    }
+
+    pub fn reinitialize(&self) -> Result<(), SensorHttpError> {
+        self.agent
+            .post(&self.url("/api/v1/sensor/cmd/reinitialize"))
+            .call()?;
+        Ok(())
+    }
+
+    /// Firmware image, e.g. `ousteros-image-prod-aries-v3.0.1+20221109215206`
+    pub fn firmware(&self) -> Result<String, SensorHttpError> {
+        #[derive(Deserialize)]
+        struct Firmware {
+            fw: String,
+        }
+        let firmware: Firmware = serde_json::from_str(&self.get("/api/v1/system/firmware")?)?;
+        Ok(firmware.fw)
+    }
+
+    /// Network settings of the sensor, not modelled as they differ between firmwares
+    pub fn network(&self) -> Result<Value, SensorHttpError> {
+        Ok(serde_json::from_str(&self.get("/api/v1/system/network")?)?)
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{path}", self.base_url)
+    }
+
+    fn get(&self, path: &str) -> Result<String, SensorHttpError> {
+        Ok(self.agent.get(&self.url(path)).call()?.into_string()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ConfigParamsRaw, DualProfile, LidarMode, ValidOusterConfig};
+
+    use super::*;
+
+    const METADATA: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/data/os-1-64_v3.0.1_1024x10.json"
+    ));
+
+    #[test]
+    fn read_metadata() {
+        let sensor = MockSensor::start(METADATA).unwrap();
+        let client = SensorHttpClient::new(&sensor.host());
+        let metadata = client.metadata().unwrap();
+        assert_eq!("992109000258", metadata.sensor_info.prod_sn);
+        let config = client.ouster_config().unwrap();
+        ValidOusterConfig::<DualProfile<16, 64>>::try_from(config).unwrap();
+        assert_eq!(
+            "ousteros-image-prod-aries-v3.0.1+20221109215206",
+            client.firmware().unwrap()
+        );
+        assert!(client.network().unwrap()["ipv4"].is_object());
+    }
+
+    #[test]
+    fn change_config_params() {
+        let sensor = MockSensor::start(METADATA).unwrap();
+        let client = SensorHttpClient::new(&sensor.host());
+        let mut raw = ConfigParamsRaw::from(client.config_params().unwrap());
+        raw.lidar_mode = LidarMode::Mode2048x10;
+        client.set_config_params(&raw.try_into().unwrap()).unwrap();
+        assert_eq!(
+            LidarMode::Mode1024x10,
+            client.config_params().unwrap().lidar_mode
+        );
+
+        client.reinitialize().unwrap();
+        assert_eq!(
+            LidarMode::Mode2048x10,
+            client.config_params().unwrap().lidar_mode
+        );
+        assert_eq!(
+            "2048x10",
+            client.metadata().unwrap().config_params.lidar_mode.name()
+        );
+    }
+
+    #[test]
+    fn missing_firmware() {
+        // Without sensor_info the mock responds with `"fw": null`
+        let sensor = MockSensor::start("{}").unwrap();
+        let client = SensorHttpClient::new(&sensor.host());
+        assert!(matches!(client.firmware(), Err(SensorHttpError::Json(_))));
+    }
+
+    #[test]
+    fn unknown_endpoint() {
+        let sensor = MockSensor::start(METADATA).unwrap();
+        let client = SensorHttpClient::new(&sensor.host());
+        assert!(matches!(
+            client.get("/api/v1/unknown"),
+            Err(SensorHttpError::Http(_))
+        ));
+    }
+}
@@ -0,0 +1,298 @@
+use std::{
+    fmt::Display,
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use serde_json::{Map, Value};
+
+use crate::{
+    ConfigParams, ConfigParamsRaw, InvalidConfig, LegacyMetadata, OusterConfig, SensorInfo,
+};
+
+/// Port of the text based configuration protocol
+pub const SENSOR_TCP_PORT: u16 = 7501;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, thiserror::Error)]
+pub enum SensorTcpError {
+    #[error("Connection failed: {0}")]
+    Io(#[from] io::Error),
+    #[error("Invalid json: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid config: {0}")]
+    InvalidConfig(#[from] InvalidConfig),
+    /// The sensor didn't accept the command, e.g. because the firmware doesn't know it
+    #[error("Command '{command}' failed: {response}")]
+    Command { command: String, response: String },
+}
+
+/// Client for the TCP command interface, which is the only API of firmware < 2.0
+pub struct SensorTcpClient {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl SensorTcpClient {
+    /// `addr` of the sensor, usually with [SENSOR_TCP_PORT]
+    pub fn connect(addr: impl ToSocketAddrs) -> Result<Self, SensorTcpError> {
+        let writer = TcpStream::connect(addr)?;
+        writer.set_read_timeout(Some(DEFAULT_TIMEOUT))?;
+        Ok(Self {
+            reader: BufReader::new(writer.try_clone()?),
+            writer,
+        })
+    }
+
+    /// Sends a raw command and returns the response line
+    pub fn command(&mut self, command: &str) -> Result<String, SensorTcpError> {
+        self.writer.write_all(format!("{command}\n").as_bytes())?;
+        let mut response = String::new();
+        if self.reader.read_line(&mut response)? == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        let response = response.trim_end();
+        if response.starts_with("error") {
+            return Err(SensorTcpError::Command {
+                command: command.into(),
+                response: response.into(),
+            });
+        }
+        Ok(response.into())
+    }
+
+    pub fn sensor_info(&mut self) -> Result<SensorInfo, SensorTcpError> {
+        Ok(serde_json::from_value(self.json("get_sensor_info")?)?)
+    }
+
+    pub fn config_params_active(&mut self) -> Result<ConfigParams, SensorTcpError> {
+        self.config_params("get_config_param active")
+    }
+
+    pub fn config_params_staged(&mut self) -> Result<ConfigParams, SensorTcpError> {
+        self.config_params("get_config_param staged")
+    }
+
+    /// Builds the same config as [OusterConfig::from_metadata_json] for the metadata of this sensor
+    /// The config params contain everything the sensor reports
+    pub fn ouster_config(&mut self) -> Result<OusterConfig, SensorTcpError> {
+        let params = normalize_config_params(self.json("get_config_param active")?);
+        let mut legacy = self.json("get_beam_intrinsics")?;
+        for key in ["lidar_mode", "udp_port_lidar", "azimuth_window"] {
+            if let Some(value) = params.get(key) {
+                legacy[key] = value.clone();
+            }
+        }
+        match self.json("get_lidar_data_format") {
+            Ok(format) => legacy["data_format"] = format,
+            // Not available before firmware 1.14
+            Err(SensorTcpError::Command { .. }) => {}
+            Err(err) => return Err(err),
+        }
+        let mut config: OusterConfig =
+            serde_json::from_value::<LegacyMetadata>(legacy)?.try_into()?;
+
+        let mut merged = serde_json::to_value(&config.config_params)?;
+        merged
+            .as_object_mut()
+            .expect("Serialized struct")
+            .extend(params);
+        config.config_params = serde_json::from_value(merged)?;
+        Ok(config)
+    }
+
+    /// Becomes active after [SensorTcpClient::reinitialize]
+    pub fn set_config_param(
+        &mut self,
+        key: &str,
+        value: impl Display,
+    ) -> Result<(), SensorTcpError> {
+        self.expect_echo(
+            &format!("set_config_param {key} {value}"),
+            "set_config_param",
+        )
+    }
+
+    pub fn reinitialize(&mut self) -> Result<(), SensorTcpError> {
+        self.expect_echo("reinitialize", "reinitialize")
+    }
+
+    fn expect_echo(&mut self, command: &str, echo: &str) -> Result<(), SensorTcpError> {
+        let response = self.command(command)?;
+        if response != echo {
+            return Err(SensorTcpError::Command {
+                command: command.into(),
+                response,
+            });
+        }
+        Ok(())
+    }
+
+    fn json(&mut self, command: &str) -> Result<Value, SensorTcpError> {
+        Ok(serde_json::from_str(&self.command(command)?)?)
+    }
+
+    fn config_params(&mut self, command: &str) -> Result<ConfigParams, SensorTcpError> {
+        let mut merged = serde_json::to_value(ConfigParamsRaw::default())?;
+        let params = normalize_config_params(self.json(command)?);
+        merged
+            .as_object_mut()
+            .expect("Serialized struct")
+            .extend(params);
+        Ok(serde_json::from_value(merged)?)
+    }
+}
+
+/// Older firmwares report some values as strings and use different names
+fn normalize_config_params(params: Value) -> Map<String, Value> {
+    let Value::Object(params) = params else {
+        return Map::new();
+    };
+    params
+        .into_iter()
+        .map(|(key, value)| {
+            let key = match key.as_str() {
+                "udp_ip" => "udp_dest".to_owned(),
+                _ => key,
+            };
+            let value = match value {
+                Value::String(s) => serde_json::from_str::<Value>(&s)
+                    .ok()
+                    .filter(|v| v.is_number() || v.is_boolean())
+                    .unwrap_or(Value::String(s)),
+                value => value,
+            };
+            (key, value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        net::{Ipv4Addr, SocketAddr, TcpListener},
+    };
+
+    use serde_json::json;
+
+    use crate::{LegacyProfile, LidarMode, TimestampMode, ValidOusterConfig};
+
+    use super::*;
+
+    const LEGACY: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/data/os-1-64_v1.14.0_1024x10_legacy.json"
+    ));
+
+    /// Answers like a sensor with firmware 1.14 for a single connection
+    fn fake_sensor() -> SocketAddr {
+        let metadata: Value = serde_json::from_str(LEGACY).unwrap();
+        let mut active = json!({
+            "udp_ip": "",
+            "udp_port_lidar": "7502",
+            "udp_port_imu": 7503,
+            "timestamp_mode": "TIME_FROM_INTERNAL_OSC",
+            "auto_start_flag": 1,
+            "lidar_mode": metadata["lidar_mode"],
+            "azimuth_window": [0, 360000],
+            "phase_lock_enable": "false",
+        });
+        let mut staged = active.clone();
+        let mut responses = HashMap::new();
+        responses.insert(
+            "get_sensor_info",
+            json!({
+                "prod_line": metadata["prod_line"],
+                "prod_sn": metadata["prod_sn"],
+                "build_rev": metadata["build_rev"],
+                "status": metadata["status"],
+                "proto_rev": "v1.1.1",
+            }),
+        );
+        responses.insert(
+            "get_beam_intrinsics",
+            json!({
+                "beam_altitude_angles": metadata["beam_altitude_angles"],
+                "beam_azimuth_angles": metadata["beam_azimuth_angles"],
+                "lidar_origin_to_beam_origin_mm": metadata["lidar_origin_to_beam_origin_mm"],
+            }),
+        );
+        responses.insert("get_lidar_data_format", metadata["data_format"].clone());
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            for line in BufReader::new(stream).lines() {
+                let line = line.unwrap();
+                let mut args = line.split(' ');
+                let response = match (args.next().unwrap(), args.next(), args.next()) {
+                    ("get_config_param", Some("active"), None) => active.to_string(),
+                    ("get_config_param", Some("staged"), None) => staged.to_string(),
+                    ("set_config_param", Some(key), Some(value)) => {
+                        staged[key] = value.into();
+                        "set_config_param".into()
+                    }
+                    ("reinitialize", None, None) => {
+                        active = staged.clone();
+                        "reinitialize".into()
+                    }
+                    (command, None, None) if responses.contains_key(command) => {
+                        responses[command].to_string()
+                    }
+                    _ => "error: unknown command".into(),
+                };
+                writeln!(writer, "{response}").unwrap();
+            }
+        });
+        addr
+    }
+
+    #[test]
+    fn same_config_as_json_path() {
+        let mut client = SensorTcpClient::connect(fake_sensor()).unwrap();
+        let config = client.ouster_config().unwrap();
+        let expected = OusterConfig::from_metadata_json(LEGACY.as_bytes()).unwrap();
+        assert_eq!(expected.beam_intrinsics, config.beam_intrinsics);
+        assert_eq!(expected.lidar_data_format, config.lidar_data_format);
+        assert_eq!(
+            expected.config_params.lidar_mode,
+            config.config_params.lidar_mode
+        );
+        assert_eq!(
+            Some(TimestampMode::InternalOsc),
+            config.config_params.timestamp_mode
+        );
+        assert_eq!(Some(false), config.config_params.phase_lock_enable);
+        ValidOusterConfig::<LegacyProfile<16, 64>>::try_from(config).unwrap();
+
+        assert_eq!("992109000258", client.sensor_info().unwrap().prod_sn);
+    }
+
+    #[test]
+    fn set_config_param_and_reinitialize() {
+        let mut client = SensorTcpClient::connect(fake_sensor()).unwrap();
+        client.set_config_param("lidar_mode", "2048x10").unwrap();
+        assert_eq!(
+            LidarMode::Mode1024x10,
+            client.config_params_active().unwrap().lidar_mode
+        );
+        assert_eq!(
+            LidarMode::Mode2048x10,
+            client.config_params_staged().unwrap().lidar_mode
+        );
+        client.reinitialize().unwrap();
+        assert_eq!(
+            LidarMode::Mode2048x10,
+            client.config_params_active().unwrap().lidar_mode
+        );
+        assert!(matches!(
+            client.command("get_alerts"),
+            Err(SensorTcpError::Command { .. })
+        ));
+    }
+}
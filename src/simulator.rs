@@ -0,0 +1,359 @@
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    CartesianIterator, ChanField, ColumnHeaderMut, OusterPacket, PointInfosMut, ProfileMut,
+    ValidOusterConfig, ValidWindow,
+};
+
+/// Geometry of a [SceneObject] in meters, relative to the lidar frame
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    /// Infinite plane through `point`
+    Plane {
+        point: [f32; 3],
+        normal: [f32; 3],
+    },
+    /// Axis aligned box
+    Box {
+        min: [f32; 3],
+        max: [f32; 3],
+    },
+    Sphere {
+        center: [f32; 3],
+        radius: f32,
+    },
+}
+
+impl Shape {
+    /// Distance along the normalized direction to the first surface in front of the origin
+    pub fn intersect(&self, origin: [f32; 3], direction: [f32; 3]) -> Option<f32> {
+        match self {
+            Shape::Plane { point, normal } => {
+                let denominator = dot(direction, *normal);
+                if denominator.abs() < f32::EPSILON {
+                    return None;
+                }
+                let t = dot(sub(*point, origin), *normal) / denominator;
+                (t > 0.).then_some(t)
+            }
+            Shape::Box { min, max } => {
+                let (mut near, mut far) = (f32::NEG_INFINITY, f32::INFINITY);
+                for axis in 0..3 {
+                    let t1 = (min[axis] - origin[axis]) / direction[axis];
+                    let t2 = (max[axis] - origin[axis]) / direction[axis];
+                    near = near.max(t1.min(t2));
+                    far = far.min(t1.max(t2));
+                }
+                // Origins inside of the box hit the walls from the inside
+                if far < near.max(0.) {
+                    None
+                } else if near > 0. {
+                    Some(near)
+                } else {
+                    Some(far)
+                }
+            }
+            Shape::Sphere { center, radius } => {
+                let to_origin = sub(origin, *center);
+                let b = dot(to_origin, direction);
+                let discriminant = b * b - (dot(to_origin, to_origin) - radius * radius);
+                if discriminant < 0. {
+                    return None;
+                }
+                let root = discriminant.sqrt();
+                [-b - root, -b + root].into_iter().find(|t| *t > 0.)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneObject {
+    pub shape: Shape,
+    pub reflectivity: u8,
+    /// Ambient light, reported as nir
+    pub nir: u16,
+}
+
+/// Objects which are seen by the [Simulator]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Scene {
+    pub objects: Vec<SceneObject>,
+}
+
+impl Scene {
+    pub fn with_object(mut self, shape: Shape, reflectivity: u8, nir: u16) -> Self {
+        self.objects.push(SceneObject {
+            shape,
+            reflectivity,
+            nir,
+        });
+        self
+    }
+
+    /// Closest object hit by the ray and the distance in meters
+    pub fn cast(&self, origin: [f32; 3], direction: [f32; 3]) -> Option<(f32, &SceneObject)> {
+        self.objects
+            .iter()
+            .filter_map(|object| Some((object.shape.intersect(origin, direction)?, object)))
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+    }
+}
+
+/// Creates the packets a static sensor would send while looking at a [Scene]
+///
+/// Beams are cast along the same directions the [CartesianIterator] uses, so converted points lie on the scene.
+/// Only the first return is simulated, ranges beyond the 65.535 m, which [crate::PointInfos] reports, have no return.
+pub struct Simulator<TProfile: ProfileMut> {
+    scene: Scene,
+    cartesian: CartesianIterator<TProfile, Arc<[(f32, f32)]>>,
+    window: ValidWindow<TProfile>,
+    n_vec: u32,
+    frame_period: Duration,
+    column_period: Duration,
+    frame_id: u16,
+    frame_timestamp: Duration,
+}
+
+impl<TProfile: ProfileMut> Simulator<TProfile> {
+    /// Rotates with the frequency of the lidar mode
+    pub fn new(config: &ValidOusterConfig<TProfile>, scene: Scene) -> Self {
+        Self {
+            scene,
+            cartesian: CartesianIterator::new_cheap_cloneable_from_config(config),
+            window: config.lidar_data_format.column_window.clone(),
+            n_vec: config.n_vec(),
+            frame_period: config.config_params.lidar_mode.frame_period(),
+            column_period: config.measurement_time_offset(1),
+            frame_id: 0,
+            frame_timestamp: Duration::ZERO,
+        }
+    }
+
+    /// Timestamp of the first column of measurement_id 0
+    pub fn with_start_timestamp(mut self, timestamp: Duration) -> Self {
+        self.frame_timestamp = timestamp;
+        self
+    }
+
+    pub fn scene_mut(&mut self) -> &mut Scene {
+        &mut self.scene
+    }
+
+    /// Packets of the column window of the next rotation, all with the same frame_id
+    pub fn next_frame(&mut self) -> Vec<OusterPacket<TProfile>> {
+        let mut cartesian = self.cartesian.clone();
+        let packets = (0..self.window.required_measurements)
+            .map(|i| {
+                let first_column = ((self.window.start_measurement_id as usize + i)
+                    % self.window.measurements_per_frame as usize)
+                    * TProfile::COLUMNS;
+                let mut packet = OusterPacket::<TProfile>::default();
+                packet.set_frame_id(self.frame_id);
+                for (measurement_id, column) in
+                    (first_column..).zip(packet.columns.as_mut().iter_mut())
+                {
+                    column
+                        .channels_header
+                        .set_measurement_id(measurement_id as u16);
+                    column.channels_header.set_timestamp(
+                        self.frame_timestamp + self.column_period * measurement_id as u32,
+                    );
+                    column.set_valid(true);
+                    for (channel, polar_point) in
+                        column.channels.as_mut().iter_mut().zip(&mut cartesian)
+                    {
+                        let origin = polar_point.translation;
+                        let (x, y, z) = polar_point.calc_xyz(1.);
+                        let direction = [x - origin.0, y - origin.1, z - origin.2];
+                        let origin = [origin.0, origin.1, origin.2].map(|x| x / 1000.);
+                        self.simulate_pixel(channel, origin, direction);
+                    }
+                }
+                packet
+            })
+            .collect();
+        self.frame_id = self.frame_id.wrapping_add(1);
+        self.frame_timestamp += self.frame_period;
+        packets
+    }
+
+    /// Endless stream of packets
+    pub fn packets(&mut self) -> impl Iterator<Item = OusterPacket<TProfile>> + '_ {
+        std::iter::repeat_with(|| self.next_frame()).flatten()
+    }
+
+    fn simulate_pixel(
+        &self,
+        channel: &mut TProfile::Channel,
+        origin: [f32; 3],
+        direction: [f32; 3],
+    ) {
+        let Some((distance, object)) = self.scene.cast(origin, direction) else {
+            return;
+        };
+        let range = (distance * 1000.).round() as u32 + self.n_vec;
+        if range > u16::MAX as u32 {
+            return;
+        }
+        // Returned energy decreases with the square of the distance
+        let signal = object.reflectivity as f32 * 1000. / distance.max(1.).powi(2);
+        channel.set_field(ChanField::Range, range);
+        channel.set_field(ChanField::Reflectivity, object.reflectivity as u32);
+        channel.set_field(ChanField::Signal, signal as u32);
+        channel.set_field(ChanField::Nir, object.nir as u32);
+    }
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        packet::ColumnHeader, test_utils::test_ouster_config, Aggregator, DualLowProfile,
+        DualProfile, LegacyProfile, LidarMode, LidarProfile, LowDataProfile, PointInfos,
+    };
+
+    use super::*;
+
+    #[test]
+    fn intersections() {
+        let forward = [1., 0., 0.];
+        let plane = Shape::Plane {
+            point: [0., 0., -2.],
+            normal: [0., 0., 1.],
+        };
+        assert_eq!(None, plane.intersect([0.; 3], forward));
+        assert_eq!(Some(2.), plane.intersect([0.; 3], [0., 0., -1.]));
+
+        let cube = Shape::Box {
+            min: [4., -1., -1.],
+            max: [6., 1., 1.],
+        };
+        assert_eq!(Some(4.), cube.intersect([0.; 3], forward));
+        assert_eq!(Some(1.), cube.intersect([5., 0., 0.], forward));
+        assert_eq!(None, cube.intersect([0.; 3], [-1., 0., 0.]));
+
+        let sphere = Shape::Sphere {
+            center: [10., 0., 0.],
+            radius: 2.,
+        };
+        assert_eq!(Some(8.), sphere.intersect([0.; 3], forward));
+        assert_eq!(None, sphere.intersect([0.; 3], [0., 1., 0.]));
+
+        let scene = Scene::default()
+            .with_object(sphere, 10, 0)
+            .with_object(cube, 20, 0);
+        assert_eq!(
+            Some(20),
+            scene.cast([0.; 3], forward).map(|x| x.1.reflectivity)
+        );
+    }
+
+    #[test]
+    fn frames_match_scene() {
+        let config = test_ouster_config::<DualProfile<16, 64>>(LidarProfile::DualReturn, &[0; 64]);
+        // Cylinder like room around the sensor, the sensor looks towards x at measurement_id 0
+        let scene = Scene::default().with_object(
+            Shape::Sphere {
+                center: [0.; 3],
+                radius: 20.,
+            },
+            100,
+            30,
+        );
+        let mut simulator = Simulator::new(&config, scene);
+        let packets = simulator.next_frame();
+        assert_eq!(64, packets.len());
+        assert_eq!(1, simulator.next_frame()[0].frame_id());
+
+        let column = &packets[2].columns[3];
+        assert_eq!(35, column.channels_header.measurement_id());
+        assert_eq!(
+            LidarMode::Mode1024x10.column_period() * 35,
+            column.channels_header.timestamp()
+        );
+        assert!(column.channels_header.is_valid());
+        let channel = &column.channels[5];
+        assert_eq!(
+            [20000, 100, 250, 30, 0],
+            [
+                ChanField::Range,
+                ChanField::Reflectivity,
+                ChanField::Signal,
+                ChanField::Nir,
+                ChanField::Range2
+            ]
+            .map(|f| channel.get_field(f, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn pipeline_roundtrip() {
+        let config = test_ouster_config::<DualProfile<16, 64>>(LidarProfile::DualReturn, &[0; 64]);
+        let scene = Scene::default().with_object(
+            Shape::Box {
+                min: [5., -2., -1.],
+                max: [6., 2., 1.],
+            },
+            50,
+            0,
+        );
+        let mut simulator = Simulator::new(&config, scene);
+        let mut aggregator = Aggregator::from_config(&config);
+        let frame = simulator
+            .packets()
+            .find_map(|packet| aggregator.put_data_value(packet))
+            .unwrap();
+        assert_eq!(0, frame.frame_id());
+
+        let cartesian = CartesianIterator::new_cheap_cloneable_from_config(&config);
+        let points = frame.export_points(&config, cartesian).collect::<Vec<_>>();
+        // tan(2m / 5m) covers ~43.6° of 360°
+        assert!(
+            (120..130).contains(&(points.len() / 64)),
+            "{}",
+            points.len()
+        );
+        assert!(points.iter().all(|p| {
+            (p.xyz.0 - 5.).abs() < 0.002 && p.xyz.1.abs() <= 2.001 && p.reflectivity == 50
+        }));
+    }
+
+    #[test]
+    fn all_profiles() {
+        fn range_of_first_pixel<TProfile: ProfileMut>(profile: LidarProfile) -> u16 {
+            let config = test_ouster_config::<TProfile>(profile, &[0; 16]);
+            let scene = Scene::default().with_object(
+                Shape::Plane {
+                    point: [3., 0., 0.],
+                    normal: [-1., 0., 0.],
+                },
+                1,
+                1,
+            );
+            let packets = Simulator::new(&config, scene).next_frame();
+            packets[0].columns.as_ref()[0].channels.as_ref()[0]
+                .get_field(ChanField::Range, 0)
+                .unwrap()
+        }
+        assert_eq!(
+            3000,
+            range_of_first_pixel::<LowDataProfile<16, 16>>(LidarProfile::LowData)
+        );
+        assert_eq!(
+            3000,
+            range_of_first_pixel::<DualLowProfile<16, 16>>(LidarProfile::DualLowData)
+        );
+        assert_eq!(
+            3000,
+            range_of_first_pixel::<LegacyProfile<16, 16>>(LidarProfile::Legacy)
+        );
+    }
+}
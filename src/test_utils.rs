@@ -0,0 +1,128 @@
+use std::time::Duration;
+
+use crate::{
+    Aggregator, BeamIntrinsics, ChanField, ColumnHeaderMut, CompleteData, ConfigParamsRaw,
+    LidarDataFormat, LidarProfile, OusterPacket, PointInfosMut, Profile, ProfileMut,
+    ValidOperationConfig, ValidOusterConfig,
+};
+
+pub(crate) fn test_config<TProfile: Profile>(
+    udp_profile_lidar: LidarProfile,
+    pixel_shift_by_row: &[i8],
+) -> ValidOperationConfig<TProfile> {
+    assert_eq!(TProfile::LAYERS, pixel_shift_by_row.len());
+    ValidOperationConfig {
+        beam_intrinsics: BeamIntrinsics {
+            beam_altitude_angles: vec![0.; TProfile::LAYERS],
+            beam_azimuth_angles: vec![0.; TProfile::LAYERS],
+            lidar_origin_to_beam_origin_mm: 0.,
+            beam_to_lidar_transform: [0.; 16],
+        },
+        lidar_data_format: LidarDataFormat {
+            columns_per_packet: TProfile::COLUMNS as _,
+            pixels_per_column: TProfile::LAYERS as _,
+            columns_per_frame: 1024,
+            pixel_shift_by_row: pixel_shift_by_row.into(),
+            column_window: (0, 1023),
+            udp_profile_lidar,
+        }
+        .try_into()
+        .unwrap(),
+    }
+}
+
+/// test_config with the default config_params, whose lidar mode is 1024x10
+pub(crate) fn test_ouster_config<TProfile: Profile>(
+    udp_profile_lidar: LidarProfile,
+    pixel_shift_by_row: &[i8],
+) -> ValidOusterConfig<TProfile> {
+    ValidOusterConfig {
+        config_params: ConfigParamsRaw {
+            udp_profile_lidar,
+            ..Default::default()
+        }
+        .try_into()
+        .unwrap(),
+        valid_operation: test_config(udp_profile_lidar, pixel_shift_by_row),
+    }
+}
+
+/// Feeds a complete frame and the beginning of the next one into a new aggregator
+/// create is called with (frame_id, measurement_id) of the packet to create
+pub(crate) fn complete_frame<TProfile: Profile>(
+    config: &ValidOperationConfig<TProfile>,
+    mut create: impl FnMut(u16, u16) -> OusterPacket<TProfile>,
+) -> CompleteData<TProfile> {
+    let window = &config.lidar_data_format.column_window;
+    let mut aggregator = Aggregator::new(window);
+    let packets_per_frame = window.len() / TProfile::COLUMNS;
+    (0..)
+        .find_map(|i: usize| {
+            let frame_id = (i / packets_per_frame) as u16;
+            let measurement_id = ((i % packets_per_frame) * TProfile::COLUMNS) as u16;
+            aggregator.put_data_value(create(frame_id, measurement_id))
+        })
+        .unwrap()
+}
+
+/// Packets of `frames` rotations with 1024 columns, only the first column of each packet is filled
+/// Its timestamp is 1 ms after the start of the frame with 10 Hz and the first pixel has a range of 1000 mm
+pub(crate) fn frame_packets<TProfile: ProfileMut>(frames: u16) -> Vec<OusterPacket<TProfile>> {
+    let packets_per_frame = (1024 / TProfile::COLUMNS) as u16;
+    (0..frames * packets_per_frame)
+        .map(|i| {
+            let frame_id = i / packets_per_frame;
+            let mut packet = OusterPacket::<TProfile>::default();
+            packet.set_frame_id(frame_id);
+            let column = &mut packet.columns.as_mut()[0];
+            column
+                .channels_header
+                .set_measurement_id((i % packets_per_frame) * TProfile::COLUMNS as u16);
+            column
+                .channels_header
+                .set_timestamp(Duration::from_millis(100 * frame_id as u64 + 1));
+            column.channels.as_mut()[0].set_field(ChanField::Range, 1000);
+            packet
+        })
+        .collect()
+}
+
+/// Pcap file with Ethernet frames, UDP payloads exceeding 1500 bytes are split into IPv4 fragments
+pub(crate) fn pcap_file(datagrams: &[(Duration, u16, &[u8])]) -> Vec<u8> {
+    const MTU_PAYLOAD: usize = 1480;
+    let mut out = Vec::new();
+    // Magic, version 2.4, timezone, accuracy, snaplen, Ethernet
+    out.extend_from_slice(&0xa1b2_c3d4u32.to_le_bytes());
+    out.extend_from_slice(&[2, 0, 4, 0]);
+    for x in [0u32, 0, 65535, 1] {
+        out.extend_from_slice(&x.to_le_bytes());
+    }
+    for (identification, (timestamp, port, payload)) in datagrams.iter().enumerate() {
+        let mut udp = Vec::new();
+        udp.extend_from_slice(&40000u16.to_be_bytes());
+        udp.extend_from_slice(&port.to_be_bytes());
+        udp.extend_from_slice(&(payload.len() as u16 + 8).to_be_bytes());
+        udp.extend_from_slice(&[0, 0]);
+        udp.extend_from_slice(payload);
+
+        let chunks = udp.chunks(MTU_PAYLOAD).collect::<Vec<_>>();
+        for (i, chunk) in chunks.iter().enumerate() {
+            let mut frame = vec![0xff; 12];
+            frame.extend_from_slice(&0x0800u16.to_be_bytes());
+            frame.extend_from_slice(&[0x45, 0]);
+            frame.extend_from_slice(&(chunk.len() as u16 + 20).to_be_bytes());
+            frame.extend_from_slice(&(identification as u16).to_be_bytes());
+            let more_fragments = if i + 1 < chunks.len() { 0x2000 } else { 0 };
+            frame.extend_from_slice(&(more_fragments | (i * MTU_PAYLOAD / 8) as u16).to_be_bytes());
+            frame.extend_from_slice(&[64, 17, 0, 0, 192, 168, 1, 2, 192, 168, 1, 1]);
+            frame.extend_from_slice(chunk);
+
+            out.extend_from_slice(&(timestamp.as_secs() as u32).to_le_bytes());
+            out.extend_from_slice(&timestamp.subsec_micros().to_le_bytes());
+            out.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            out.extend_from_slice(&frame);
+        }
+    }
+    out
+}
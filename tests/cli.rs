@@ -0,0 +1,134 @@
+//! Runs the command line tool on a simulated recording with embedded metadata
+
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Output},
+    time::Duration,
+};
+
+use ouster_rs_ce::{
+    DualProfile, OusterConfig, PcapNgWriter, Scene, Shape, Simulator, UdpDatagram,
+    ValidOusterConfig,
+};
+
+const METADATA: &str = include_str!("data/os-1-64_v3.0.1_1024x10.json");
+
+fn ouster(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ouster"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+/// Empty directory per test, tests run in parallel
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ouster-cli-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Three frames of a sensor inside of a sphere
+fn recording(dir: &Path) -> PathBuf {
+    let config: ValidOusterConfig<DualProfile<16, 64>> =
+        OusterConfig::from_metadata_json(METADATA.as_bytes())
+            .unwrap()
+            .try_into()
+            .unwrap();
+    let scene = Scene::default().with_object(
+        Shape::Sphere {
+            center: [0.; 3],
+            radius: 10.,
+        },
+        100,
+        5,
+    );
+    let mut simulator = Simulator::new(&config, scene).with_start_timestamp(Duration::from_secs(1));
+    let path = dir.join("recording.pcapng");
+    let mut writer = PcapNgWriter::create(&path, Some(METADATA)).unwrap();
+    for packet in (0..3).flat_map(|_| simulator.next_frame()) {
+        writer
+            .write_datagram(&UdpDatagram {
+                timestamp: Duration::ZERO,
+                source: ([192, 168, 1, 2], 40000).into(),
+                destination: ([192, 168, 1, 1], config.config_params.udp_port_lidar).into(),
+                payload: packet.as_slice().to_vec(),
+            })
+            .unwrap();
+    }
+    writer.finish().unwrap();
+    path
+}
+
+#[test]
+fn stats() {
+    let dir = test_dir("stats");
+    let pcap = recording(&dir);
+    let output = stdout(&ouster(&["stats", pcap.to_str().unwrap()]));
+    assert!(output.contains("Frames:               3\n"), "{output}");
+    assert!(
+        output.contains("Frame rate:           10.00 Hz (expected 10 Hz)\n"),
+        "{output}"
+    );
+    assert!(
+        output.contains("Packet rate:          640.0/s (expected 640.0/s)\n"),
+        "{output}"
+    );
+    assert!(output.contains("Lost packets:         0\n"), "{output}");
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn convert() {
+    let dir = test_dir("convert");
+    let pcap = recording(&dir);
+    let output = dir.join("ply");
+    let stdout = stdout(&ouster(&[
+        "convert",
+        pcap.to_str().unwrap(),
+        output.to_str().unwrap(),
+        "--format",
+        "ply",
+    ]));
+    assert!(stdout.starts_with("Wrote 3 frames"), "{stdout}");
+    let mut files = std::fs::read_dir(&output)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    files.sort();
+    assert_eq!(vec!["000000.ply", "000001.ply", "000002.ply"], files);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn validate_reports_all_problems() {
+    let dir = test_dir("validate");
+    let valid = dir.join("valid.json");
+    std::fs::write(&valid, METADATA).unwrap();
+    assert!(stdout(&ouster(&["validate", valid.to_str().unwrap()])).starts_with("Valid"));
+
+    let mut value = serde_json::from_str::<serde_json::Value>(METADATA).unwrap();
+    value["config_params"]["lidar_mode"] = "2048x10".into();
+    value["beam_intrinsics"]["beam_azimuth_angles"]
+        .as_array_mut()
+        .unwrap()
+        .pop();
+    let invalid = dir.join("invalid.json");
+    std::fs::write(&invalid, value.to_string()).unwrap();
+    let output = ouster(&["validate", invalid.to_str().unwrap()]);
+    assert!(!output.status.success());
+    let problems = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(2, problems.lines().count(), "{problems}");
+    assert!(problems.contains("columns_per_frame"), "{problems}");
+    assert!(problems.contains("beam_azimuth_angles"), "{problems}");
+    std::fs::remove_dir_all(dir).unwrap();
+}
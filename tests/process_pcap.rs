@@ -116,20 +116,15 @@ fn ouster_pcd_converter<TProfile: Profile>(
 
                 let (x, y, z) = polar_point.calc_xyz(p.distance as f32);
 
-                let x = x.min(20_000.).max(-20000.);
-                let y = y.min(20_000.).max(-20000.);
-                let z = z.min(20_000.).max(-20000.);
+                let x = x.clamp(-20_000., 20_000.);
+                let y = y.clamp(-20_000., 20_000.);
+                let z = z.clamp(-20_000., 20_000.);
                 pcd_writer.push(&PcdPoint { x, y, z })?;
 
                 //const FACTOR: f32 = 0.03;
                 //const OFFSET: f32 = -80.;
 
-                const MAX_RANGE: f32 = 6000.;
-                const MIN_RANGE: f32 = 4000.;
-
-                let val = ((p.distance as f32 - MIN_RANGE) * (255. / (MAX_RANGE - MIN_RANGE)))
-                    .min(255.)
-                    .max(0.) as u8;
+                let val = to_gray(p.distance);
                 min = min.min(val as f32);
                 max = max.max(val as f32);
                 // let col = ((polar_point.azimuth / (PI * 2.) * scan_width as f32)
@@ -141,6 +136,13 @@ fn ouster_pcd_converter<TProfile: Profile>(
                 //image[image_idx] = val;
                 image[image_idx_from_iter] = val;
             }
+            assert_eq!(
+                image,
+                complete_buf
+                    .range_image(&config)
+                    .map(|d| to_gray(*d))
+                    .into_vec()
+            );
             let mut dist = complete_buf
                 .iter_infos_primary(&config)
                 .map(|x| x.distance)
@@ -184,6 +186,13 @@ fn ouster_pcd_converter<TProfile: Profile>(
     Ok(())
 }
 
+fn to_gray(distance: u16) -> u8 {
+    const MAX_RANGE: f32 = 6000.;
+    const MIN_RANGE: f32 = 4000.;
+
+    ((distance as f32 - MIN_RANGE) * (255. / (MAX_RANGE - MIN_RANGE))).clamp(0., 255.) as u8
+}
+
 #[derive(PcdSerialize)]
 pub struct PcdPoint {
     x: f32,
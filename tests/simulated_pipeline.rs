@@ -0,0 +1,74 @@
+use ouster_rs_ce::{
+    Aggregator, CartesianIterator, DualProfile, OusterConfig, Scene, Shape, Simulator,
+    ValidOusterConfig,
+};
+
+type TestProfile = DualProfile<16, 64>;
+
+/// Runs simulated packets through the aggregator and the cartesian conversion, without a recording
+#[test]
+fn simulated_scene_to_points() -> Result<(), Box<dyn std::error::Error>> {
+    let data = std::fs::read(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/data/os-1-64_v3.0.1_1024x10.json"
+    ))?;
+    let config: ValidOusterConfig<TestProfile> =
+        serde_json::from_slice::<OusterConfig>(&data)?.try_into()?;
+
+    let scene = Scene::default()
+        .with_object(
+            Shape::Plane {
+                point: [0., 0., -1.5],
+                normal: [0., 0., 1.],
+            },
+            20,
+            5,
+        )
+        .with_object(
+            Shape::Box {
+                min: [4., -1., -1.5],
+                max: [5., 1., 0.5],
+            },
+            120,
+            10,
+        )
+        .with_object(
+            Shape::Sphere {
+                center: [0., 6., 0.],
+                radius: 1.,
+            },
+            200,
+            15,
+        );
+    let mut simulator = Simulator::new(&config, scene);
+    let mut aggregator = Aggregator::from_config(&config);
+    let cartesian = CartesianIterator::new_cheap_cloneable_from_config(&config);
+
+    let frames = simulator
+        .packets()
+        .filter_map(|packet| aggregator.put_data_value(packet))
+        .take(3)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![0, 1, 2],
+        frames.iter().map(|f| f.frame_id()).collect::<Vec<_>>()
+    );
+
+    let points = frames[0]
+        .export_points(&config, cartesian)
+        .collect::<Vec<_>>();
+    assert!(points.len() > 10_000, "{}", points.len());
+    for reflectivity in [20, 120, 200] {
+        assert!(points.iter().any(|p| p.reflectivity == reflectivity));
+    }
+    for p in points {
+        let (x, y, z) = p.xyz;
+        let on_surface = match p.reflectivity {
+            20 => (z + 1.5).abs() < 0.01,
+            120 => (3.99..=5.01).contains(&x) && y.abs() <= 1.01,
+            _ => ((x * x + (y - 6.) * (y - 6.) + z * z).sqrt() - 1.).abs() < 0.01,
+        };
+        assert!(on_surface, "{p:?}");
+    }
+    Ok(())
+}